@@ -1,5 +1,5 @@
 use ariadne::{Color, Config, Fmt, IndexType, Label, Report, ReportKind};
-use huff_ast::{Definition, IdentifiableNode, Instruction, Macro, Spanned};
+use huff_ast::{Definition, IdentifiableNode, Instruction, Macro, Span, Spanned};
 
 type InvokeChain<'src, 'ast> = Box<[(&'ast Macro<'src>, &'ast Spanned<&'src str>)]>;
 
@@ -43,6 +43,12 @@ pub enum AnalysisError<'ast, 'src> {
         def_type: &'static str,
         not_found: &'ast Spanned<&'src str>,
     },
+    /// A function, event or error referenced by name that is overloaded.
+    AmbiguousAbiReference {
+        scope: &'ast Macro<'src>,
+        reference: &'ast Spanned<&'src str>,
+        overloads: Box<[&'ast Definition<'src>]>,
+    },
     EntryPointHasArgs {
         target: &'ast Macro<'src>,
     },
@@ -64,23 +70,160 @@ pub enum AnalysisError<'ast, 'src> {
         intent: String,
         span: Spanned<()>,
     },
+    /// `__argsoffset()` in code included with `__codesize`/`__codeoffset`, which isn't followed
+    /// by the constructor arguments.
+    ArgsOffsetInIncludedCode {
+        scope: &'ast Macro<'src>,
+        span: Span,
+    },
+    /// `__argsoffset()` in code deployed by the default constructor, which isn't followed by the
+    /// constructor arguments either.
+    ArgsOffsetInDeployedCode {
+        scope: &'ast Macro<'src>,
+        span: Span,
+    },
 }
 
 impl<'ast, 'src> AnalysisError<'ast, 'src> {
-    pub fn report(&self, filename: String) -> Report<'_, (String, std::ops::Range<usize>)> {
+    /// Span most relevant to the error, `None` if it isn't tied to a location in the source.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::DefinitionNameCollision { .. } => self.spanned_definition().map(|def| def.span()),
+            Self::NoConstantToOverride { .. } | Self::EntryPointNotFound { .. } => None,
+            Self::RecursiveMacroInvocation { invocation_chain } => {
+                invocation_chain.first().map(|(_, invoke)| invoke.1)
+            }
+            Self::RecursiveCodeInclusion { linking_inclusions } => linking_inclusions
+                .last()
+                .map(|inclusion| inclusion.inclusion.1),
+            Self::LabelNotFound { not_found, .. }
+            | Self::MacroArgNotFound { not_found, .. }
+            | Self::DefinitionNotFound { not_found, .. } => Some(not_found.1),
+            Self::AmbiguousAbiReference { reference, .. } => Some(reference.1),
+            Self::EntryPointHasArgs { target } => Some(target.args.1),
+            Self::MacroArgumentCountMismatch { args, .. } => Some(args.1),
+            Self::DuplicateLabelDefinition { duplicates, .. }
+            | Self::DuplicateMacroArgDefinition { duplicates, .. } => {
+                duplicates.last().map(|dup| dup.1)
+            }
+            Self::NotYetSupported { span, .. } => Some(span.1),
+            Self::ArgsOffsetInIncludedCode { span, .. }
+            | Self::ArgsOffsetInDeployedCode { span, .. } => Some(*span),
+        }
+    }
+
+    /// Definition [`Self::span`] points into, as definitions may be included from other files
+    /// than the macros. `None` if the span points into a macro or there is none.
+    pub fn spanned_definition(&self) -> Option<&'ast Definition<'src>> {
+        match self {
+            Self::DefinitionNameCollision { collided, .. } => {
+                collided.iter().copied().max_by_key(|def| def.span().start)
+            }
+            _ => None,
+        }
+    }
+
+    /// Plain text summary of the error, without the color codes used by [`Self::report`].
+    pub fn message(&self) -> String {
+        match self {
+            Self::DefinitionNameCollision { duplicate_name, .. } => {
+                format!("Definitions with duplicate name '{}'", duplicate_name)
+            }
+            Self::NoConstantToOverride { name } => {
+                format!("Constant {} to be overriden not found", name)
+            }
+            Self::EntryPointNotFound { name } => format!("Entry point '{}' not found", name),
+            Self::RecursiveMacroInvocation { invocation_chain } => format!(
+                "Cannot expand macro {} with recursive dependency on itself",
+                invocation_chain
+                    .first()
+                    .map_or("", |(scope, _)| scope.ident())
+            ),
+            Self::RecursiveCodeInclusion { linking_inclusions } => format!(
+                "Macro {} cannot be included because it recursively includes itself",
+                linking_inclusions
+                    .last()
+                    .map_or("", |inclusion| inclusion.inclusion.ident())
+            ),
+            Self::LabelNotFound {
+                scope, not_found, ..
+            } => format!(
+                "Label '{}' not found in macro {} or its parent contexts",
+                not_found.ident(),
+                scope.ident()
+            ),
+            Self::MacroArgNotFound { scope, not_found } => format!(
+                "Reference to macro argument '{}' not found in macro {}",
+                not_found.ident(),
+                scope.ident()
+            ),
+            Self::DefinitionNotFound {
+                scope,
+                def_type,
+                not_found,
+            } => format!(
+                "Definition of {} '{}' not found in macro {}",
+                def_type,
+                not_found.ident(),
+                scope.ident()
+            ),
+            Self::AmbiguousAbiReference {
+                scope, reference, ..
+            } => format!(
+                "Reference to overloaded '{}' in macro {} is ambiguous",
+                reference.ident(),
+                scope.ident()
+            ),
+            Self::EntryPointHasArgs { target } => format!(
+                "Entry point macro '{}' is expected to have 0 arguments, found {}",
+                target.ident(),
+                target.args.0.len()
+            ),
+            Self::MacroArgumentCountMismatch { args, target, .. } => format!(
+                "Macro '{}' takes {} argument{}, invoked with {}",
+                target.ident(),
+                target.args.0.len(),
+                if target.args.0.len() == 1 { "" } else { "s" },
+                args.0.len()
+            ),
+            Self::DuplicateLabelDefinition { scope, duplicates } => format!(
+                "Duplicate label '{}' defined in '{}'",
+                duplicates.first().map_or("", |dup| dup.ident()),
+                scope.ident()
+            ),
+            Self::DuplicateMacroArgDefinition { scope, duplicates } => format!(
+                "Duplicate macro argument '{}' defined in '{}'",
+                duplicates.first().map_or("", |dup| dup.ident()),
+                scope.ident()
+            ),
+            Self::NotYetSupported { intent, .. } => format!("{} is not yet supported", intent),
+            Self::ArgsOffsetInIncludedCode { scope, .. } => format!(
+                "__argsoffset() used in '{}', included with __codesize/__codeoffset",
+                scope.ident()
+            ),
+            Self::ArgsOffsetInDeployedCode { scope, .. } => format!(
+                "__argsoffset() used in '{}', deployed by the default constructor",
+                scope.ident()
+            ),
+        }
+    }
+
+    /// Report of the error, with spans in `filename` except for those of definitions, whose file
+    /// is given by `file_of`.
+    pub fn report(
+        &self,
+        filename: String,
+        file_of: impl Fn(&Definition<'src>) -> String,
+    ) -> Report<'_, (String, std::ops::Range<usize>)> {
         match self {
             Self::DefinitionNameCollision {
                 collided,
                 duplicate_name,
             } => {
-                let first_span = collided
-                    .iter()
-                    .map(|def| def.spanned())
-                    .max_by_key(|name| name.1.start)
-                    .unwrap();
+                let first = self.spanned_definition().unwrap();
 
                 let base_report =
-                    Report::build(ReportKind::Error, filename.clone(), first_span.1.start)
+                    Report::build(ReportKind::Error, file_of(first), first.spanned().1.start)
                         .with_config(Config::default().with_index_type(IndexType::Byte))
                         .with_message(format!(
                             "Definitions with duplicate name '{}'",
@@ -89,7 +232,7 @@ impl<'ast, 'src> AnalysisError<'ast, 'src> {
 
                 base_report
                     .with_labels(collided.iter().map(|def| {
-                        Label::new((filename.clone(), def.spanned().1.into_range()))
+                        Label::new((file_of(def), def.spanned().1.into_range()))
                             .with_color(Color::Red)
                     }))
                     .with_help(format!(
@@ -216,6 +359,33 @@ impl<'ast, 'src> AnalysisError<'ast, 'src> {
                     Label::new((filename.clone(), not_found.1.into_range())).with_color(Color::Red),
                 )
                 .finish(),
+            Self::AmbiguousAbiReference {
+                scope,
+                reference,
+                overloads,
+            } => Report::build(ReportKind::Error, filename.clone(), reference.1.start)
+                .with_config(Config::default().with_index_type(IndexType::Byte))
+                .with_message(format!(
+                    "Reference to overloaded '{}' in macro {} is ambiguous",
+                    reference.ident().fg(Color::Red),
+                    scope.ident().fg(Color::Blue)
+                ))
+                .with_label(
+                    Label::new((filename.clone(), reference.1.into_range())).with_color(Color::Red),
+                )
+                .with_labels(overloads.iter().map(|def| {
+                    Label::new((file_of(def), def.span().into_range()))
+                        .with_color(Color::Yellow)
+                        .with_message(def.abi_signature().unwrap_or_default())
+                }))
+                .with_help(format!(
+                    "Reference one of the overloads by its signature instead, e.g. \"{}\"",
+                    overloads
+                        .first()
+                        .and_then(|def| def.abi_signature())
+                        .unwrap_or_default()
+                ))
+                .finish(),
             Self::LabelNotFound {
                 scope,
                 invocation_chain,
@@ -378,6 +548,34 @@ impl<'ast, 'src> AnalysisError<'ast, 'src> {
                     )
                     .finish()
             }
+            Self::ArgsOffsetInIncludedCode { scope, span } => {
+                Report::build(ReportKind::Error, filename.clone(), span.start)
+                    .with_config(Config::default().with_index_type(IndexType::Byte))
+                    .with_message(format!(
+                        "__argsoffset() used in '{}', included with __codesize/__codeoffset",
+                        scope.ident().fg(Color::Blue)
+                    ))
+                    .with_label(
+                        Label::new((filename.clone(), span.into_range())).with_color(Color::Red),
+                    )
+                    .with_help(
+                        "Only the entry point's code is followed by the constructor arguments",
+                    )
+                    .finish()
+            }
+            Self::ArgsOffsetInDeployedCode { scope, span } => {
+                Report::build(ReportKind::Error, filename.clone(), span.start)
+                    .with_config(Config::default().with_index_type(IndexType::Byte))
+                    .with_message(format!(
+                        "__argsoffset() used in '{}', deployed by the default constructor",
+                        scope.ident().fg(Color::Blue)
+                    ))
+                    .with_label(
+                        Label::new((filename.clone(), span.into_range())).with_color(Color::Red),
+                    )
+                    .with_help("Constructor arguments are only available to a custom constructor")
+                    .finish()
+            }
             Self::RecursiveCodeInclusion { linking_inclusions } => {
                 let recursing_inclusion = linking_inclusions.last().unwrap().inclusion;
                 let recursing_name = recursing_inclusion.ident();
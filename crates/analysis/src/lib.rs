@@ -5,8 +5,11 @@ pub mod label_stack;
 use crate::const_overrides::ConstantOverride;
 use crate::errors::{AnalysisError, Inclusion};
 use crate::label_stack::LabelStack;
-use huff_ast::{Definition, IdentifiableNode, Instruction, Invoke, Macro, MacroStatement, Spanned};
-use std::collections::BTreeMap;
+use huff_ast::{
+    Decorator, Definition, IdentifiableNode, Instruction, Invoke, Macro, MacroStatement, Spanned,
+    Test, WordRef,
+};
+use std::collections::{BTreeMap, BTreeSet};
 
 pub fn analyze_global_for_dups<'src, 'ast: 'src, E: FnMut(AnalysisError<'ast, 'src>)>(
     global_defs: &BTreeMap<&'src str, Vec<&'ast Definition<'src>>>,
@@ -17,6 +20,8 @@ pub fn analyze_global_for_dups<'src, 'ast: 'src, E: FnMut(AnalysisError<'ast, 's
         .filter_map(|(name, found_defs)| match found_defs.as_slice() {
             [] => None,
             [found_def] => Some((*name, *found_def)),
+            // Overloads are referenced by their signature rather than their name.
+            many_defs if are_overloads(many_defs) => None,
             many_defs => {
                 emit_error(AnalysisError::DefinitionNameCollision {
                     collided: many_defs.to_vec().into_boxed_slice(),
@@ -28,6 +33,30 @@ pub fn analyze_global_for_dups<'src, 'ast: 'src, E: FnMut(AnalysisError<'ast, 's
         .collect()
 }
 
+/// Whether the definitions are functions, events or errors of the same kind with distinct
+/// signatures.
+fn are_overloads(defs: &[&Definition]) -> bool {
+    let same_kind = defs.windows(2).all(|pair| {
+        matches!(
+            pair,
+            [Definition::SolFunction(_), Definition::SolFunction(_)]
+                | [Definition::SolEvent(_), Definition::SolEvent(_)]
+                | [Definition::SolError(_), Definition::SolError(_)]
+        )
+    });
+    let signatures: Option<BTreeSet<_>> = defs.iter().map(|def| def.abi_signature()).collect();
+    same_kind && signatures.is_some_and(|signatures| signatures.len() == defs.len())
+}
+
+/// Overloads of the function, event or error named by an ABI builtin or decorator.
+fn overloads<'src, 'ast: 'src>(
+    global_defs: &BTreeMap<&'src str, Vec<&'ast Definition<'src>>>,
+    name: &'src str,
+) -> Option<Box<[&'ast Definition<'src>]>> {
+    let defs = global_defs.get(name)?;
+    (defs.len() > 1 && are_overloads(defs)).then(|| defs.clone().into_boxed_slice())
+}
+
 pub fn verify_constants_to_be_overriden_defined<'src, 'ast: 'src, E>(
     global_defs: &BTreeMap<&'src str, Vec<&'ast Definition<'src>>>,
     overrides: &'ast [ConstantOverride],
@@ -58,6 +87,7 @@ fn get_macro_def<'src, 'ast: 'src>(
     let possible_defs = global_defs.get(name)?;
     possible_defs.iter().find_map(|def| match def {
         Definition::Macro(entry_point) => Some(entry_point),
+        Definition::Test(test) => Some(&test.r#macro),
         _ => None,
     })
 }
@@ -86,6 +116,20 @@ pub fn analyze_entry_point<'src, 'ast: 'src, E: FnMut(AnalysisError<'ast, 'src>)
         });
     }
 
+    let test = global_defs[entry_point_name]
+        .iter()
+        .find_map(|def| match def {
+            Definition::Test(test) => Some(test),
+            _ => None,
+        });
+    if let Some(test) = test {
+        analyze_decorators(global_defs, test, &mut emit_error);
+    }
+
+    // Frames are pushed while analyzing, the last one is the entry point's until then.
+    let in_included_code = macros_to_include
+        .last()
+        .is_some_and(|frame| !frame.linking_inclusions.is_empty());
     MacroAnalysis::run(
         global_defs,
         entry_point,
@@ -93,9 +137,64 @@ pub fn analyze_entry_point<'src, 'ast: 'src, E: FnMut(AnalysisError<'ast, 'src>)
         &mut invoke_stack,
         &mut emit_error,
         macros_to_include,
+        in_included_code,
     );
 }
 
+/// Analyzes the entry point as well as all macros it includes via `__codesize`/`__codeoffset`.
+pub fn analyze_entry_point_with_inclusions<'src, 'ast: 'src, E>(
+    global_defs: &BTreeMap<&'src str, Vec<&'ast Definition<'src>>>,
+    entry_point_name: &'src str,
+    emit_error: E,
+) where
+    E: FnMut(AnalysisError<'ast, 'src>),
+{
+    analyze_inclusions(
+        global_defs,
+        CodeInclusionFrame::top(entry_point_name),
+        emit_error,
+    );
+}
+
+/// Analyzes an entry point deployed by the default constructor, whose code isn't followed by the
+/// constructor arguments, as well as all macros it includes.
+pub fn analyze_deployed_entry_point_with_inclusions<'src, 'ast: 'src, E>(
+    global_defs: &BTreeMap<&'src str, Vec<&'ast Definition<'src>>>,
+    entry_point_name: &'src str,
+    emit_error: E,
+) where
+    E: FnMut(AnalysisError<'ast, 'src>),
+{
+    let top = CodeInclusionFrame {
+        deployed: true,
+        ..CodeInclusionFrame::top(entry_point_name)
+    };
+    analyze_inclusions(global_defs, top, emit_error);
+}
+
+fn analyze_inclusions<'src, 'ast: 'src, E>(
+    global_defs: &BTreeMap<&'src str, Vec<&'ast Definition<'src>>>,
+    top: CodeInclusionFrame<'src, 'ast>,
+    mut emit_error: E,
+) where
+    E: FnMut(AnalysisError<'ast, 'src>),
+{
+    let mut to_analyze_stack = vec![top];
+    let mut analyzed_macros = BTreeSet::new();
+    while let Some(next_entrypoint) = to_analyze_stack.last() {
+        let idx_to_remove = to_analyze_stack.len() - 1;
+        if analyzed_macros.insert(next_entrypoint.name) {
+            analyze_entry_point(
+                global_defs,
+                next_entrypoint.name,
+                &mut emit_error,
+                &mut to_analyze_stack,
+            );
+        }
+        to_analyze_stack.remove(idx_to_remove);
+    }
+}
+
 macro_rules! global_exists {
     ($global_defs:expr, $ident:expr, $pattern:pat) => {
         $global_defs.get($ident).map_or(false, |defs| {
@@ -107,10 +206,56 @@ macro_rules! global_exists {
     };
 }
 
+fn analyze_decorators<'src, 'ast: 'src, E: FnMut(AnalysisError<'ast, 'src>)>(
+    global_defs: &BTreeMap<&'src str, Vec<&'ast Definition<'src>>>,
+    test: &'ast Test<'src>,
+    emit_error: &mut E,
+) {
+    for (decorator, _) in test.decorators.iter() {
+        match decorator {
+            Decorator::Storage { slot, value } => {
+                for word in [slot, value] {
+                    let WordRef::Constant(const_ref) = word else {
+                        continue;
+                    };
+                    if !global_exists!(global_defs, const_ref.ident(), Definition::Constant(_)) {
+                        emit_error(AnalysisError::DefinitionNotFound {
+                            scope: &test.r#macro,
+                            def_type: "constant",
+                            not_found: const_ref,
+                        });
+                    }
+                }
+            }
+            Decorator::ExpectRevert(Some(error_ref)) => {
+                if !global_exists!(global_defs, error_ref.ident(), Definition::SolError(_)) {
+                    emit_error(AnalysisError::DefinitionNotFound {
+                        scope: &test.r#macro,
+                        def_type: "solidity error",
+                        not_found: error_ref,
+                    });
+                } else if let Some(overloads) = overloads(global_defs, error_ref.ident()) {
+                    emit_error(AnalysisError::AmbiguousAbiReference {
+                        scope: &test.r#macro,
+                        reference: error_ref,
+                        overloads,
+                    });
+                }
+            }
+            Decorator::Calldata(_)
+            | Decorator::Value(_)
+            | Decorator::Caller(_)
+            | Decorator::ExpectRevert(None) => {}
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CodeInclusionFrame<'src, 'ast: 'src> {
     pub name: &'src str,
     linking_inclusions: Vec<Inclusion<'src, 'ast>>,
+    /// Whether the code is deployed by the default constructor rather than being the initcode.
+    deployed: bool,
 }
 
 impl<'src> CodeInclusionFrame<'src, '_> {
@@ -118,6 +263,7 @@ impl<'src> CodeInclusionFrame<'src, '_> {
         Self {
             name,
             linking_inclusions: vec![],
+            deployed: false,
         }
     }
 
@@ -136,6 +282,9 @@ struct MacroAnalysis<'a, 'src, 'ast: 'src, E: FnMut(AnalysisError<'ast, 'src>)>
     invoke_stack: &'a mut Vec<(&'ast Macro<'src>, &'ast Spanned<&'src str>)>,
     emit_error: &'a mut E,
     macros_to_include: &'a mut Vec<CodeInclusionFrame<'src, 'ast>>,
+    /// Whether the macro is part of code included with `__codesize`/`__codeoffset` rather than of
+    /// the entry point's code.
+    in_included_code: bool,
 }
 
 impl<'a, 'src, 'ast: 'src, E: FnMut(AnalysisError<'ast, 'src>)> MacroAnalysis<'a, 'src, 'ast, E> {
@@ -150,6 +299,7 @@ impl<'a, 'src, 'ast: 'src, E: FnMut(AnalysisError<'ast, 'src>)> MacroAnalysis<'a
         invoke_stack: &'a mut Vec<(&'ast Macro<'src>, &'ast Spanned<&'src str>)>,
         emit_error: &'a mut E,
         macros_to_include: &mut Vec<CodeInclusionFrame<'src, 'ast>>,
+        in_included_code: bool,
     ) {
         MacroAnalysis {
             global_defs,
@@ -158,6 +308,7 @@ impl<'a, 'src, 'ast: 'src, E: FnMut(AnalysisError<'ast, 'src>)> MacroAnalysis<'a
             invoke_stack,
             emit_error,
             macros_to_include,
+            in_included_code,
         }
         .analyze();
     }
@@ -266,6 +417,7 @@ impl<'a, 'src, 'ast: 'src, E: FnMut(AnalysisError<'ast, 'src>)> MacroAnalysis<'a
                                 self.invoke_stack,
                                 self.emit_error,
                                 self.macros_to_include,
+                                self.in_included_code,
                             );
                         });
                     self.invoke_stack.pop().unwrap();
@@ -332,6 +484,7 @@ impl<'a, 'src, 'ast: 'src, E: FnMut(AnalysisError<'ast, 'src>)> MacroAnalysis<'a
                         self.macros_to_include.push(CodeInclusionFrame {
                             name: code_ref.ident(),
                             linking_inclusions,
+                            deployed: false,
                         });
                     }
                 }
@@ -342,8 +495,28 @@ impl<'a, 'src, 'ast: 'src, E: FnMut(AnalysisError<'ast, 'src>)> MacroAnalysis<'a
                             def_type: "ABI function",
                             not_found: func,
                         })
+                    } else if let Some(overloads) = overloads(self.global_defs, func.ident()) {
+                        self.emit(AnalysisError::AmbiguousAbiReference {
+                            scope: self.m,
+                            reference: func,
+                            overloads,
+                        })
                     }
                 }
+                Invoke::BuiltinArgsOffset(span) => {
+                    if self.in_included_code {
+                        self.emit(AnalysisError::ArgsOffsetInIncludedCode {
+                            scope: self.m,
+                            span: *span,
+                        })
+                    } else if self.current_frame().deployed {
+                        self.emit(AnalysisError::ArgsOffsetInDeployedCode {
+                            scope: self.m,
+                            span: *span,
+                        })
+                    }
+                }
+                Invoke::BuiltinFuncSigLiteral(_) | Invoke::BuiltinEventHashLiteral(_) => {}
                 Invoke::BuiltinError(func_or_error_ref) => {
                     if !global_exists!(
                         self.global_defs,
@@ -370,12 +543,13 @@ impl<'a, 'src, 'ast: 'src, E: FnMut(AnalysisError<'ast, 'src>)> MacroAnalysis<'a
                             def_type: "solidity event",
                             not_found: event_ref,
                         })
+                    } else if let Some(overloads) = overloads(self.global_defs, event_ref.ident()) {
+                        self.emit(AnalysisError::AmbiguousAbiReference {
+                            scope: self.m,
+                            reference: event_ref,
+                            overloads,
+                        })
                     }
-
-                    self.emit(AnalysisError::NotYetSupported {
-                        intent: "__EVENT_HASH".to_string(),
-                        span: ((), event_ref.1),
-                    });
                 }
             },
         });
@@ -448,6 +622,7 @@ fn in_ident_map<'ast, 'src, N: IdentifiableNode<'src>>(
 #[cfg(test)]
 mod test {
     use super::*;
+    use alloy_primitives::U256;
     use chumsky::prelude::*;
     use huff_ast::*;
 
@@ -672,4 +847,206 @@ mod test {
 
         emits_analysis_error([&m1, &m2], "MAIN", []);
     }
+
+    #[test]
+    fn test_is_entry_point_but_not_invocable() {
+        let span = SimpleSpan::new(0, 0);
+        let invoke_span = SimpleSpan::new(3, 12);
+
+        let inner_test = Macro {
+            name: ("TEST_OTHER", span),
+            args: (Box::new([]), span),
+            takes_returns: None,
+            body: Box::new([MacroStatement::Invoke(Invoke::Macro {
+                name: ("OTHER", span),
+                args: (Box::new([]), span),
+            })]),
+        };
+        let t = Definition::Test(Test {
+            decorators: Box::new([]),
+            r#macro: inner_test,
+            fuzz: None,
+        });
+        let inner_macro = Macro {
+            name: ("OTHER", span),
+            args: (Box::new([]), span),
+            takes_returns: None,
+            body: Box::new([MacroStatement::Invoke(Invoke::Macro {
+                name: ("TEST_OTHER", invoke_span),
+                args: (Box::new([]), span),
+            })]),
+        };
+        let m = Definition::Macro(inner_macro.clone());
+
+        emits_analysis_error(
+            [&t, &m],
+            "TEST_OTHER",
+            [AnalysisError::DefinitionNotFound {
+                scope: &inner_macro,
+                def_type: "macro",
+                not_found: &("TEST_OTHER", invoke_span),
+            }],
+        );
+    }
+
+    #[test]
+    fn decorator_refs_not_found() {
+        let span = SimpleSpan::new(0, 0);
+        let slot_span = SimpleSpan::new(2, 6);
+        let error_span = SimpleSpan::new(8, 12);
+
+        let t = Definition::Test(Test {
+            decorators: Box::new([
+                (
+                    Decorator::Storage {
+                        slot: WordRef::Constant(("SLOT", slot_span)),
+                        value: WordRef::Literal((U256::from(1), span)),
+                    },
+                    span,
+                ),
+                (
+                    Decorator::ExpectRevert(Some(("Unauthorized", error_span))),
+                    span,
+                ),
+            ]),
+            r#macro: Macro {
+                name: ("TEST", span),
+                args: (Box::new([]), span),
+                takes_returns: None,
+                body: Box::new([]),
+            },
+            fuzz: None,
+        });
+        let Definition::Test(test) = &t else {
+            unreachable!()
+        };
+
+        emits_analysis_error(
+            [&t],
+            "TEST",
+            [
+                AnalysisError::DefinitionNotFound {
+                    scope: &test.r#macro,
+                    def_type: "constant",
+                    not_found: &("SLOT", slot_span),
+                },
+                AnalysisError::DefinitionNotFound {
+                    scope: &test.r#macro,
+                    def_type: "solidity error",
+                    not_found: &("Unauthorized", error_span),
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn overloaded_functions_are_referenced_by_signature() {
+        let root = parse(
+            "#define function transfer(address,uint256) nonpayable returns (bool)
+            #define function transfer(address) nonpayable returns (bool)
+            #define event Transfer(address)
+            #define event Transfer(address)
+            #define macro MAIN() = {
+                __FUNC_SIG(transfer)
+                __FUNC_SIG(\"transfer(address)\")
+            }",
+        )
+        .unwrap();
+        let defs: Vec<_> = root
+            .0
+            .iter()
+            .filter_map(|section| match section {
+                RootSection::Definition(def) => Some(def),
+                _ => None,
+            })
+            .collect();
+        let global_defs = build_ident_map(defs.iter().copied());
+
+        let mut emitted = vec![];
+        let unique_defs = analyze_global_for_dups(&global_defs, |err| emitted.push(err));
+        assert!(!unique_defs.contains_key("transfer"));
+        assert_eq!(
+            emitted,
+            vec![AnalysisError::DefinitionNameCollision {
+                collided: Box::new([defs[2], defs[3]]),
+                duplicate_name: "Transfer"
+            }]
+        );
+
+        let Definition::Macro(main) = defs[4] else {
+            unreachable!()
+        };
+        let MacroStatement::Invoke(Invoke::BuiltinFuncSig(reference)) = &main.body[0] else {
+            unreachable!()
+        };
+        emits_analysis_error(
+            [defs[0], defs[1], defs[4]],
+            "MAIN",
+            [AnalysisError::AmbiguousAbiReference {
+                scope: main,
+                reference,
+                overloads: Box::new([defs[0], defs[1]]),
+            }],
+        );
+    }
+
+    #[test]
+    fn args_offset_only_in_entry_point_code() {
+        let root = parse(
+            "#define macro RUNTIME() = { __argsoffset() }
+            #define macro CONSTRUCTOR() = { __codesize(RUNTIME) __argsoffset() }",
+        )
+        .unwrap();
+        let defs: Vec<_> = root
+            .0
+            .iter()
+            .filter_map(|section| match section {
+                RootSection::Definition(def) => Some(def),
+                _ => None,
+            })
+            .collect();
+        let Definition::Macro(runtime) = defs[0] else {
+            unreachable!()
+        };
+        let MacroStatement::Invoke(Invoke::BuiltinArgsOffset(span)) = runtime.body[0] else {
+            unreachable!()
+        };
+
+        let mut emitted = vec![];
+        analyze_entry_point_with_inclusions(
+            &build_ident_map(defs.iter().copied()),
+            "CONSTRUCTOR",
+            |err| emitted.push(err),
+        );
+        assert_eq!(
+            emitted,
+            vec![AnalysisError::ArgsOffsetInIncludedCode {
+                scope: runtime,
+                span
+            }]
+        );
+    }
+    #[test]
+    fn args_offset_not_in_deployed_code() {
+        let root = parse("#define macro MAIN() = { __argsoffset() }").unwrap();
+        let RootSection::Definition(def) = &root.0[0] else {
+            unreachable!()
+        };
+        let Definition::Macro(main) = def else {
+            unreachable!()
+        };
+        let MacroStatement::Invoke(Invoke::BuiltinArgsOffset(span)) = main.body[0] else {
+            unreachable!()
+        };
+        let global_defs = build_ident_map([def].into_iter());
+
+        let mut emitted = vec![];
+        analyze_entry_point_with_inclusions(&global_defs, "MAIN", |err| emitted.push(err));
+        assert_eq!(emitted, vec![]);
+        analyze_deployed_entry_point_with_inclusions(&global_defs, "MAIN", |err| emitted.push(err));
+        assert_eq!(
+            emitted,
+            vec![AnalysisError::ArgsOffsetInDeployedCode { scope: main, span }]
+        );
+    }
 }
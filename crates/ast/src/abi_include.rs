@@ -0,0 +1,874 @@
+use crate::{
+    ast::{Definition, SolError, SolEvent, SolFunction, SolParam, StateMutability},
+    lexer::Token,
+    Span, Spanned,
+};
+use alloy_dyn_abi::DynSolType;
+use chumsky::{error::Rich, span::SimpleSpan};
+use std::collections::{BTreeMap, BTreeSet};
+
+type Error<'src> = Rich<'src, Token<'src>>;
+
+fn error<'src>(span: Span, msg: impl ToString) -> Error<'src> {
+    Rich::custom(span, msg.to_string())
+}
+
+/// Parse the functions, events and errors of an ABI included with `#include_abi`, a Solidity
+/// interface if the path ends in `.sol`, a JSON ABI otherwise. The spans of the definitions point
+/// into the included source.
+pub fn parse_abi_include<'src>(
+    path: &str,
+    src: &'src str,
+) -> Result<Vec<Definition<'src>>, Error<'src>> {
+    if path.ends_with(".sol") {
+        SolParser::new(src)?.definitions()
+    } else {
+        json_definitions(src)
+    }
+}
+
+#[derive(Debug)]
+enum Json<'src> {
+    Null,
+    Bool(bool),
+    Number,
+    /// The string without its quotes, escapes are kept as is.
+    String(&'src str),
+    Array(Vec<Spanned<Json<'src>>>),
+    Object(Vec<(&'src str, Spanned<Json<'src>>)>),
+}
+
+impl<'src> Json<'src> {
+    fn get(&self, key: &str) -> Option<&Spanned<Json<'src>>> {
+        match self {
+            Self::Object(members) => members
+                .iter()
+                .find_map(|(name, value)| (*name == key).then_some(value)),
+            _ => None,
+        }
+    }
+}
+
+/// Parses JSON, keeping the spans of all values.
+struct JsonParser<'src> {
+    src: &'src str,
+    pos: usize,
+}
+
+impl<'src> JsonParser<'src> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\n' | '\r' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn unexpected(&self) -> Error<'src> {
+        let end = self.pos + self.peek().map_or(0, char::len_utf8);
+        match self.peek() {
+            Some(c) => error(
+                SimpleSpan::new(self.pos, end),
+                format!("unexpected '{}'", c),
+            ),
+            None => error(SimpleSpan::new(self.pos, end), "unexpected end of input"),
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error<'src>> {
+        self.skip_whitespace();
+        if self.peek() != Some(c) {
+            return Err(self.unexpected());
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn document(&mut self) -> Result<Spanned<Json<'src>>, Error<'src>> {
+        let value = self.value()?;
+        self.skip_whitespace();
+        match self.peek() {
+            Some(_) => Err(self.unexpected()),
+            None => Ok(value),
+        }
+    }
+
+    fn value(&mut self) -> Result<Spanned<Json<'src>>, Error<'src>> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let value = match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                let mut members = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        let (key, _) = self.string()?;
+                        self.expect(':')?;
+                        members.push((key, self.value()?));
+                        if !self.separator('}')? {
+                            break;
+                        }
+                    }
+                }
+                Json::Object(members)
+            }
+            Some('[') => {
+                self.pos += 1;
+                let mut elements = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        elements.push(self.value()?);
+                        if !self.separator(']')? {
+                            break;
+                        }
+                    }
+                }
+                Json::Array(elements)
+            }
+            Some('"') => Json::String(self.string()?.0),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || "+-.".contains(c))
+                {
+                    self.pos += 1;
+                }
+                Json::Number
+            }
+            _ => {
+                let rest = &self.src[self.pos..];
+                let (literal, value) = [
+                    ("true", Json::Bool(true)),
+                    ("false", Json::Bool(false)),
+                    ("null", Json::Null),
+                ]
+                .into_iter()
+                .find(|(literal, _)| rest.starts_with(literal))
+                .ok_or_else(|| self.unexpected())?;
+                self.pos += literal.len();
+                value
+            }
+        };
+        Ok((value, SimpleSpan::new(start, self.pos)))
+    }
+
+    /// Consumes a `,` or the closing delimiter, returning whether more elements follow.
+    fn separator(&mut self, close: char) -> Result<bool, Error<'src>> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(',') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            Some(c) if c == close => {
+                self.pos += 1;
+                Ok(false)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn string(&mut self) -> Result<Spanned<&'src str>, Error<'src>> {
+        self.skip_whitespace();
+        if self.peek() != Some('"') {
+            return Err(self.unexpected());
+        }
+        let start = self.pos + 1;
+        let mut escaped = false;
+        for (idx, c) in self.src[start..].char_indices() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                self.pos = start + idx + 1;
+                return Ok((
+                    &self.src[start..start + idx],
+                    SimpleSpan::new(start, start + idx),
+                ));
+            }
+        }
+        Err(error(
+            SimpleSpan::new(self.pos, self.src.len()),
+            "unterminated string",
+        ))
+    }
+}
+
+fn string_field<'src>(
+    object: &Spanned<Json<'src>>,
+    key: &str,
+) -> Result<Option<Spanned<&'src str>>, Error<'src>> {
+    match object.0.get(key) {
+        // Without the quotes.
+        Some((Json::String(value), span)) => Ok(Some((
+            *value,
+            SimpleSpan::new(span.start + 1, span.end - 1),
+        ))),
+        Some((_, span)) => Err(error(*span, format!("expected `{}` to be a string", key))),
+        None => Ok(None),
+    }
+}
+
+fn required_string_field<'src>(
+    object: &Spanned<Json<'src>>,
+    key: &str,
+) -> Result<Spanned<&'src str>, Error<'src>> {
+    string_field(object, key)?.ok_or_else(|| error(object.1, format!("missing `{}`", key)))
+}
+
+fn json_definitions(src: &str) -> Result<Vec<Definition<'_>>, Error<'_>> {
+    let root = JsonParser { src, pos: 0 }.document()?;
+    // Artifacts of Foundry and Hardhat hold the ABI in their `abi` field.
+    let abi = root.0.get("abi").unwrap_or(&root);
+    let Json::Array(entries) = &abi.0 else {
+        return Err(error(abi.1, "expected a JSON ABI array"));
+    };
+    entries
+        .iter()
+        .filter_map(|entry| json_definition(entry).transpose())
+        .collect()
+}
+
+/// The function, event or error of an ABI entry, `None` for constructors, fallback and receive
+/// functions.
+fn json_definition<'src>(
+    entry: &Spanned<Json<'src>>,
+) -> Result<Option<Definition<'src>>, Error<'src>> {
+    let kind = string_field(entry, "type")?.map_or("function", |(kind, _)| kind);
+    let definition = match kind {
+        "function" => Definition::SolFunction(SolFunction {
+            name: required_string_field(entry, "name")?,
+            args: json_params(entry, "inputs", false)?,
+            rets: json_params(entry, "outputs", false)?,
+            state_mutability: match string_field(entry, "stateMutability")? {
+                None | Some(("nonpayable", _)) => StateMutability::NonPayable,
+                Some(("payable", _)) => StateMutability::Payable,
+                Some(("view", _)) => StateMutability::View,
+                Some(("pure", _)) => StateMutability::Pure,
+                Some((other, span)) => {
+                    return Err(error(span, format!("unknown state mutability `{}`", other)))
+                }
+            },
+        }),
+        "event" => Definition::SolEvent(SolEvent {
+            name: required_string_field(entry, "name")?,
+            args: json_params(entry, "inputs", true)?,
+            anonymous: matches!(entry.0.get("anonymous"), Some((Json::Bool(true), _))),
+        }),
+        "error" => Definition::SolError(SolError {
+            name: required_string_field(entry, "name")?,
+            args: json_params(entry, "inputs", false)?,
+        }),
+        "constructor" | "fallback" | "receive" => return Ok(None),
+        other => {
+            let span = required_string_field(entry, "type")?.1;
+            return Err(error(span, format!("unknown ABI entry type `{}`", other)));
+        }
+    };
+    Ok(Some(definition))
+}
+
+fn json_params<'src>(
+    entry: &Spanned<Json<'src>>,
+    key: &str,
+    indexable: bool,
+) -> Result<Box<[SolParam<'src>]>, Error<'src>> {
+    let params = match entry.0.get(key) {
+        Some((Json::Array(params), _)) => params,
+        Some((_, span)) => return Err(error(*span, format!("expected `{}` to be an array", key))),
+        None => return Ok(Box::new([])),
+    };
+    params
+        .iter()
+        .map(|param| {
+            let ty_span = required_string_field(param, "type")?.1;
+            let ty = DynSolType::parse(&json_type(param)?).map_err(|err| error(ty_span, err))?;
+            Ok(SolParam {
+                ty: (ty, ty_span),
+                name: string_field(param, "name")?.filter(|(name, _)| !name.is_empty()),
+                indexed: indexable && matches!(param.0.get("indexed"), Some((Json::Bool(true), _))),
+            })
+        })
+        .collect()
+}
+
+/// Canonical type of a parameter, tuples being spelled out from their `components`.
+fn json_type<'src>(param: &Spanned<Json<'src>>) -> Result<String, Error<'src>> {
+    let (ty, span) = required_string_field(param, "type")?;
+    let Some(suffix) = ty.strip_prefix("tuple") else {
+        return Ok(ty.to_owned());
+    };
+    let Some((Json::Array(components), _)) = param.0.get("components") else {
+        return Err(error(span, "missing `components` of tuple"));
+    };
+    let components = components
+        .iter()
+        .map(json_type)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(format!("({}){}", components.join(","), suffix))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SolToken<'src> {
+    Ident(&'src str),
+    Punct(char),
+    /// A number or string literal.
+    Literal(&'src str),
+}
+
+fn sol_tokens(src: &str) -> Result<Vec<Spanned<SolToken<'_>>>, Error<'_>> {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '$';
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while let Some(c) = src[pos..].chars().next() {
+        let rest = &src[pos..];
+        let len = if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        } else if rest.starts_with("//") {
+            pos += rest.find('\n').unwrap_or(rest.len());
+            continue;
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let Some(end) = comment.find("*/") else {
+                return Err(error(
+                    SimpleSpan::new(pos, src.len()),
+                    "unterminated comment",
+                ));
+            };
+            pos += end + 4;
+            continue;
+        } else if c == '"' || c == '\'' {
+            let Some(end) = rest[1..].find(c) else {
+                return Err(error(
+                    SimpleSpan::new(pos, src.len()),
+                    "unterminated string",
+                ));
+            };
+            end + 2
+        } else if is_ident(c) {
+            rest.find(|c: char| !is_ident(c)).unwrap_or(rest.len())
+        } else {
+            c.len_utf8()
+        };
+        let text = &rest[..len];
+        let token = if c == '"' || c == '\'' || c.is_ascii_digit() {
+            SolToken::Literal(text)
+        } else if is_ident(c) {
+            SolToken::Ident(text)
+        } else {
+            SolToken::Punct(c)
+        };
+        tokens.push((token, SimpleSpan::new(pos, pos + len)));
+        pos += len;
+    }
+    Ok(tokens)
+}
+
+/// Type of a Solidity parameter as written, struct types are resolved once all are known.
+#[derive(Debug)]
+struct SolRawType<'src> {
+    /// Name of the type, the last component of qualified names like `IPool.Position`.
+    name: &'src str,
+    /// Array suffixes, e.g. `[][2]`.
+    suffix: String,
+    span: Span,
+}
+
+#[derive(Debug)]
+struct SolRawParam<'src> {
+    ty: SolRawType<'src>,
+    name: Option<Spanned<&'src str>>,
+    indexed: bool,
+}
+
+#[derive(Debug)]
+enum SolItem<'src> {
+    Function {
+        name: Spanned<&'src str>,
+        args: Vec<SolRawParam<'src>>,
+        rets: Vec<SolRawParam<'src>>,
+        state_mutability: StateMutability,
+    },
+    Event {
+        name: Spanned<&'src str>,
+        args: Vec<SolRawParam<'src>>,
+        anonymous: bool,
+    },
+    Error {
+        name: Spanned<&'src str>,
+        args: Vec<SolRawParam<'src>>,
+    },
+}
+
+/// Parses the external functions, events and errors of Solidity interfaces and contracts. Function
+/// bodies and other declarations are skipped.
+struct SolParser<'src> {
+    tokens: Vec<Spanned<SolToken<'src>>>,
+    pos: usize,
+    eoi: Span,
+    items: Vec<SolItem<'src>>,
+    structs: BTreeMap<&'src str, Vec<SolRawType<'src>>>,
+    /// Enums, encoded as `uint8`.
+    enums: BTreeSet<&'src str>,
+    /// Contracts and interfaces, encoded as `address`.
+    contracts: BTreeSet<&'src str>,
+}
+
+impl<'src> SolParser<'src> {
+    fn new(src: &'src str) -> Result<Self, Error<'src>> {
+        Ok(Self {
+            tokens: sol_tokens(src)?,
+            pos: 0,
+            eoi: SimpleSpan::new(src.len(), src.len()),
+            items: Vec::new(),
+            structs: BTreeMap::new(),
+            enums: BTreeSet::new(),
+            contracts: BTreeSet::new(),
+        })
+    }
+
+    fn peek(&self) -> Option<SolToken<'src>> {
+        self.tokens.get(self.pos).map(|(token, _)| *token)
+    }
+
+    fn span(&self) -> Span {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.eoi, |(_, span)| *span)
+    }
+
+    /// End of the last consumed token.
+    fn last_end(&self) -> usize {
+        self.pos
+            .checked_sub(1)
+            .map_or(0, |last| self.tokens[last].1.end)
+    }
+
+    fn eat(&mut self, token: SolToken) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error<'src>> {
+        if !self.eat(SolToken::Punct(c)) {
+            return Err(error(self.span(), format!("expected '{}'", c)));
+        }
+        Ok(())
+    }
+
+    fn ident(&mut self) -> Result<Spanned<&'src str>, Error<'src>> {
+        match self.tokens.get(self.pos) {
+            Some(&(SolToken::Ident(ident), span)) => {
+                self.pos += 1;
+                Ok((ident, span))
+            }
+            _ => Err(error(self.span(), "expected identifier")),
+        }
+    }
+
+    /// Skips a declaration up to its `;` or the end of its `{ ... }` body.
+    fn skip_declaration(&mut self) -> Result<(), Error<'src>> {
+        let mut depth = 0usize;
+        while let Some(token) = self.peek() {
+            self.pos += 1;
+            match token {
+                SolToken::Punct('(' | '[' | '{') => depth += 1,
+                SolToken::Punct(')' | ']') => depth = depth.saturating_sub(1),
+                SolToken::Punct('}') => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                SolToken::Punct(';') if depth == 0 => return Ok(()),
+                _ => {}
+            }
+        }
+        Err(error(self.eoi, "unexpected end of input"))
+    }
+
+    /// Skips a bracketed group, e.g. `(A, B)`.
+    fn skip_group(&mut self) -> Result<(), Error<'src>> {
+        let mut depth = 0usize;
+        while let Some(token) = self.peek() {
+            self.pos += 1;
+            match token {
+                SolToken::Punct('(' | '[' | '{') => depth += 1,
+                SolToken::Punct(')' | ']' | '}') => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(error(self.eoi, "unexpected end of input"))
+    }
+
+    fn source_unit(&mut self) -> Result<(), Error<'src>> {
+        while let Some(token) = self.peek() {
+            match token {
+                SolToken::Ident("abstract" | "contract" | "interface" | "library") => {
+                    self.eat(SolToken::Ident("abstract"));
+                    self.pos += 1;
+                    let (name, _) = self.ident()?;
+                    self.contracts.insert(name);
+                    // Skips the inherited contracts.
+                    while !matches!(self.peek(), Some(SolToken::Punct('{')) | None) {
+                        self.pos += 1;
+                    }
+                    self.expect('{')?;
+                    while !self.eat(SolToken::Punct('}')) {
+                        if self.peek().is_none() {
+                            return Err(error(self.eoi, "expected '}'"));
+                        }
+                        self.member(true)?;
+                    }
+                }
+                _ => self.member(false)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn member(&mut self, in_contract: bool) -> Result<(), Error<'src>> {
+        match self.peek() {
+            Some(SolToken::Ident("function")) => {
+                self.pos += 1;
+                let name = self.ident()?;
+                let args = self.params(false)?;
+                let mut external = in_contract;
+                let mut state_mutability = StateMutability::NonPayable;
+                let mut rets = Vec::new();
+                loop {
+                    match self.peek() {
+                        Some(SolToken::Ident("internal" | "private")) => external = false,
+                        Some(SolToken::Ident("payable")) => {
+                            state_mutability = StateMutability::Payable
+                        }
+                        Some(SolToken::Ident("view")) => state_mutability = StateMutability::View,
+                        Some(SolToken::Ident("pure")) => state_mutability = StateMutability::Pure,
+                        Some(SolToken::Ident("returns")) => {
+                            self.pos += 1;
+                            rets = self.params(false)?;
+                            continue;
+                        }
+                        // Other modifiers, along with their arguments, e.g. `override(A, B)`.
+                        Some(SolToken::Ident(_)) => {
+                            self.pos += 1;
+                            if self.peek() == Some(SolToken::Punct('(')) {
+                                self.skip_group()?;
+                            }
+                            continue;
+                        }
+                        _ => break,
+                    }
+                    self.pos += 1;
+                }
+                self.skip_declaration()?;
+                if external {
+                    self.items.push(SolItem::Function {
+                        name,
+                        args,
+                        rets,
+                        state_mutability,
+                    });
+                }
+            }
+            Some(SolToken::Ident("event")) => {
+                self.pos += 1;
+                let name = self.ident()?;
+                let args = self.params(true)?;
+                let anonymous = self.eat(SolToken::Ident("anonymous"));
+                self.expect(';')?;
+                self.items.push(SolItem::Event {
+                    name,
+                    args,
+                    anonymous,
+                });
+            }
+            Some(SolToken::Ident("error")) => {
+                self.pos += 1;
+                let name = self.ident()?;
+                let args = self.params(false)?;
+                self.expect(';')?;
+                self.items.push(SolItem::Error { name, args });
+            }
+            Some(SolToken::Ident("struct")) => {
+                self.pos += 1;
+                let (name, _) = self.ident()?;
+                self.expect('{')?;
+                let mut fields = Vec::new();
+                while !self.eat(SolToken::Punct('}')) {
+                    fields.push(self.ty()?);
+                    self.ident()?;
+                    self.expect(';')?;
+                }
+                self.structs.insert(name, fields);
+            }
+            Some(SolToken::Ident("enum")) => {
+                self.pos += 1;
+                let (name, _) = self.ident()?;
+                self.enums.insert(name);
+                self.skip_declaration()?;
+            }
+            Some(_) => self.skip_declaration()?,
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn params(&mut self, indexable: bool) -> Result<Vec<SolRawParam<'src>>, Error<'src>> {
+        self.expect('(')?;
+        let mut params = Vec::new();
+        if self.eat(SolToken::Punct(')')) {
+            return Ok(params);
+        }
+        loop {
+            let ty = self.ty()?;
+            while matches!(
+                self.peek(),
+                Some(SolToken::Ident("memory" | "calldata" | "storage"))
+            ) {
+                self.pos += 1;
+            }
+            let indexed = indexable && self.eat(SolToken::Ident("indexed"));
+            let name = self.ident().ok();
+            params.push(SolRawParam { ty, name, indexed });
+            if !self.eat(SolToken::Punct(',')) {
+                self.expect(')')?;
+                return Ok(params);
+            }
+        }
+    }
+
+    fn ty(&mut self) -> Result<SolRawType<'src>, Error<'src>> {
+        let (mut name, start) = self.ident()?;
+        if matches!(name, "mapping" | "function") {
+            return Err(error(start, format!("`{}` types are not supported", name)));
+        }
+        while self.eat(SolToken::Punct('.')) {
+            name = self.ident()?.0;
+        }
+        if name == "address" {
+            self.eat(SolToken::Ident("payable"));
+        }
+        let mut suffix = String::new();
+        while self.eat(SolToken::Punct('[')) {
+            match self.tokens.get(self.pos) {
+                Some(&(SolToken::Literal(len), _)) if len.bytes().all(|b| b.is_ascii_digit()) => {
+                    self.pos += 1;
+                    suffix.push_str(&format!("[{}]", len));
+                }
+                Some((SolToken::Punct(']'), _)) => suffix.push_str("[]"),
+                _ => return Err(error(self.span(), "expected array length")),
+            }
+            self.expect(']')?;
+        }
+        Ok(SolRawType {
+            name,
+            suffix,
+            span: SimpleSpan::new(start.start, self.last_end()),
+        })
+    }
+
+    /// Canonical type, spelling out structs as tuples.
+    fn canonical(&self, ty: &SolRawType<'src>, depth: usize) -> Result<String, Error<'src>> {
+        let base = if let Some(fields) = self.structs.get(ty.name) {
+            if depth > self.structs.len() {
+                return Err(error(ty.span, format!("recursive struct `{}`", ty.name)));
+            }
+            let fields = fields
+                .iter()
+                .map(|field| self.canonical(field, depth + 1))
+                .collect::<Result<Vec<_>, _>>()?;
+            format!("({})", fields.join(","))
+        } else if self.enums.contains(ty.name) {
+            "uint8".to_owned()
+        } else if self.contracts.contains(ty.name) {
+            "address".to_owned()
+        } else {
+            ty.name.to_owned()
+        };
+        Ok(base + &ty.suffix)
+    }
+
+    fn resolve(&self, params: &[SolRawParam<'src>]) -> Result<Box<[SolParam<'src>]>, Error<'src>> {
+        params
+            .iter()
+            .map(|param| {
+                let ty = DynSolType::parse(&self.canonical(&param.ty, 0)?).map_err(|_| {
+                    error(param.ty.span, format!("unknown type `{}`", param.ty.name))
+                })?;
+                Ok(SolParam {
+                    ty: (ty, param.ty.span),
+                    name: param.name,
+                    indexed: param.indexed,
+                })
+            })
+            .collect()
+    }
+
+    fn definitions(mut self) -> Result<Vec<Definition<'src>>, Error<'src>> {
+        self.source_unit()?;
+        self.items
+            .iter()
+            .map(|item| {
+                Ok(match item {
+                    SolItem::Function {
+                        name,
+                        args,
+                        rets,
+                        state_mutability,
+                    } => Definition::SolFunction(SolFunction {
+                        name: *name,
+                        args: self.resolve(args)?,
+                        rets: self.resolve(rets)?,
+                        state_mutability: *state_mutability,
+                    }),
+                    SolItem::Event {
+                        name,
+                        args,
+                        anonymous,
+                    } => Definition::SolEvent(SolEvent {
+                        name: *name,
+                        args: self.resolve(args)?,
+                        anonymous: *anonymous,
+                    }),
+                    SolItem::Error { name, args } => Definition::SolError(SolError {
+                        name: *name,
+                        args: self.resolve(args)?,
+                    }),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signatures(definitions: &[Definition]) -> Vec<String> {
+        definitions
+            .iter()
+            .map(|def| def.abi_signature().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn parses_json_abi() {
+        let src = r#"{"abi": [
+    {"type": "constructor", "inputs": []},
+    {"type": "function", "name": "transfer", "stateMutability": "nonpayable",
+     "inputs": [{"name": "to", "type": "address"}, {"name": "", "type": "uint256"}],
+     "outputs": [{"name": "", "type": "bool"}]},
+    {"type": "function", "name": "positions", "stateMutability": "view",
+     "inputs": [{"name": "owners", "type": "tuple[]", "components": [
+        {"name": "owner", "type": "address"}, {"name": "amount", "type": "uint128"}]}],
+     "outputs": []},
+    {"type": "event", "name": "Transfer", "anonymous": false, "inputs": [
+        {"name": "from", "type": "address", "indexed": true},
+        {"name": "value", "type": "uint256", "indexed": false}]},
+    {"type": "error", "name": "Unauthorized", "inputs": []}
+]}"#;
+        let definitions = parse_abi_include("IERC20.json", src).unwrap();
+        assert_eq!(
+            signatures(&definitions),
+            [
+                "transfer(address,uint256)",
+                "positions((address,uint128)[])",
+                "Transfer(address,uint256)",
+                "Unauthorized()"
+            ]
+        );
+
+        let Definition::SolFunction(transfer) = &definitions[0] else {
+            panic!("expected a function")
+        };
+        assert_eq!(&src[transfer.name.1.into_range()], "transfer");
+        assert_eq!(transfer.args[0].name.map(|(name, _)| name), Some("to"));
+        assert_eq!(transfer.args[1].name, None);
+        let Definition::SolEvent(event) = &definitions[2] else {
+            panic!("expected an event")
+        };
+        assert_eq!(
+            event.args.iter().map(|arg| arg.indexed).collect::<Vec<_>>(),
+            [true, false]
+        );
+
+        let err = parse_abi_include(
+            "bad.json",
+            r#"[{"name": "f", "inputs": [{"type": "uint7"}]}]"#,
+        )
+        .unwrap_err();
+        assert_eq!(err.span().into_range(), 36..41);
+    }
+
+    #[test]
+    fn parses_sol_interface() {
+        let src = "// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+interface IPool is IBase {
+    struct Position { address owner; uint128[2] amounts; Kind kind; }
+    enum Kind { Long, Short }
+
+    event Opened(address indexed owner, Position position) anonymous;
+    error Unauthorized(address payable caller);
+
+    function open(IPool.Position[] calldata positions, string memory) external payable;
+    function positions(address owner) external view returns (Position[] memory);
+    function token() external pure returns (IPool);
+}
+
+contract Pool {
+    mapping(address => uint256) balances;
+
+    function close(uint256 id) public virtual override(A, B) returns (bool) {
+        if (id > 0) { return true; }
+    }
+
+    function settle() internal {}
+}";
+        let definitions = parse_abi_include("IPool.sol", src).unwrap();
+        assert_eq!(
+            signatures(&definitions),
+            [
+                "Opened(address,(address,uint128[2],uint8))",
+                "Unauthorized(address)",
+                "open((address,uint128[2],uint8)[],string)",
+                "positions(address)",
+                "token()",
+                "close(uint256)"
+            ]
+        );
+
+        let Definition::SolEvent(opened) = &definitions[0] else {
+            panic!("expected an event")
+        };
+        assert!(opened.anonymous);
+        assert_eq!(&src[opened.name.1.into_range()], "Opened");
+        assert_eq!(&src[opened.args[1].ty.1.into_range()], "Position");
+        let Definition::SolFunction(open) = &definitions[2] else {
+            panic!("expected a function")
+        };
+        assert_eq!(open.state_mutability, StateMutability::Payable);
+        assert_eq!(open.args[1].name, None);
+
+        let err = parse_abi_include("I.sol", "interface I { function f(Unknown x) external; }")
+            .unwrap_err();
+        assert_eq!(err.span().into_range(), 25..32);
+    }
+}
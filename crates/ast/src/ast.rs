@@ -1,15 +1,29 @@
+use crate::util::compute_signature;
 use alloy_dyn_abi::DynSolType;
-use alloy_primitives::U256;
+use alloy_primitives::{Address, U256};
 use chumsky::span::SimpleSpan;
 use evm_glue::opcodes::Opcode;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Root<'src>(pub Box<[RootSection<'src>]>);
 
+impl<'src> Root<'src> {
+    /// The file's definitions, skipping its includes.
+    pub fn definitions(&self) -> impl Iterator<Item = &Definition<'src>> + Clone {
+        self.0.iter().filter_map(|section| match section {
+            RootSection::Definition(def) => Some(def),
+            RootSection::Include(_) | RootSection::IncludeAbi(_) => None,
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RootSection<'src> {
     Definition(Definition<'src>),
     Include(Spanned<String>),
+    /// `#include_abi "IERC20.json"`, a JSON ABI or Solidity interface whose functions, events and
+    /// errors are defined as if they were part of the file.
+    IncludeAbi(Spanned<String>),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -30,6 +44,8 @@ pub enum Definition<'src> {
     SolFunction(SolFunction<'src>),
     SolEvent(SolEvent<'src>),
     SolError(SolError<'src>),
+    /// A `#define test` or `#define fuzz`, compiled and executed as its own entry point.
+    Test(Test<'src>),
 }
 
 pub trait IdentifiableNode<'a> {
@@ -44,10 +60,23 @@ pub trait IdentifiableNode<'a> {
     }
 }
 
+impl Definition<'_> {
+    /// Canonical signature of a function, event or error definition, `None` for other definitions.
+    pub fn abi_signature(&self) -> Option<String> {
+        match self {
+            Self::SolFunction(f) => Some(compute_signature(&f.name, &f.args)),
+            Self::SolEvent(e) => Some(compute_signature(&e.name, &e.args)),
+            Self::SolError(e) => Some(compute_signature(&e.name, &e.args)),
+            _ => None,
+        }
+    }
+}
+
 impl<'src> IdentifiableNode<'src> for Definition<'src> {
     fn spanned(&self) -> &Spanned<&'src str> {
         match self {
             Self::Macro(m) => &m.name,
+            Self::Test(t) => &t.r#macro.name,
             Self::Constant(c) => &c.name,
             Self::Jumptable(jt) => &jt.name,
             Self::CodeTable { name, .. } => name,
@@ -72,6 +101,37 @@ impl<'src> IdentifiableNode<'src> for Macro<'src> {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Test<'src> {
+    pub decorators: Box<[Spanned<Decorator<'src>>]>,
+    pub r#macro: Macro<'src>,
+    /// Argument types of a `#define fuzz`, called with random ABI encoded values of them.
+    pub fuzz: Option<Box<[Spanned<DynSolType>]>>,
+}
+
+/// Setup or expectation attached to a test with `#[...]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decorator<'src> {
+    Calldata(Box<[u8]>),
+    /// Wei sent along with the call.
+    Value(U256),
+    Caller(Address),
+    /// Storage slot of the tested contract, set before the call.
+    Storage {
+        slot: WordRef<'src>,
+        value: WordRef<'src>,
+    },
+    /// The test only passes if it reverts, with the given error if any.
+    ExpectRevert(Option<Spanned<&'src str>>),
+}
+
+/// A literal word or a reference to a constant (`[NAME]`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WordRef<'src> {
+    Literal(Spanned<U256>),
+    Constant(Spanned<&'src str>),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ConstExpr {
     Value(U256),
@@ -85,6 +145,17 @@ pub enum MacroStatement<'src> {
     Invoke(Invoke<'src>),
 }
 
+impl MacroStatement<'_> {
+    /// Span of the statement, only the argument's for builtin invocations.
+    pub fn get_span(&self) -> Span {
+        match self {
+            Self::LabelDefinition(name) => name.1,
+            Self::Instruction(instruction) => instruction.get_span(),
+            Self::Invoke(invoke) => invoke.get_span(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Instruction<'src> {
     Op(Spanned<Opcode>),
@@ -117,8 +188,33 @@ pub enum Invoke<'src> {
     BuiltinCodeSize(Spanned<&'src str>),
     BuiltinCodeOffset(Spanned<&'src str>),
     BuiltinFuncSig(Spanned<&'src str>),
+    /// `__FUNC_SIG("transfer(address,uint256)")`
+    BuiltinFuncSigLiteral(Spanned<SolSignature>),
     BuiltinEventHash(Spanned<&'src str>),
+    /// `__EVENT_HASH("Transfer(address,address,uint256)")`
+    BuiltinEventHashLiteral(Spanned<SolSignature>),
     BuiltinError(Spanned<&'src str>),
+    /// `__argsoffset()`, the end of the entry point's code where constructor arguments are appended.
+    BuiltinArgsOffset(Span),
+}
+
+impl Invoke<'_> {
+    pub fn get_span(&self) -> Span {
+        match self {
+            Self::Macro { name, args } => Span::new(name.1.start, args.1.end),
+            Self::BuiltinTableStart(name)
+            | Self::BuiltinTableSize(name)
+            | Self::BuiltinCodeSize(name)
+            | Self::BuiltinCodeOffset(name)
+            | Self::BuiltinFuncSig(name)
+            | Self::BuiltinEventHash(name)
+            | Self::BuiltinError(name) => name.1,
+            Self::BuiltinFuncSigLiteral(signature) | Self::BuiltinEventHashLiteral(signature) => {
+                signature.1
+            }
+            Self::BuiltinArgsOffset(span) => *span,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -128,23 +224,63 @@ pub struct Jumptable<'src> {
     pub labels: Box<[&'src str]>,
 }
 
+/// A function or event signature spelled out in a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolSignature {
+    pub name: String,
+    pub args: Box<[SolParam<'static>]>,
+}
+
+/// A parameter of a function, event or error definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolParam<'src> {
+    pub ty: Spanned<DynSolType>,
+    pub name: Option<Spanned<&'src str>>,
+    /// Whether the parameter is stored as a topic, only event parameters can be indexed.
+    pub indexed: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StateMutability {
+    Pure,
+    View,
+    #[default]
+    NonPayable,
+    Payable,
+}
+
+impl StateMutability {
+    /// The mutability as named in Solidity and JSON ABIs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pure => "pure",
+            Self::View => "view",
+            Self::NonPayable => "nonpayable",
+            Self::Payable => "payable",
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SolFunction<'src> {
     pub name: Spanned<&'src str>,
-    pub args: Box<[Spanned<DynSolType>]>,
-    pub rets: Box<[Spanned<DynSolType>]>,
+    pub args: Box<[SolParam<'src>]>,
+    pub rets: Box<[SolParam<'src>]>,
+    pub state_mutability: StateMutability,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SolEvent<'src> {
     pub name: Spanned<&'src str>,
-    pub args: Box<[Spanned<DynSolType>]>,
+    pub args: Box<[SolParam<'src>]>,
+    /// Anonymous events are logged without their signature's hash as the first topic.
+    pub anonymous: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SolError<'src> {
     pub name: Spanned<&'src str>,
-    pub args: Box<[Spanned<DynSolType>]>,
+    pub args: Box<[SolParam<'src>]>,
 }
 
 /// A span.
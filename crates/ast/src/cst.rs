@@ -0,0 +1,459 @@
+use crate::{
+    ast,
+    lexer::{lex_lossless, Token},
+    Span, Spanned,
+};
+use chumsky::{error::Rich, span::SimpleSpan};
+use std::{fmt, ops::Range};
+
+/// Source text between significant tokens that is irrelevant to the parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trivia<'src> {
+    Whitespace(&'src str),
+    Comment(&'src str),
+}
+
+impl<'src> Trivia<'src> {
+    pub fn text(&self) -> &'src str {
+        match self {
+            Self::Whitespace(s) | Self::Comment(s) => s,
+        }
+    }
+
+    pub fn is_comment(&self) -> bool {
+        matches!(self, Self::Comment(_))
+    }
+}
+
+/// A significant token together with the trivia surrounding it.
+///
+/// Trailing trivia is everything following the token up to (excluding) the next line break, all
+/// other trivia is attached as leading trivia to the token that follows it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CstToken<'src> {
+    pub token: Token<'src>,
+    /// The token exactly as it appears in the source.
+    pub text: &'src str,
+    pub span: Span,
+    pub leading: Box<[Spanned<Trivia<'src>>]>,
+    pub trailing: Box<[Spanned<Trivia<'src>>]>,
+}
+
+impl<'src> CstToken<'src> {
+    pub fn leading_comments(&self) -> impl Iterator<Item = &Spanned<Trivia<'src>>> {
+        self.leading
+            .iter()
+            .filter(|(trivia, _)| trivia.is_comment())
+    }
+
+    pub fn trailing_comments(&self) -> impl Iterator<Item = &Spanned<Trivia<'src>>> {
+        self.trailing
+            .iter()
+            .filter(|(trivia, _)| trivia.is_comment())
+    }
+
+    /// Number of line breaks in the leading trivia.
+    pub fn leading_newlines(&self) -> usize {
+        self.leading
+            .iter()
+            .map(|(trivia, _)| match trivia {
+                Trivia::Whitespace(ws) => ws.matches('\n').count(),
+                Trivia::Comment(_) => 0,
+            })
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CstNodeKind {
+    Root,
+    /// A `#define ...` section, including the decorators (`#[...]`) preceding it.
+    Definition,
+    /// An `#include ...` or `#include_abi ...` section.
+    Include,
+    /// A bracketed group, holds the opening delimiter (`(`, `{` or `[`).
+    Group(char),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CstNode {
+    pub kind: CstNodeKind,
+    /// Index range into [`Cst::tokens`], includes the delimiters of groups.
+    pub tokens: Range<usize>,
+    pub children: Box<[CstNode]>,
+}
+
+/// Lossless concrete syntax tree. Displaying it reproduces the source it was parsed from
+/// byte-for-byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cst<'src> {
+    pub src: &'src str,
+    pub tokens: Box<[CstToken<'src>]>,
+    /// Trivia following the last token.
+    pub eof_trivia: Box<[Spanned<Trivia<'src>>]>,
+    pub root: CstNode,
+}
+
+impl<'src> Cst<'src> {
+    /// Parse the given source code string into a concrete syntax tree.
+    pub fn parse(src: &'src str) -> Result<Self, Vec<Rich<'src, Token<'src>>>> {
+        let raw_tokens = lex_lossless(src)?;
+
+        let mut tokens: Vec<CstToken<'src>> = Vec::with_capacity(raw_tokens.len() / 2);
+        let mut pending: Vec<Spanned<Trivia<'src>>> = Vec::new();
+        let mut trailing: Vec<Spanned<Trivia<'src>>> = Vec::new();
+        let mut on_token_line = false;
+
+        for (token, span) in raw_tokens {
+            let trivia = match token {
+                Token::Whitespace(ws) => Trivia::Whitespace(ws),
+                Token::Comment(comment) => Trivia::Comment(comment),
+                token => {
+                    if let Some(last) = tokens.last_mut() {
+                        last.trailing = std::mem::take(&mut trailing).into_boxed_slice();
+                    }
+                    tokens.push(CstToken {
+                        token,
+                        text: &src[span.into_range()],
+                        span,
+                        leading: std::mem::take(&mut pending).into_boxed_slice(),
+                        trailing: Box::new([]),
+                    });
+                    on_token_line = true;
+                    continue;
+                }
+            };
+            if on_token_line && !matches!(trivia, Trivia::Whitespace(ws) if ws.contains('\n')) {
+                trailing.push((trivia, span));
+            } else {
+                on_token_line = false;
+                pending.push((trivia, span));
+            }
+        }
+        if let Some(last) = tokens.last_mut() {
+            last.trailing = trailing.into_boxed_slice();
+        }
+
+        let root = build_tree(&tokens);
+
+        Ok(Self {
+            src,
+            tokens: tokens.into_boxed_slice(),
+            eof_trivia: pending.into_boxed_slice(),
+            root,
+        })
+    }
+
+    pub fn node_span(&self, node: &CstNode) -> Span {
+        if node.kind == CstNodeKind::Root {
+            return SimpleSpan::new(0, self.src.len());
+        }
+        match (
+            self.tokens.get(node.tokens.start),
+            node.tokens
+                .end
+                .checked_sub(1)
+                .and_then(|last| self.tokens.get(last)),
+        ) {
+            (Some(first), Some(last)) => SimpleSpan::new(first.span.start, last.span.end),
+            _ => SimpleSpan::new(0, 0),
+        }
+    }
+
+    pub fn node_tokens(&self, node: &CstNode) -> &[CstToken<'src>] {
+        &self.tokens[node.tokens.clone()]
+    }
+
+    /// Index of the section's `#define`/`#include` keyword, following its decorators if any.
+    pub fn section_keyword(&self, section: &CstNode) -> Option<usize> {
+        section
+            .tokens
+            .clone()
+            .find(|&idx| matches!(self.tokens[idx].token, Token::Keyword(_)))
+    }
+
+    /// Index of the token containing the given byte offset.
+    pub fn token_index_at(&self, offset: usize) -> Option<usize> {
+        let idx = self.tokens.partition_point(|tok| tok.span.end <= offset);
+        self.tokens
+            .get(idx)
+            .filter(|tok| tok.span.start <= offset)
+            .map(|_| idx)
+    }
+
+    /// Range of the tokens overlapping the given span.
+    pub fn token_range(&self, span: Span) -> Range<usize> {
+        let start = self
+            .tokens
+            .partition_point(|tok| tok.span.end <= span.start);
+        let end = self
+            .tokens
+            .partition_point(|tok| tok.span.start < span.end.max(span.start + 1));
+        start..end.max(start)
+    }
+
+    /// Deepest node fully containing the given span.
+    pub fn covering_node(&self, span: Span) -> &CstNode {
+        let mut node = &self.root;
+        while let Some(child) = node.children.iter().find(|child| {
+            let child_span = self.node_span(child);
+            child_span.start <= span.start && span.end <= child_span.end
+        }) {
+            node = child;
+        }
+        node
+    }
+
+    /// Maps an AST node back to the deepest CST node containing it.
+    pub fn node_for<N: SpannedNode>(&self, node: &N) -> &CstNode {
+        self.covering_node(node.node_span())
+    }
+
+    /// Tokens making up the given AST node.
+    pub fn tokens_for<N: SpannedNode>(&self, node: &N) -> &[CstToken<'src>] {
+        &self.tokens[self.token_range(node.node_span())]
+    }
+}
+
+impl fmt::Display for Cst<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in self.tokens.iter() {
+            for (trivia, _) in token.leading.iter() {
+                f.write_str(trivia.text())?;
+            }
+            f.write_str(token.text)?;
+            for (trivia, _) in token.trailing.iter() {
+                f.write_str(trivia.text())?;
+            }
+        }
+        for (trivia, _) in self.eof_trivia.iter() {
+            f.write_str(trivia.text())?;
+        }
+        Ok(())
+    }
+}
+
+fn build_tree(tokens: &[CstToken<'_>]) -> CstNode {
+    struct Frame {
+        kind: CstNodeKind,
+        start: usize,
+        children: Vec<CstNode>,
+    }
+
+    fn close(stack: &mut Vec<Frame>, end: usize) {
+        let frame = stack.pop().unwrap();
+        stack.last_mut().unwrap().children.push(CstNode {
+            kind: frame.kind,
+            tokens: frame.start..end,
+            children: frame.children.into_boxed_slice(),
+        });
+    }
+
+    let mut stack = vec![Frame {
+        kind: CstNodeKind::Root,
+        start: 0,
+        children: Vec::new(),
+    }];
+    // Whether the open section was started by decorators and awaits its `#define`.
+    let mut decorated = false;
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token.token {
+            Token::Punct('#') if !decorated => {
+                while stack.len() > 1 {
+                    close(&mut stack, i);
+                }
+                stack.push(Frame {
+                    kind: CstNodeKind::Definition,
+                    start: i,
+                    children: Vec::new(),
+                });
+                decorated = true;
+            }
+            Token::Keyword("define") if decorated && stack.len() == 2 => decorated = false,
+            Token::Keyword(keyword) => {
+                decorated = false;
+                // Sections only ever appear at the root, a keyword closes anything left open.
+                while stack.len() > 1 {
+                    close(&mut stack, i);
+                }
+                let kind = match keyword {
+                    "include" | "include_abi" => CstNodeKind::Include,
+                    _ => CstNodeKind::Definition,
+                };
+                stack.push(Frame {
+                    kind,
+                    start: i,
+                    children: Vec::new(),
+                });
+            }
+            Token::Punct(open @ ('(' | '{' | '[')) => stack.push(Frame {
+                kind: CstNodeKind::Group(open),
+                start: i,
+                children: Vec::new(),
+            }),
+            Token::Punct(close_delim @ (')' | '}' | ']')) => {
+                let open = match close_delim {
+                    ')' => '(',
+                    '}' => '{',
+                    _ => '[',
+                };
+                if stack.last().unwrap().kind == CstNodeKind::Group(open) {
+                    close(&mut stack, i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    while stack.len() > 1 {
+        close(&mut stack, tokens.len());
+    }
+
+    let root = stack.pop().unwrap();
+    CstNode {
+        kind: CstNodeKind::Root,
+        tokens: 0..tokens.len(),
+        children: root.children.into_boxed_slice(),
+    }
+}
+
+/// AST node that can be mapped back to its location in the source.
+pub trait SpannedNode {
+    fn node_span(&self) -> Span;
+}
+
+impl<T> SpannedNode for Spanned<T> {
+    fn node_span(&self) -> Span {
+        self.1
+    }
+}
+
+impl SpannedNode for ast::RootSection<'_> {
+    fn node_span(&self) -> Span {
+        match self {
+            Self::Definition(def) => def.node_span(),
+            Self::Include(path) | Self::IncludeAbi(path) => path.1,
+        }
+    }
+}
+
+impl SpannedNode for ast::Definition<'_> {
+    fn node_span(&self) -> Span {
+        ast::IdentifiableNode::span(self)
+    }
+}
+
+impl SpannedNode for ast::Macro<'_> {
+    fn node_span(&self) -> Span {
+        self.name.1
+    }
+}
+
+impl SpannedNode for ast::Constant<'_> {
+    fn node_span(&self) -> Span {
+        SimpleSpan::new(self.name.1.start, self.expr.1.end)
+    }
+}
+
+impl SpannedNode for ast::MacroStatement<'_> {
+    fn node_span(&self) -> Span {
+        self.get_span()
+    }
+}
+
+impl SpannedNode for ast::Instruction<'_> {
+    fn node_span(&self) -> Span {
+        self.get_span()
+    }
+}
+
+impl SpannedNode for ast::Invoke<'_> {
+    fn node_span(&self) -> Span {
+        self.get_span()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const EXAMPLES: [&str; 4] = [
+        include_str!("../../../examples/Ownable.huff"),
+        include_str!("../../../examples/SimpleMacros.huff"),
+        include_str!("../../../examples/features/Constants.huff"),
+        include_str!("../../../examples/features/CodeTable.huff"),
+    ];
+
+    #[test]
+    fn round_trips_source() {
+        for src in EXAMPLES {
+            assert_eq!(Cst::parse(src).unwrap().to_string(), src);
+        }
+        assert_eq!(Cst::parse("").unwrap().to_string(), "");
+        assert_eq!(
+            Cst::parse("  // only trivia\n").unwrap().to_string(),
+            "  // only trivia\n"
+        );
+    }
+
+    #[test]
+    fn attaches_trivia() {
+        let cst = Cst::parse("// doc\nadd // [c]\n  sub").unwrap();
+        assert_eq!(cst.tokens.len(), 2);
+        assert_eq!(
+            *cst.tokens[0].leading,
+            [
+                (Trivia::Comment("// doc"), SimpleSpan::new(0, 6)),
+                (Trivia::Whitespace("\n"), SimpleSpan::new(6, 7))
+            ]
+        );
+        assert_eq!(
+            *cst.tokens[0].trailing,
+            [
+                (Trivia::Whitespace(" "), SimpleSpan::new(10, 11)),
+                (Trivia::Comment("// [c]"), SimpleSpan::new(11, 17))
+            ]
+        );
+        assert_eq!(
+            *cst.tokens[1].leading,
+            [(Trivia::Whitespace("\n  "), SimpleSpan::new(17, 20))]
+        );
+        assert_eq!(cst.tokens[1].leading_newlines(), 1);
+    }
+
+    #[test]
+    fn maps_ast_to_cst() {
+        let src = "#define constant A = 0x1\n#define macro M() = { [A] add }";
+        let cst = Cst::parse(src).unwrap();
+        let ast = parse(src).unwrap();
+
+        assert_eq!(cst.root.children.len(), 2);
+        let ast::RootSection::Definition(ast::Definition::Macro(m)) = &ast.0[1] else {
+            panic!("expected macro")
+        };
+        let section = cst.node_for(&ast.0[1]);
+        assert_eq!(section.kind, CstNodeKind::Definition);
+        assert_eq!(cst.node_span(section), SimpleSpan::new(25, src.len()));
+
+        assert_eq!(cst.node_for(&m.body[0]).kind, CstNodeKind::Group('['));
+        assert_eq!(cst.node_for(&m.body[1]).kind, CstNodeKind::Group('{'));
+        let tokens: Vec<_> = cst.tokens_for(&m.body[0]).iter().map(|t| t.text).collect();
+        assert_eq!(tokens, ["A"]);
+    }
+
+    #[test]
+    fn groups_decorators_with_definition() {
+        let src = "#define macro M() = {}\n#[value(1)]\n#[expect_revert]\n#define test T() = {}";
+        let cst = Cst::parse(src).unwrap();
+
+        assert_eq!(cst.root.children.len(), 2);
+        let section = &cst.root.children[1];
+        assert_eq!(section.kind, CstNodeKind::Definition);
+        assert_eq!(cst.tokens[section.tokens.start].text, "#");
+        assert_eq!(
+            cst.tokens[cst.section_keyword(section).unwrap()].text,
+            "#define"
+        );
+    }
+}
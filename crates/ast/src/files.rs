@@ -0,0 +1,23 @@
+use std::path::{Path, PathBuf};
+
+/// Collects `.huff` files, directories are searched recursively skipping hidden entries.
+pub fn collect_huff_files(path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for entry in entries {
+        let hidden = entry
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if !hidden && (entry.is_dir() || entry.extension().is_some_and(|ext| ext == "huff")) {
+            collect_huff_files(&entry, files)?;
+        }
+    }
+    Ok(())
+}
@@ -17,9 +17,23 @@ pub(crate) fn lex<'a>(src: &'a str) -> Result<Vec<Spanned<Token<'a>>>, Vec<Rich<
     })
 }
 
+/// Lex the given source code string into tokens, keeping whitespace and comments as
+/// [`Token::Whitespace`] and [`Token::Comment`] tokens. The spans of the returned tokens cover the
+/// entire source without gaps.
+pub(crate) fn lex_lossless<'a>(
+    src: &'a str,
+) -> Result<Vec<Spanned<Token<'a>>>, Vec<Rich<'a, Token<'a>>>> {
+    lossless_lexer().parse(src).into_result().map_err(|e| {
+        e.into_iter()
+            .map(|errs| errs.map_token(Token::Error))
+            .collect::<Vec<_>>()
+    })
+}
+
 /// Lexer token
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Token<'src> {
+    Whitespace(&'src str),
     Comment(&'src str),
     Keyword(&'src str),
     Ident(&'src str),
@@ -35,7 +49,8 @@ pub enum Token<'src> {
 impl fmt::Display for Token<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Token::Comment(s)
+            Token::Whitespace(s)
+            | Token::Comment(s)
             | Token::Keyword(s)
             | Token::Ident(s)
             | Token::Dec(s)
@@ -47,7 +62,24 @@ impl fmt::Display for Token<'_> {
     }
 }
 
+impl Token<'_> {
+    /// Whether the token is whitespace or a comment, i.e. not significant to the parser.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, Token::Whitespace(_) | Token::Comment(_))
+    }
+}
+
 fn lexer<'src>(
+) -> impl Parser<'src, &'src str, Vec<Spanned<Token<'src>>>, extra::Err<Rich<'src, char>>> {
+    lossless_lexer().map(|tokens| {
+        tokens
+            .into_iter()
+            .filter(|(tok, _)| !tok.is_trivia())
+            .collect()
+    })
+}
+
+fn lossless_lexer<'src>(
 ) -> impl Parser<'src, &'src str, Vec<Spanned<Token<'src>>>, extra::Err<Rich<'src, char>>> {
     let validate_end = any()
         .or_not()
@@ -60,13 +92,20 @@ fn lexer<'src>(
             }
         });
     let keyword = just("#")
-        .ignore_then(choice((keyword("define"), keyword("include"))))
+        .ignore_then(choice((
+            keyword("define"),
+            keyword("include"),
+            keyword("include_abi"),
+        )))
         .then_ignore(validate_end)
         .map(Token::Keyword);
 
     let ident = text::ident().then_ignore(validate_end).map(Token::Ident);
 
-    let punct = one_of("(){}[]<>:=,").map(Token::Punct);
+    // `#` only opens decorators (`#[...]`), it's otherwise part of a keyword.
+    let punct = one_of("(){}[]<>:=,")
+        .or(just('#').then_ignore(just('[').rewind()))
+        .map(Token::Punct);
 
     let hex = just("0x")
         .ignore_then(text::digits(16))
@@ -94,20 +133,27 @@ fn lexer<'src>(
 
     let token = choice((keyword, ident, punct, hex, bin, dec, string));
 
+    let whitespace = any()
+        .filter(|c: &char| c.is_whitespace())
+        .repeated()
+        .at_least(1)
+        .to_slice()
+        .map(Token::Whitespace);
+
     // comments
     let single_line_comment = just("//")
         .then(any().and_is(just('\n').not()).repeated())
-        .padded();
+        .to_slice();
     let multi_line_comment = just("/*")
         .then(any().and_is(just("*/").not()).repeated())
-        .then_ignore(just("*/"))
-        .padded();
-    let comment = single_line_comment.or(multi_line_comment);
+        .then(just("*/"))
+        .to_slice();
+    let comment = single_line_comment
+        .or(multi_line_comment)
+        .map(Token::Comment);
 
-    token
+    choice((whitespace, comment, token))
         .map_with(|tok, ex| (tok, ex.span()))
-        .padded_by(comment.repeated())
-        .padded()
         // .recover_with(skip_then_retry_until(any().ignored(), end()))
         .repeated()
         .collect()
@@ -140,6 +186,10 @@ mod tests {
             "#include",
             (Token::Keyword("include"), SimpleSpan::new(0, 8))
         );
+        assert_ok!(
+            "#include_abi",
+            (Token::Keyword("include_abi"), SimpleSpan::new(0, 12))
+        );
     }
 
     #[test]
@@ -159,6 +209,11 @@ mod tests {
     #[test]
     fn lex_punct() {
         assert_ok!("(", (Token::Punct('('), SimpleSpan::new(0, 1)));
+        assert_ok!(
+            "#[",
+            (Token::Punct('#'), SimpleSpan::new(0, 1)),
+            (Token::Punct('['), SimpleSpan::new(1, 2))
+        );
         assert_ok!(
             "()",
             (Token::Punct('('), SimpleSpan::new(0, 1)),
@@ -195,6 +250,20 @@ mod tests {
         assert_ok!("0b0", (Token::Bin("0b0"), SimpleSpan::new(0, 3)));
     }
 
+    #[test]
+    fn lex_lossless_trivia() {
+        assert_eq!(
+            lossless_lexer().parse("add // [a]\n/* b */").into_result(),
+            Ok(vec![
+                (Token::Ident("add"), SimpleSpan::new(0, 3)),
+                (Token::Whitespace(" "), SimpleSpan::new(3, 4)),
+                (Token::Comment("// [a]"), SimpleSpan::new(4, 10)),
+                (Token::Whitespace("\n"), SimpleSpan::new(10, 11)),
+                (Token::Comment("/* b */"), SimpleSpan::new(11, 18)),
+            ])
+        );
+    }
+
     #[test]
     fn lex_string() {
         assert_ok!(
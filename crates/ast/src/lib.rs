@@ -1,8 +1,15 @@
+mod abi_include;
 mod ast;
+mod cst;
+mod files;
 mod lexer;
 mod parser;
 mod util;
 
+pub use abi_include::parse_abi_include;
 pub use ast::*;
+pub use cst::*;
+pub use files::collect_huff_files;
+pub use lexer::Token;
 pub use parser::parse;
 pub use util::*;
@@ -8,7 +8,7 @@ use crate::{
     Span, Spanned,
 };
 use alloy_dyn_abi::DynSolType;
-use alloy_primitives::{hex::FromHex, Bytes, U256};
+use alloy_primitives::{hex::FromHex, Address, Bytes, B256, U256};
 use chumsky::{
     error::Rich,
     extra,
@@ -68,19 +68,36 @@ fn root_section<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, ast::Roo
     let include = just(Keyword("include"))
         .ignore_then(select! {String(s) => s}.map_with(|s, ex| (s, ex.span())))
         .map(ast::RootSection::Include);
+    let include_abi = just(Keyword("include_abi"))
+        .ignore_then(select! {String(s) => s}.map_with(|s, ex| (s, ex.span())))
+        .map(ast::RootSection::IncludeAbi);
 
-    choice((definition, include))
+    choice((definition, include, include_abi))
 }
 
 fn definition<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, ast::Definition<'src>> {
-    just(Keyword("define")).ignore_then(choice((
-        r#macro(),
-        constant(),
-        table(),
-        sol_function(),
-        sol_event(),
-        sol_error(),
-    )))
+    let test = decorators()
+        .then_ignore(just(Keyword("define")))
+        .then(test())
+        .map(|(decorators, (r#macro, fuzz))| {
+            ast::Definition::Test(ast::Test {
+                decorators,
+                r#macro,
+                fuzz,
+            })
+        });
+
+    choice((
+        test,
+        just(Keyword("define")).ignore_then(choice((
+            r#macro(),
+            constant(),
+            table(),
+            sol_function(),
+            sol_event(),
+            sol_error(),
+        ))),
+    ))
 }
 
 fn r#macro<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, ast::Definition<'src>> {
@@ -101,21 +118,111 @@ fn r#macro<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, ast::Definiti
                 .then(dec().delimited_by(punct('('), punct(')')))
                 .or_not(),
         )
-        .then(
-            macro_statement()
-                .repeated()
-                .collect::<Vec<_>>()
-                .delimited_by(punct('{'), punct('}')),
-        )
+        .then(macro_body())
         .map(|(((name, args), takes_returns), body)| ast::Macro {
             name,
             args,
             takes_returns,
-            body: body.into_boxed_slice(),
+            body,
         })
         .map(ast::Definition::Macro)
 }
 
+/// A `test NAME()` or a `fuzz NAME(types...)` along with its fuzzed argument types.
+fn test<'tokens, 'src: 'tokens>(
+) -> impl Parser<'tokens, 'src, (ast::Macro<'src>, Option<Box<[Spanned<DynSolType>]>>)> {
+    let test = just(Ident("test")).ignore_then(ident()).then(
+        punct('(')
+            .then(punct(')'))
+            .map_with(|_, ex| (None, ex.span())),
+    );
+    let fuzz = just(Ident("fuzz"))
+        .ignore_then(ident())
+        .then(sol_type_list().map_with(|args, ex| (Some(args), ex.span())));
+
+    choice((test, fuzz))
+        .then_ignore(punct('='))
+        .then(macro_body())
+        .map(|((name, (fuzz, args_span)), body)| {
+            (
+                ast::Macro {
+                    name,
+                    args: (Box::default(), args_span),
+                    takes_returns: None,
+                    body,
+                },
+                fuzz,
+            )
+        })
+}
+
+fn decorators<'tokens, 'src: 'tokens>(
+) -> impl Parser<'tokens, 'src, Box<[Spanned<ast::Decorator<'src>>]>> {
+    decorator()
+        .separated_by(punct(','))
+        .at_least(1)
+        .collect::<Vec<_>>()
+        .delimited_by(punct('#').then(punct('[')), punct(']'))
+        .repeated()
+        .collect::<Vec<_>>()
+        .map(|groups| groups.into_iter().flatten().collect())
+}
+
+fn decorator<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, Spanned<ast::Decorator<'src>>>
+{
+    let calldata = just(Ident("calldata"))
+        .ignore_then(
+            select! {String(s) => s}
+                .try_map_with(|s, ex| {
+                    Bytes::from_hex(&s).map_err(|_e| Rich::custom(ex.span(), "invalid hex"))
+                })
+                .delimited_by(punct('('), punct(')')),
+        )
+        .map(|data| ast::Decorator::Calldata(data.to_vec().into_boxed_slice()));
+    let value = just(Ident("value"))
+        .ignore_then(word().delimited_by(punct('('), punct(')')))
+        .map(|(value, _)| ast::Decorator::Value(value));
+    let caller = just(Ident("caller"))
+        .ignore_then(word().delimited_by(punct('('), punct(')')))
+        .validate(|(value, span), _, emitter| {
+            if value.bit_len() > 160 {
+                emitter.emit(Rich::custom(span, "address overflows"));
+            }
+            ast::Decorator::Caller(Address::from_word(B256::from(value.to_be_bytes::<32>())))
+        });
+    let storage = just(Ident("storage"))
+        .ignore_then(
+            word_ref()
+                .then_ignore(punct(','))
+                .then(word_ref())
+                .delimited_by(punct('('), punct(')')),
+        )
+        .map(|(slot, value)| ast::Decorator::Storage { slot, value });
+    let expect_revert = just(Ident("expect_revert"))
+        .ignore_then(ident().delimited_by(punct('('), punct(')')).or_not())
+        .map(ast::Decorator::ExpectRevert);
+
+    choice((calldata, value, caller, storage, expect_revert)).map_with(|dec, ex| (dec, ex.span()))
+}
+
+fn word_ref<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, ast::WordRef<'src>> {
+    choice((
+        word().map(ast::WordRef::Literal),
+        ident()
+            .delimited_by(punct('['), punct(']'))
+            .map(ast::WordRef::Constant),
+    ))
+}
+
+fn macro_body<'tokens, 'src: 'tokens>(
+) -> impl Parser<'tokens, 'src, Box<[ast::MacroStatement<'src>]>> {
+    macro_statement()
+        .repeated()
+        .collect::<Vec<_>>()
+        .delimited_by(punct('{'), punct('}'))
+        .map(Vec::into_boxed_slice)
+}
+
 fn macro_statement<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, ast::MacroStatement<'src>>
 {
     let label = ident()
@@ -243,14 +350,31 @@ fn invoke<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, ast::Invoke<'s
             .map(constructor)
     };
 
+    let invoke_builtin_literal =
+        |name, constructor: fn(Spanned<ast::SolSignature>) -> ast::Invoke<'src>| {
+            just(Ident(name))
+                .ignore_then(punct('('))
+                .ignore_then(sol_signature())
+                .then_ignore(punct(')'))
+                .map(constructor)
+        };
+
+    let invoke_args_offset = just(Ident("__argsoffset"))
+        .ignore_then(punct('('))
+        .ignore_then(punct(')'))
+        .map_with(|_, ex| ast::Invoke::BuiltinArgsOffset(ex.span()));
+
     choice((
         invoke_builtin("__tablestart", ast::Invoke::BuiltinTableStart),
         invoke_builtin("__tablesize", ast::Invoke::BuiltinTableSize),
         invoke_builtin("__codesize", ast::Invoke::BuiltinCodeSize),
         invoke_builtin("__codeoffset", ast::Invoke::BuiltinCodeOffset),
         invoke_builtin("__FUNC_SIG", ast::Invoke::BuiltinFuncSig),
+        invoke_builtin_literal("__FUNC_SIG", ast::Invoke::BuiltinFuncSigLiteral),
         invoke_builtin("__EVENT_HASH", ast::Invoke::BuiltinEventHash),
+        invoke_builtin_literal("__EVENT_HASH", ast::Invoke::BuiltinEventHashLiteral),
         invoke_builtin("__ERROR", ast::Invoke::BuiltinError),
+        invoke_args_offset,
         invoke_macro,
     ))
 }
@@ -291,46 +415,102 @@ fn table<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, ast::Definition
 }
 
 fn sol_function<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, ast::Definition<'src>> {
+    let modifier = choice((
+        just(Ident("public")).to(None),
+        just(Ident("external")).to(None),
+        just(Ident("payable")).to(Some(ast::StateMutability::Payable)),
+        just(Ident("nonpayable")).to(Some(ast::StateMutability::NonPayable)),
+        just(Ident("view")).to(Some(ast::StateMutability::View)),
+        just(Ident("pure")).to(Some(ast::StateMutability::Pure)),
+    ));
+
     just(Ident("function"))
         .ignore_then(ident())
-        .then(sol_type_list())
+        .then(sol_param_list(false))
         .then(
-            choice((
-                just(Ident("public")),
-                just(Ident("external")),
-                just(Ident("payable")),
-                just(Ident("nonpayable")),
-            ))
-            .or_not()
-            .then_ignore(choice((just(Ident("view")), just(Ident("pure")))).or_not())
-            .or_not()
-            .ignore_then(just(Ident("returns")))
-            .ignore_then(sol_type_list())
-            .or_not(),
+            modifier
+                .repeated()
+                .collect::<Vec<_>>()
+                .validate(|modifiers, ex, emitter| {
+                    let mut state_mutabilities = modifiers.into_iter().flatten();
+                    let state_mutability = state_mutabilities.next().unwrap_or_default();
+                    if state_mutabilities.next().is_some() {
+                        emitter.emit(Rich::custom(
+                            ex.span(),
+                            "only one state mutability can be given",
+                        ));
+                    }
+                    state_mutability
+                }),
+        )
+        .then(
+            just(Ident("returns"))
+                .ignore_then(sol_param_list(false))
+                .or_not(),
         )
-        .map(|((name, args), rets)| {
+        .map(|(((name, args), state_mutability), rets)| {
             let rets = rets.unwrap_or(Box::new([]));
-            ast::Definition::SolFunction(ast::SolFunction { name, args, rets })
+            ast::Definition::SolFunction(ast::SolFunction {
+                name,
+                args,
+                rets,
+                state_mutability,
+            })
         })
 }
 
 fn sol_event<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, ast::Definition<'src>> {
     just(Ident("event"))
         .ignore_then(ident())
-        .then(sol_type_list())
-        .map(|(name, args)| ast::Definition::SolEvent(ast::SolEvent { name, args }))
+        .then(sol_param_list(true))
+        .then(just(Ident("anonymous")).or_not())
+        .map(|((name, args), anonymous)| {
+            ast::Definition::SolEvent(ast::SolEvent {
+                name,
+                args,
+                anonymous: anonymous.is_some(),
+            })
+        })
 }
 
 fn sol_error<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, ast::Definition<'src>> {
     just(Ident("error"))
         .ignore_then(ident())
-        .then(sol_type_list())
+        .then(sol_param_list(false))
         .map(|(name, args)| ast::Definition::SolError(ast::SolError { name, args }))
 }
 
+/// Parameters with optional names, `indexed` ones only being allowed if `indexable`.
+fn sol_param_list<'tokens, 'src: 'tokens>(
+    indexable: bool,
+) -> impl Parser<'tokens, 'src, Box<[ast::SolParam<'src>]>> {
+    sol_type()
+        .then(just(Ident("indexed")).or_not())
+        .then(ident().or_not())
+        .validate(move |((ty, indexed), name), ex, emitter| {
+            if indexed.is_some() && !indexable {
+                emitter.emit(Rich::custom(
+                    ex.span(),
+                    "only event parameters can be indexed",
+                ));
+            }
+            ast::SolParam {
+                ty,
+                name,
+                indexed: indexed.is_some(),
+            }
+        })
+        .separated_by(punct(','))
+        .collect::<Vec<_>>()
+        .delimited_by(punct('('), punct(')'))
+        .map(|args| args.into_boxed_slice())
+}
+
+/// Types of parameters, their names are allowed but not kept.
 fn sol_type_list<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, Box<[Spanned<DynSolType>]>>
 {
     sol_type()
+        .then_ignore(ident().or_not())
         .separated_by(punct(','))
         .collect::<Vec<_>>()
         .delimited_by(punct('('), punct(')'))
@@ -338,10 +518,64 @@ fn sol_type_list<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, Box<[Sp
 }
 
 fn sol_type<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, Spanned<DynSolType>> {
+    sol_raw_type().try_map_with(|typ, ex| {
+        DynSolType::parse(&typ)
+            .map(|typ| (typ, ex.span()))
+            .map_err(|e| Rich::custom(ex.span(), e))
+    })
+}
+
+/// A signature in a string, e.g. `"transfer(address,uint256)"`.
+fn sol_signature<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, Spanned<ast::SolSignature>>
+{
+    select! {String(s) => s}.validate(|signature, ex, emitter| {
+        let span: Span = ex.span();
+        let parsed = signature.split_once('(').and_then(|(name, _)| {
+            let is_ident = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            match DynSolType::parse(&signature[name.len()..]) {
+                Ok(DynSolType::Tuple(types)) if is_ident => Some((name, types)),
+                _ => None,
+            }
+        });
+        let Some((name, types)) = parsed else {
+            emitter.emit(Rich::custom(
+                span,
+                format!("invalid signature \"{}\"", signature),
+            ));
+            return (
+                ast::SolSignature {
+                    name: signature,
+                    args: Box::new([]),
+                },
+                span,
+            );
+        };
+        let args = types
+            .into_iter()
+            .map(|ty| ast::SolParam {
+                ty: (ty, span),
+                name: None,
+                indexed: false,
+            })
+            .collect();
+        (
+            ast::SolSignature {
+                name: name.to_owned(),
+                args,
+            },
+            span,
+        )
+    })
+}
+
+/// A type in its canonical form, tuple components may be named.
+fn sol_raw_type<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, std::string::String> {
     recursive(|sol_raw_type| {
         let sol_raw_primitive_type = ident().map(|(typ, _)| typ.to_string());
 
         let sol_raw_tuple_type = sol_raw_type
+            .then_ignore(ident().or_not())
             .separated_by(punct(','))
             .collect::<Vec<_>>()
             .delimited_by(punct('('), punct(')'))
@@ -358,12 +592,12 @@ fn sol_type<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, Spanned<DynS
                 punct('[')
                     .ignore_then(dec().or_not())
                     .then_ignore(punct(']'))
-                    .or_not(),
+                    .repeated()
+                    .collect::<Vec<_>>(),
             )
-            .then_ignore(ident().or_not())
-            .map(|(typ, slice)| {
+            .map(|(typ, dimensions)| {
                 let mut result = typ;
-                if let Some(size) = slice {
+                for size in dimensions {
                     result.push('[');
                     if let Some((n, _span)) = size {
                         result.push_str(&n.to_string());
@@ -374,11 +608,6 @@ fn sol_type<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, Spanned<DynS
             })
             .boxed()
     })
-    .try_map_with(|typ, ex| {
-        DynSolType::parse(&typ)
-            .map(|typ| (typ, ex.span()))
-            .map_err(|e| Rich::custom(ex.span(), e))
-    })
 }
 
 fn ident<'tokens, 'src: 'tokens>() -> impl Parser<'tokens, 'src, Spanned<&'src str>> {
@@ -500,6 +729,11 @@ mod tests {
             vec![Keyword("include"), String("test".to_string())],
             ast::RootSection::Include(("test".to_string(), span))
         );
+        assert_ok!(
+            root_section(),
+            vec![Keyword("include_abi"), String("IERC20.json".to_string())],
+            ast::RootSection::IncludeAbi(("IERC20.json".to_string(), span))
+        );
         assert_ok!(
             root_section(),
             vec![Keyword("define"), Ident("constant"), Ident("TEST"), Punct('='), Hex("0x1")],
@@ -564,6 +798,145 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_test() {
+        let span: Span = SimpleSpan::new(0, 0);
+        assert_ok!(
+            test(),
+            vec![
+                Ident("test"),
+                Ident("ADDS"),
+                Punct('('),
+                Punct(')'),
+                Punct('='),
+                Punct('{'),
+                Ident("add"),
+                Punct('}')
+            ],
+            (
+                ast::Macro {
+                    name: ("ADDS", span),
+                    args: (Box::new([]), span),
+                    takes_returns: None,
+                    body: Box::new([ast::MacroStatement::Instruction(ast::Instruction::Op((
+                        Opcode::ADD,
+                        span
+                    )))]),
+                },
+                None
+            )
+        );
+        assert_ok!(
+            test(),
+            vec![
+                Ident("fuzz"),
+                Ident("ADDS"),
+                Punct('('),
+                Ident("uint256"),
+                Ident("a"),
+                Punct(','),
+                Ident("address"),
+                Ident("b"),
+                Punct(')'),
+                Punct('='),
+                Punct('{'),
+                Punct('}')
+            ],
+            (
+                ast::Macro {
+                    name: ("ADDS", span),
+                    args: (Box::new([]), span),
+                    takes_returns: None,
+                    body: Box::new([]),
+                },
+                Some(
+                    Box::new([(DynSolType::Uint(256), span), (DynSolType::Address, span)])
+                        as Box<[_]>
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn parse_decorated_test() {
+        let span: Span = SimpleSpan::new(0, 0);
+        assert_ok!(
+            definition(),
+            vec![
+                Punct('#'),
+                Punct('['),
+                Ident("calldata"),
+                Punct('('),
+                String("0x0102".to_string()),
+                Punct(')'),
+                Punct(','),
+                Ident("value"),
+                Punct('('),
+                Dec("1"),
+                Punct(')'),
+                Punct(']'),
+                Punct('#'),
+                Punct('['),
+                Ident("storage"),
+                Punct('('),
+                Punct('['),
+                Ident("SLOT"),
+                Punct(']'),
+                Punct(','),
+                Hex("0x2"),
+                Punct(')'),
+                Punct(','),
+                Ident("expect_revert"),
+                Punct('('),
+                Ident("Unauthorized"),
+                Punct(')'),
+                Punct(']'),
+                Keyword("define"),
+                Ident("test"),
+                Ident("REVERTS"),
+                Punct('('),
+                Punct(')'),
+                Punct('='),
+                Punct('{'),
+                Punct('}')
+            ],
+            ast::Definition::Test(ast::Test {
+                decorators: Box::new([
+                    (ast::Decorator::Calldata(Box::new([0x01, 0x02])), span),
+                    (ast::Decorator::Value(uint!(1U256)), span),
+                    (
+                        ast::Decorator::Storage {
+                            slot: ast::WordRef::Constant(("SLOT", span)),
+                            value: ast::WordRef::Literal((uint!(2U256), span)),
+                        },
+                        span
+                    ),
+                    (
+                        ast::Decorator::ExpectRevert(Some(("Unauthorized", span))),
+                        span
+                    ),
+                ]),
+                r#macro: ast::Macro {
+                    name: ("REVERTS", span),
+                    args: (Box::new([]), span),
+                    takes_returns: None,
+                    body: Box::new([]),
+                },
+                fuzz: None,
+            })
+        );
+        assert_err!(
+            decorator(),
+            vec![
+                Ident("caller"),
+                Punct('('),
+                Hex("0x10000000000000000000000000000000000000000"),
+                Punct(')')
+            ],
+            "address overflows"
+        );
+    }
+
     #[test]
     fn parse_macro_statement() {
         let span: Span = SimpleSpan::new(0, 0);
@@ -578,6 +951,11 @@ mod tests {
             vec![Ident("__tablestart"), Punct('('), Ident("TABLE"), Punct(')')],
             ast::MacroStatement::Invoke(ast::Invoke::BuiltinTableStart(("TABLE", span)))
         );
+        assert_ok!(
+            macro_statement(),
+            vec![Ident("__argsoffset"), Punct('('), Punct(')')],
+            ast::MacroStatement::Invoke(ast::Invoke::BuiltinArgsOffset(span))
+        );
         assert_ok!(
             macro_statement(),
             vec![Ident("READ_ADDRESS"), Punct('('), Hex("0x4"), Punct(')')],
@@ -591,6 +969,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_signature_builtins() {
+        let span: Span = SimpleSpan::new(0, 0);
+        let signature = |name: &str, types: &[DynSolType]| ast::SolSignature {
+            name: name.to_owned(),
+            args: types
+                .iter()
+                .map(|ty| ast::SolParam {
+                    ty: (ty.clone(), span),
+                    name: None,
+                    indexed: false,
+                })
+                .collect(),
+        };
+
+        assert_ok!(
+            invoke(),
+            vec![
+                Ident("__FUNC_SIG"),
+                Punct('('),
+                String("transfer(address,uint256)".to_owned()),
+                Punct(')')
+            ],
+            ast::Invoke::BuiltinFuncSigLiteral((
+                signature("transfer", &[DynSolType::Address, DynSolType::Uint(256)]),
+                span
+            ))
+        );
+        assert_ok!(
+            invoke(),
+            vec![Ident("__EVENT_HASH"), Punct('('), String("Ping()".to_owned()), Punct(')')],
+            ast::Invoke::BuiltinEventHashLiteral((signature("Ping", &[]), span))
+        );
+        assert_err!(
+            invoke(),
+            vec![
+                Ident("__FUNC_SIG"),
+                Punct('('),
+                String("transfer(address,".to_owned()),
+                Punct(')')
+            ],
+            "invalid signature \"transfer(address,\""
+        );
+    }
+
     #[test]
     fn parse_instruction() {
         let span: Span = SimpleSpan::new(0, 0);
@@ -700,11 +1123,6 @@ mod tests {
             vec![Ident("address"),],
             (DynSolType::parse("address").unwrap(), span)
         );
-        assert_ok!(
-            sol_type(),
-            vec![Ident("address"), Ident("token")],
-            (DynSolType::parse("address").unwrap(), span)
-        );
         assert_ok!(
             sol_type(),
             vec![Ident("address"), Punct('['), Punct(']')],
@@ -752,6 +1170,31 @@ mod tests {
                 span
             )
         );
+        assert_ok!(
+            sol_type(),
+            vec![Ident("uint256"), Punct('['), Punct(']'), Punct('['), Dec("2"), Punct(']'),],
+            (DynSolType::parse("uint256[][2]").unwrap(), span)
+        );
+        assert_ok!(
+            sol_type(),
+            vec![Ident("bytes32"), Punct('['), Dec("3"), Punct(']'), Punct('['), Punct(']'),],
+            (DynSolType::parse("bytes32[3][]").unwrap(), span)
+        );
+        assert_ok!(
+            sol_type(),
+            vec![
+                Punct('('),
+                Ident("address"),
+                Punct(','),
+                Ident("uint256"),
+                Punct(')'),
+                Punct('['),
+                Punct(']'),
+                Punct('['),
+                Punct(']'),
+            ],
+            (DynSolType::parse("(address,uint256)[][]").unwrap(), span)
+        );
     }
 
     #[test]
@@ -769,6 +1212,15 @@ mod tests {
         );
     }
 
+    fn param<'src>(ty: &str, name: Option<&'src str>) -> ast::SolParam<'src> {
+        let span: Span = SimpleSpan::new(0, 0);
+        ast::SolParam {
+            ty: (DynSolType::parse(ty).unwrap(), span),
+            name: name.map(|name| (name, span)),
+            indexed: false,
+        }
+    }
+
     #[test]
     fn parse_sol_function() {
         let span: Span = SimpleSpan::new(0, 0);
@@ -788,8 +1240,9 @@ mod tests {
             ],
             ast::Definition::SolFunction(ast::SolFunction {
                 name: ("balanceOf", span),
-                args: Box::new([(DynSolType::parse("address").unwrap(), span)]),
-                rets: Box::new([(DynSolType::parse("uint256").unwrap(), span)]),
+                args: Box::new([param("address", None)]),
+                rets: Box::new([param("uint256", None)]),
+                state_mutability: ast::StateMutability::NonPayable,
             })
         );
         assert_ok!(
@@ -799,20 +1252,52 @@ mod tests {
                 Ident("balanceOf"),
                 Punct('('),
                 Ident("address"),
+                Ident("owner"),
                 Punct(')'),
                 Ident("public"),
                 Ident("view"),
                 Ident("returns"),
                 Punct('('),
                 Ident("uint256"),
+                Ident("balance"),
                 Punct(')')
             ],
             ast::Definition::SolFunction(ast::SolFunction {
                 name: ("balanceOf", span),
-                args: Box::new([(DynSolType::parse("address").unwrap(), span)]),
-                rets: Box::new([(DynSolType::parse("uint256").unwrap(), span)]),
+                args: Box::new([param("address", Some("owner"))]),
+                rets: Box::new([param("uint256", Some("balance"))]),
+                state_mutability: ast::StateMutability::View,
             })
         );
+        assert_ok!(
+            sol_function(),
+            vec![
+                Ident("function"),
+                Ident("deposit"),
+                Punct('('),
+                Punct(')'),
+                Ident("external"),
+                Ident("payable"),
+            ],
+            ast::Definition::SolFunction(ast::SolFunction {
+                name: ("deposit", span),
+                args: Box::new([]),
+                rets: Box::new([]),
+                state_mutability: ast::StateMutability::Payable,
+            })
+        );
+        assert_err!(
+            sol_function(),
+            vec![
+                Ident("function"),
+                Ident("deposit"),
+                Punct('('),
+                Punct(')'),
+                Ident("view"),
+                Ident("payable"),
+            ],
+            "only one state mutability can be given"
+        );
     }
 
     #[test]
@@ -834,10 +1319,45 @@ mod tests {
             ast::Definition::SolEvent(ast::SolEvent {
                 name: ("Transfer", span),
                 args: Box::new([
-                    (DynSolType::parse("address").unwrap(), span),
-                    (DynSolType::parse("address").unwrap(), span),
-                    (DynSolType::parse("uint256").unwrap(), span),
+                    param("address", None),
+                    param("address", None),
+                    param("uint256", None),
                 ]),
+                anonymous: false,
+            })
+        );
+        assert_ok!(
+            sol_event(),
+            vec![
+                Ident("event"),
+                Ident("Transfer"),
+                Punct('('),
+                Ident("address"),
+                Ident("indexed"),
+                Ident("from"),
+                Punct(','),
+                Ident("address"),
+                Ident("indexed"),
+                Punct(','),
+                Ident("uint256"),
+                Ident("amount"),
+                Punct(')'),
+                Ident("anonymous")
+            ],
+            ast::Definition::SolEvent(ast::SolEvent {
+                name: ("Transfer", span),
+                args: Box::new([
+                    ast::SolParam {
+                        indexed: true,
+                        ..param("address", Some("from"))
+                    },
+                    ast::SolParam {
+                        indexed: true,
+                        ..param("address", None)
+                    },
+                    param("uint256", Some("amount")),
+                ]),
+                anonymous: true,
             })
         );
     }
@@ -851,8 +1371,20 @@ mod tests {
             vec![Ident("error"), Ident("PanicError"), Punct('('), Ident("uint256"), Punct(')')],
             ast::Definition::SolError(ast::SolError {
                 name: ("PanicError", span),
-                args: Box::new([(DynSolType::parse("uint256").unwrap(), span)]),
+                args: Box::new([param("uint256", None)]),
             })
         );
+        assert_err!(
+            sol_error(),
+            vec![
+                Ident("error"),
+                Ident("PanicError"),
+                Punct('('),
+                Ident("uint256"),
+                Ident("indexed"),
+                Punct(')')
+            ],
+            "only event parameters can be indexed"
+        );
     }
 }
@@ -1,6 +1,5 @@
-use crate::Spanned;
-use alloy_dyn_abi::DynSolType;
-use alloy_primitives::{keccak256, FixedBytes, U256};
+use crate::{SolParam, Spanned};
+use alloy_primitives::{keccak256, FixedBytes, B256, U256};
 use evm_glue::opcodes::Opcode;
 
 pub(crate) fn u256_as_push_data<const N: usize>(value: U256) -> Result<[u8; N], String> {
@@ -56,18 +55,28 @@ pub fn u256_as_push(value: U256) -> Opcode {
     }
 }
 
-pub fn compute_selector(name: &Spanned<&str>, args: &[Spanned<DynSolType>]) -> FixedBytes<4> {
-    let arg_types: Vec<String> = args.iter().map(|arg| arg.0.to_string()).collect();
+/// Canonical signature of a function, event or error, e.g. `transfer(address,uint256)`.
+pub fn compute_signature(name: &Spanned<&str>, args: &[SolParam]) -> String {
+    let arg_types: Vec<String> = args.iter().map(|arg| arg.ty.0.to_string()).collect();
 
-    let signature = format!("{}({})", name.0, arg_types.join(","));
-    let hash = keccak256(signature.as_bytes());
+    format!("{}({})", name.0, arg_types.join(","))
+}
+
+pub fn compute_selector(name: &Spanned<&str>, args: &[SolParam]) -> FixedBytes<4> {
+    let hash = keccak256(compute_signature(name, args).as_bytes());
     FixedBytes::<4>::from_slice(&hash[..4])
 }
 
+/// Topic identifying an event, the hash of its signature.
+pub fn compute_event_topic(name: &Spanned<&str>, args: &[SolParam]) -> B256 {
+    keccak256(compute_signature(name, args).as_bytes())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ast::{SolError, SolFunction};
+    use crate::ast::{SolError, SolFunction, StateMutability};
+    use alloy_dyn_abi::DynSolType;
     use chumsky::span::Span;
 
     #[test]
@@ -76,17 +85,34 @@ mod tests {
         let func = SolFunction {
             name: Spanned::new("transfer", 0..8),
             args: Box::new([
-                Spanned::new(DynSolType::Address, 9..17),
-                Spanned::new(DynSolType::Uint(256), 18..26),
+                SolParam {
+                    ty: Spanned::new(DynSolType::Address, 9..17),
+                    name: None,
+                    indexed: false,
+                },
+                SolParam {
+                    ty: Spanned::new(DynSolType::Uint(256), 18..26),
+                    name: None,
+                    indexed: false,
+                },
             ]),
             rets: Box::new([]),
+            state_mutability: StateMutability::NonPayable,
         };
 
         let err = SolError {
             name: Spanned::new("TransferFailed", 0..15),
             args: Box::new([
-                Spanned::new(DynSolType::String, 16..21),
-                Spanned::new(DynSolType::Uint(256), 22..30),
+                SolParam {
+                    ty: Spanned::new(DynSolType::String, 16..21),
+                    name: None,
+                    indexed: false,
+                },
+                SolParam {
+                    ty: Spanned::new(DynSolType::Uint(256), 22..30),
+                    name: None,
+                    indexed: false,
+                },
             ]),
         };
 
@@ -105,4 +131,43 @@ mod tests {
         assert_eq!(func_selector, expected_func_selector);
         assert_eq!(err_selector, expected_err_selector);
     }
+
+    #[test]
+    fn computes_selectors_of_nested_arrays() {
+        let src =
+            "#define function batch((address,uint256)[][] transfers, uint256[][2]) view returns ()
+#define event Settled(bytes32[3][] indexed roots, (address,(uint8,bytes)[])[2] legs)
+#define error Mismatch((address,uint256)[][], bytes32[3][])";
+        let root = crate::parse(src).unwrap();
+        let signatures: Vec<_> = root
+            .0
+            .iter()
+            .map(|section| match section {
+                crate::RootSection::Definition(crate::Definition::SolFunction(f)) => (
+                    compute_signature(&f.name, &f.args),
+                    compute_selector(&f.name, &f.args).to_vec(),
+                ),
+                crate::RootSection::Definition(crate::Definition::SolEvent(e)) => (
+                    compute_signature(&e.name, &e.args),
+                    compute_event_topic(&e.name, &e.args).to_vec(),
+                ),
+                crate::RootSection::Definition(crate::Definition::SolError(e)) => (
+                    compute_signature(&e.name, &e.args),
+                    compute_selector(&e.name, &e.args).to_vec(),
+                ),
+                _ => unreachable!(),
+            })
+            .collect();
+
+        let expected = [
+            "batch((address,uint256)[][],uint256[][2])",
+            "Settled(bytes32[3][],(address,(uint8,bytes)[])[2])",
+            "Mismatch((address,uint256)[][],bytes32[3][])",
+        ];
+        for ((signature, id), expected) in signatures.iter().zip(expected) {
+            assert_eq!(signature, expected);
+            let hash = keccak256(expected.as_bytes());
+            assert_eq!(id[..], hash[..id.len()]);
+        }
+    }
 }
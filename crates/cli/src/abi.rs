@@ -0,0 +1,130 @@
+use alloy_dyn_abi::DynSolType;
+use huff_ast::{Definition, Root, RootSection, SolParam};
+use serde_json::{json, Value};
+
+/// Type of a parameter as named in JSON ABIs, tuples being `tuple` along with their components.
+fn abi_type(ty: &DynSolType) -> (String, Option<&[DynSolType]>) {
+    match ty {
+        DynSolType::Array(inner) => {
+            let (name, components) = abi_type(inner);
+            (format!("{}[]", name), components)
+        }
+        DynSolType::FixedArray(inner, len) => {
+            let (name, components) = abi_type(inner);
+            (format!("{}[{}]", name, len), components)
+        }
+        DynSolType::Tuple(components) => ("tuple".to_owned(), Some(components)),
+        ty => (ty.to_string(), None),
+    }
+}
+
+fn param(name: &str, ty: &DynSolType) -> Value {
+    let (ty, components) = abi_type(ty);
+    let mut value = json!({ "name": name, "type": ty });
+    if let Some(components) = components {
+        // Names of tuple components aren't kept by the parser.
+        value["components"] = components.iter().map(|ty| param("", ty)).collect();
+    }
+    value
+}
+
+fn params(args: &[SolParam]) -> Value {
+    args.iter()
+        .map(|arg| param(arg.name.map_or("", |(name, _)| name), &arg.ty.0))
+        .collect()
+}
+
+/// JSON ABI of the functions, events and errors defined in the file, in the order of definition.
+pub(crate) fn abi(ast: &Root) -> Value {
+    ast.0
+        .iter()
+        .filter_map(|section| match section {
+            RootSection::Definition(Definition::SolFunction(func)) => Some(json!({
+                "type": "function",
+                "name": func.name.0,
+                "inputs": params(&func.args),
+                "outputs": params(&func.rets),
+                "stateMutability": func.state_mutability.as_str(),
+            })),
+            RootSection::Definition(Definition::SolEvent(event)) => {
+                let mut inputs = params(&event.args);
+                for (input, arg) in inputs.as_array_mut().into_iter().flatten().zip(&event.args) {
+                    input["indexed"] = arg.indexed.into();
+                }
+                Some(json!({
+                    "type": "event",
+                    "name": event.name.0,
+                    "inputs": inputs,
+                    "anonymous": event.anonymous,
+                }))
+            }
+            RootSection::Definition(Definition::SolError(error)) => Some(json!({
+                "type": "error",
+                "name": error.name.0,
+                "inputs": params(&error.args),
+            })),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use huff_ast::parse;
+
+    #[test]
+    fn generates_abi() {
+        let ast = parse(
+            "#define function transfer(address to, uint256) nonpayable returns (bool)
+#define function positions((address,uint256)[2] owners) view returns (uint256[] amounts)
+#define event Approval(address indexed owner, address indexed, uint256 amount) anonymous
+#define error Unauthorized(address caller)",
+        )
+        .unwrap();
+        assert_eq!(
+            abi(&ast),
+            json!([
+                {
+                    "type": "function",
+                    "name": "transfer",
+                    "inputs": [
+                        { "name": "to", "type": "address" },
+                        { "name": "", "type": "uint256" }
+                    ],
+                    "outputs": [{ "name": "", "type": "bool" }],
+                    "stateMutability": "nonpayable"
+                },
+                {
+                    "type": "function",
+                    "name": "positions",
+                    "inputs": [{
+                        "name": "owners",
+                        "type": "tuple[2]",
+                        "components": [
+                            { "name": "", "type": "address" },
+                            { "name": "", "type": "uint256" }
+                        ]
+                    }],
+                    "outputs": [{ "name": "amounts", "type": "uint256[]" }],
+                    "stateMutability": "view"
+                },
+                {
+                    "type": "event",
+                    "name": "Approval",
+                    "inputs": [
+                        { "name": "owner", "type": "address", "indexed": true },
+                        { "name": "", "type": "address", "indexed": true },
+                        { "name": "amount", "type": "uint256", "indexed": false }
+                    ],
+                    "anonymous": true
+                },
+                {
+                    "type": "error",
+                    "name": "Unauthorized",
+                    "inputs": [{ "name": "caller", "type": "address" }]
+                }
+            ])
+        );
+    }
+}
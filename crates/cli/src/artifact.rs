@@ -0,0 +1,161 @@
+use crate::{abi::abi, generate};
+use alloy_primitives::hex;
+use huff_ast::{compute_selector, Definition, Root, RootSection};
+use huff_compilation::{generate_default_constructor, source_map, CompileGlobals, PcTable};
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+
+/// An entry point compiled for deployment, along with the code it deploys.
+pub(crate) struct Contract {
+    /// The entry point's code, wrapped in the default constructor if asked for.
+    pub code: Vec<u8>,
+    pub source_map: String,
+    /// Code deployed by `code`, empty if unknown.
+    pub deployed: Vec<u8>,
+    pub deployed_source_map: String,
+}
+
+impl Contract {
+    /// Compiles the entry point, the deployed code being the entry point's own code with the
+    /// default constructor and the `runtime` macro's otherwise.
+    pub(crate) fn compile<'a>(
+        config: &mut CompileGlobals<'a, 'a>,
+        entry_point: &str,
+        default_constructor: bool,
+        runtime: Option<&str>,
+    ) -> Self {
+        let (code, pc_table) = generate(config, entry_point);
+        if default_constructor {
+            let initcode = config.assemble(&generate_default_constructor(code.clone()));
+            return Self {
+                // The default constructor isn't generated from any source.
+                source_map: source_map(&initcode, &PcTable::new()),
+                code: initcode,
+                deployed_source_map: source_map(&code, &pc_table),
+                deployed: code,
+            };
+        }
+        let (deployed, deployed_pc_table) = match runtime {
+            Some(runtime) => generate(config, runtime),
+            None => (Vec::new(), PcTable::new()),
+        };
+        Self {
+            source_map: source_map(&code, &pc_table),
+            code,
+            deployed_source_map: source_map(&deployed, &deployed_pc_table),
+            deployed,
+        }
+    }
+}
+
+/// Code along with its source map, Huff has no libraries to link.
+fn bytecode(code: &[u8], source_map: &str) -> Value {
+    json!({
+        "object": hex::encode_prefixed(code),
+        "sourceMap": source_map,
+        "linkReferences": {},
+    })
+}
+
+/// Selectors of the file's functions by their signature.
+fn method_identifiers(ast: &Root) -> Value {
+    let identifiers: Map<String, Value> = ast
+        .0
+        .iter()
+        .filter_map(|section| match section {
+            RootSection::Definition(def @ Definition::SolFunction(func)) => Some((
+                def.abi_signature()?,
+                hex::encode(compute_selector(&func.name, &func.args)).into(),
+            )),
+            _ => None,
+        })
+        .collect();
+    identifiers.into()
+}
+
+/// Foundry style artifact of the contract.
+pub(crate) fn artifact(ast: &Root, contract: &Contract) -> Value {
+    let mut deployed_bytecode = bytecode(&contract.deployed, &contract.deployed_source_map);
+    deployed_bytecode["immutableReferences"] = json!({});
+    json!({
+        "abi": abi(ast),
+        "bytecode": bytecode(&contract.code, &contract.source_map),
+        "deployedBytecode": deployed_bytecode,
+        "methodIdentifiers": method_identifiers(ast),
+    })
+}
+
+/// Writes the artifact to `<OUT>/<FILE>/<NAME>.json`, `NAME` being the file's name without its
+/// extension, where Foundry's `vm.getCode("<FILE>:<NAME>")` looks for it.
+pub(crate) fn write(out: &Path, filename: &str, artifact: &Value) -> std::io::Result<PathBuf> {
+    let path = Path::new(filename);
+    let (Some(file), Some(name)) = (path.file_name(), path.file_stem()) else {
+        return Err(std::io::Error::other(format!(
+            "no file name in '{}'",
+            filename
+        )));
+    };
+    let dir = out.join(file);
+    std::fs::create_dir_all(&dir)?;
+    let artifact_path = dir.join(name).with_extension("json");
+    std::fs::write(&artifact_path, serde_json::to_string_pretty(artifact)?)?;
+    Ok(artifact_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze_definitions;
+    use huff_ast::parse;
+
+    #[test]
+    fn generates_artifact() {
+        let ast = parse(
+            "#define function transfer(address to, uint256 amount) nonpayable returns (bool)
+#define function balanceOf(address) view returns (uint256)
+#define event Transfer(address indexed from, address indexed to, uint256 amount)",
+        )
+        .unwrap();
+        let contract = Contract {
+            code: vec![0x60, 0x00],
+            source_map: "0:4:0:-".to_owned(),
+            deployed: vec![0x00],
+            deployed_source_map: "5:2:0:-".to_owned(),
+        };
+        let artifact = artifact(&ast, &contract);
+
+        assert_eq!(artifact["abi"], abi(&ast));
+        assert_eq!(
+            artifact["bytecode"],
+            json!({ "object": "0x6000", "sourceMap": "0:4:0:-", "linkReferences": {} })
+        );
+        assert_eq!(artifact["deployedBytecode"]["object"], "0x00");
+        assert_eq!(
+            artifact["methodIdentifiers"],
+            json!({ "transfer(address,uint256)": "a9059cbb", "balanceOf(address)": "70a08231" })
+        );
+    }
+
+    #[test]
+    fn appends_trailer_to_runtime() {
+        let ast = parse(
+            "#define macro MAIN() = takes(0) returns(0) { stop }
+#define macro CONSTRUCTOR() = takes(0) returns(0) {
+    __codesize(MAIN) __codeoffset(MAIN) 0x00 codecopy
+    __codesize(MAIN) 0x00 return
+}",
+        )
+        .unwrap();
+        let defs = analyze_definitions(&ast, ["CONSTRUCTOR", "MAIN"], false, &[]).unwrap();
+        let mut config = CompileGlobals::new(true, true, defs, &[]);
+        config.metadata_trailer = Some(vec![0xaa, 0xbb]);
+        config.trailer_macro = Some("MAIN");
+        let contract = Contract::compile(&mut config, "CONSTRUCTOR", false, Some("MAIN"));
+
+        assert_eq!(contract.deployed, [0x00, 0xaa, 0xbb]);
+        // The deployed code is included last, the constructor's code getting no trailer itself.
+        assert!(contract.code.ends_with(&contract.deployed));
+        let trailers = contract.code.windows(2).filter(|w| w == &[0xaa, 0xbb]);
+        assert_eq!(trailers.count(), 1);
+    }
+}
@@ -0,0 +1,35 @@
+use crate::fuzz::FuzzConfig;
+use huff_fmt::FmtConfig;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Name of the project file holding tool settings.
+pub const PROJECT_FILE: &str = "huff.toml";
+
+/// Project wide settings, read from the closest `huff.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub fmt: FmtConfig,
+    pub fuzz: FuzzConfig,
+}
+
+impl ProjectConfig {
+    /// Loads the project file from the current directory or the closest ancestor that has one.
+    /// Falls back to the default settings if there's no project file.
+    pub fn load() -> Result<Self, String> {
+        let cwd = std::env::current_dir().map_err(|e| e.to_string())?;
+        let Some(path) = find_project_file(&cwd) else {
+            return Ok(Self::default());
+        };
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(&content).map_err(|e| format!("Invalid {}: {}", path.display(), e))
+    }
+}
+
+fn find_project_file(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file())
+}
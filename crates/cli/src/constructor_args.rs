@@ -0,0 +1,60 @@
+use alloy_dyn_abi::{DynSolType, DynSolValue};
+
+/// ABI encodes the constructor arguments, `signature` being `constructor(<types>)` or just the
+/// parenthesized types.
+pub(crate) fn encode(signature: &str, args: &[String]) -> Result<Vec<u8>, String> {
+    let types = signature
+        .strip_prefix("constructor")
+        .unwrap_or(signature)
+        .trim();
+    let Ok(DynSolType::Tuple(types)) = DynSolType::parse(types) else {
+        return Err(format!(
+            "Invalid constructor signature {:?}, expected e.g. \"constructor(address,uint256)\"",
+            signature
+        ));
+    };
+    if types.len() != args.len() {
+        return Err(format!(
+            "Constructor takes {} argument{}, got {}",
+            types.len(),
+            if types.len() == 1 { "" } else { "s" },
+            args.len()
+        ));
+    }
+    let values = types
+        .iter()
+        .zip(args)
+        .map(|(ty, arg)| {
+            ty.coerce_str(arg)
+                .map_err(|err| format!("Invalid {} argument {:?}: {}", ty, arg, err))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(DynSolValue::Tuple(values).abi_encode_params())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::hex;
+
+    #[test]
+    fn encodes_args() {
+        let args = ["0x000000000000000000000000000000000000bEEF".to_owned(), "42".to_owned()];
+        assert_eq!(
+            hex::encode(encode("constructor(address,uint256)", &args).unwrap()),
+            "000000000000000000000000000000000000000000000000000000000000beef\
+             000000000000000000000000000000000000000000000000000000000000002a"
+        );
+        assert_eq!(encode("constructor()", &[]).unwrap(), Vec::<u8>::new());
+        assert_eq!(
+            encode("constructor(uint256)", &args),
+            Err("Constructor takes 1 argument, got 2".to_owned())
+        );
+        assert_eq!(
+            encode("constructor(uint256,bool,address)", &args),
+            Err("Constructor takes 3 arguments, got 2".to_owned())
+        );
+        assert!(encode("constructor(uint256,bool)", &args).is_err());
+        assert!(encode("uint256", &[]).is_err());
+    }
+}
@@ -0,0 +1,423 @@
+use crate::{
+    config::ProjectConfig, parse_source, read_source, run::CONTRACT, testing::run_tests,
+    CompileArgs,
+};
+use ariadne::{Color, Fmt};
+use clap::Args;
+use evm_glue::opcodes::Opcode;
+use huff_ast::{Definition, Instruction, MacroStatement, Root, RootSection, Span};
+use huff_compilation::PcTable;
+use huff_evm::{opcode::JUMPI, Inspector, Step};
+use std::{collections::BTreeMap, io::Write, path::PathBuf};
+
+#[derive(Args)]
+pub struct CoverageArgs {
+    #[clap(help = "Root huff file containing the tests")]
+    filename: String,
+
+    #[clap(
+        short = 'm',
+        long = "match",
+        help = "Only run tests whose name contains the pattern"
+    )]
+    filter: Option<String>,
+
+    #[clap(
+        long = "lcov",
+        help = "Path the LCOV report is written to",
+        default_value = "lcov.info"
+    )]
+    lcov: PathBuf,
+
+    #[command(flatten)]
+    compile: CompileArgs,
+}
+
+/// Records the instructions executed by the tested contract.
+#[derive(Default)]
+pub(crate) struct PcRecorder {
+    hits: BTreeMap<usize, u64>,
+    /// Times each `JUMPI` jumped and fell through, by program counter.
+    jumps: BTreeMap<usize, [u64; 2]>,
+    /// Depth and program counter of a `JUMPI` waiting for its outcome.
+    pending_jump: Option<(usize, usize)>,
+}
+
+impl Inspector for PcRecorder {
+    fn step(&mut self, step: &Step<'_>) {
+        if let Some((depth, pc)) = self.pending_jump.take() {
+            if depth == step.depth {
+                let fell_through = step.pc == pc + 1;
+                self.jumps.entry(pc).or_default()[usize::from(fell_through)] += 1;
+            }
+        }
+        if step.address != CONTRACT {
+            return;
+        }
+        *self.hits.entry(step.pc).or_default() += 1;
+        if step.opcode == JUMPI {
+            self.pending_jump = Some((step.depth, step.pc));
+        }
+    }
+}
+
+fn span_key(span: &Span) -> (usize, usize) {
+    (span.start, span.end)
+}
+
+/// Executions of macro statements and outcomes of their jumps, keyed by the statements' spans.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct Coverage {
+    /// Executions of the most executed instruction generated by each statement, invocations
+    /// including the instructions of the invoked macro.
+    statements: BTreeMap<(usize, usize), u64>,
+    /// Times each `JUMPI` statement jumped and fell through.
+    branches: BTreeMap<(usize, usize), [u64; 2]>,
+}
+
+impl Coverage {
+    pub fn from_recorded(pc_table: &PcTable, recorder: &PcRecorder) -> Self {
+        let mut coverage = Self::default();
+        for (pc, &hits) in &recorder.hits {
+            for (_, span) in pc_table
+                .get(pc)
+                .into_iter()
+                .flat_map(|frames| frames.iter())
+            {
+                let executions = coverage.statements.entry(span_key(span)).or_default();
+                *executions = (*executions).max(hits);
+            }
+        }
+        for (pc, jumps) in &recorder.jumps {
+            let Some((_, span)) = pc_table.get(pc).and_then(|frames| frames.last()) else {
+                continue;
+            };
+            let branches = coverage.branches.entry(span_key(span)).or_default();
+            branches[0] += jumps[0];
+            branches[1] += jumps[1];
+        }
+        coverage
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        for (span, hits) in &other.statements {
+            *self.statements.entry(*span).or_default() += hits;
+        }
+        for (span, jumps) in &other.branches {
+            let branches = self.branches.entry(*span).or_default();
+            branches[0] += jumps[0];
+            branches[1] += jumps[1];
+        }
+    }
+
+    fn hits(&self, span: &Span) -> u64 {
+        self.statements.get(&span_key(span)).copied().unwrap_or(0)
+    }
+
+    /// Jumps and fall throughs of the `JUMPI` statement, `None` if it never executed.
+    fn jumps(&self, span: &Span) -> Option<[u64; 2]> {
+        self.branches
+            .get(&span_key(span))
+            .copied()
+            .filter(|_| self.hits(span) > 0)
+    }
+}
+
+/// Coverage of a macro's own statements.
+struct MacroReport<'src> {
+    name: &'src str,
+    line: usize,
+    /// Line and executions of each statement.
+    statements: Vec<(usize, u64)>,
+    /// Line, name and executions of each label.
+    labels: Vec<(usize, &'src str, u64)>,
+    /// Line and outcomes of each `JUMPI`, `None` if it never executed.
+    branches: Vec<(usize, Option<[u64; 2]>)>,
+}
+
+impl MacroReport<'_> {
+    fn hits(&self) -> u64 {
+        self.statements
+            .iter()
+            .map(|(_, hits)| *hits)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn statements_hit(&self) -> usize {
+        self.statements.iter().filter(|(_, hits)| *hits > 0).count()
+    }
+
+    fn branches_hit(&self) -> usize {
+        self.branches
+            .iter()
+            .flat_map(|(_, jumps)| jumps.unwrap_or_default())
+            .filter(|&count| count > 0)
+            .count()
+    }
+}
+
+fn macro_reports<'src>(src: &str, ast: &Root<'src>, coverage: &Coverage) -> Vec<MacroReport<'src>> {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(src.match_indices('\n').map(|(idx, _)| idx + 1))
+        .collect();
+    let line = |span: &Span| line_starts.partition_point(|&start| start <= span.start);
+
+    ast.0
+        .iter()
+        .filter_map(|section| match section {
+            RootSection::Definition(Definition::Macro(m)) => Some(m),
+            _ => None,
+        })
+        .map(|m| {
+            let mut report = MacroReport {
+                name: m.name.0,
+                line: line(&m.name.1),
+                statements: Vec::new(),
+                labels: Vec::new(),
+                branches: Vec::new(),
+            };
+            for stmt in m.body.iter() {
+                let span = stmt.get_span();
+                let hits = coverage.hits(&span);
+                report.statements.push((line(&span), hits));
+                match stmt {
+                    MacroStatement::LabelDefinition((label, _)) => {
+                        report.labels.push((line(&span), *label, hits))
+                    }
+                    MacroStatement::Instruction(Instruction::Op((Opcode::JUMPI, _))) => {
+                        report.branches.push((line(&span), coverage.jumps(&span)))
+                    }
+                    _ => {}
+                }
+            }
+            report
+        })
+        .collect()
+}
+
+/// Writes the coverage in the LCOV tracefile format, `JUMPI`s being branch blocks whose branches
+/// are jumping and falling through.
+fn write_lcov(
+    out: &mut impl Write,
+    filename: &str,
+    reports: &[MacroReport],
+) -> std::io::Result<()> {
+    writeln!(out, "TN:")?;
+    writeln!(out, "SF:{}", filename)?;
+    for report in reports {
+        writeln!(out, "FN:{},{}", report.line, report.name)?;
+    }
+    for report in reports {
+        writeln!(out, "FNDA:{},{}", report.hits(), report.name)?;
+    }
+    writeln!(out, "FNF:{}", reports.len())?;
+    writeln!(
+        out,
+        "FNH:{}",
+        reports.iter().filter(|report| report.hits() > 0).count()
+    )?;
+
+    let branches = reports.iter().flat_map(|report| &report.branches);
+    for (block, (line, jumps)) in branches.clone().enumerate() {
+        for branch in 0..2 {
+            match jumps {
+                Some(jumps) => {
+                    writeln!(out, "BRDA:{},{},{},{}", line, block, branch, jumps[branch])?
+                }
+                None => writeln!(out, "BRDA:{},{},{},-", line, block, branch)?,
+            }
+        }
+    }
+    writeln!(out, "BRF:{}", branches.count() * 2)?;
+    writeln!(
+        out,
+        "BRH:{}",
+        reports.iter().map(MacroReport::branches_hit).sum::<usize>()
+    )?;
+
+    // Statements sharing a line count as one, executed as often as the most executed of them.
+    let mut lines: BTreeMap<usize, u64> = BTreeMap::new();
+    for (line, hits) in reports.iter().flat_map(|report| &report.statements) {
+        let line_hits = lines.entry(*line).or_default();
+        *line_hits = (*line_hits).max(*hits);
+    }
+    for (line, hits) in &lines {
+        writeln!(out, "DA:{},{}", line, hits)?;
+    }
+    writeln!(out, "LF:{}", lines.len())?;
+    writeln!(
+        out,
+        "LH:{}",
+        lines.values().filter(|&&hits| hits > 0).count()
+    )?;
+    writeln!(out, "end_of_record")
+}
+
+fn percentage(hit: usize, total: usize) -> String {
+    let text = match total {
+        0 => "-".to_owned(),
+        _ => format!("{:.2}%", hit as f64 * 100.0 / total as f64),
+    };
+    let color = if hit == total {
+        Color::Green
+    } else if hit == 0 {
+        Color::Red
+    } else {
+        Color::Yellow
+    };
+    format!("{}", text.fg(color))
+}
+
+fn print_summary(filename: &str, reports: &[MacroReport]) {
+    for report in reports {
+        let (statements, branches) = (report.statements.len(), report.branches.len() * 2);
+        println!(
+            "{}: statements {}/{} ({}), branches {}/{} ({})",
+            report.name,
+            report.statements_hit(),
+            statements,
+            percentage(report.statements_hit(), statements),
+            report.branches_hit(),
+            branches,
+            percentage(report.branches_hit(), branches)
+        );
+        for (line, label, _) in report.labels.iter().filter(|(_, _, hits)| *hits == 0) {
+            println!(
+                "    {}:{}: label {} never reached",
+                filename,
+                line,
+                label.fg(Color::Red)
+            );
+        }
+        for (line, jumps) in &report.branches {
+            let missed = match jumps {
+                None => "never executed",
+                Some([0, _]) => "never jumps",
+                Some([_, 0]) => "always jumps",
+                Some(_) => continue,
+            };
+            println!(
+                "    {}:{}: {} {}",
+                filename,
+                line,
+                "jumpi".fg(Color::Red),
+                missed
+            );
+        }
+    }
+}
+
+pub fn run(args: CoverageArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let fuzz = ProjectConfig::load()?.fuzz;
+    let src = read_source(&args.filename)?;
+    let mut included = Vec::new();
+    let (files, ast) = parse_source(&args.filename, &src, &mut included);
+    let (outcomes, _) = run_tests(
+        files,
+        &ast,
+        args.filter.as_deref(),
+        &args.compile,
+        &fuzz,
+        true,
+    );
+
+    let failed: Vec<_> = outcomes
+        .iter()
+        .filter(|outcome| outcome.failure.is_some())
+        .map(|outcome| outcome.name)
+        .collect();
+    println!(
+        "Ran {} test{} for {}",
+        outcomes.len(),
+        if outcomes.len() == 1 { "" } else { "s" },
+        args.filename
+    );
+    if !failed.is_empty() {
+        println!(
+            "{}: failing tests still count towards coverage: {}",
+            "Warning".fg(Color::Yellow),
+            failed.join(", ")
+        );
+    }
+
+    let mut coverage = Coverage::default();
+    for outcome in &outcomes {
+        coverage.merge(outcome.coverage.as_ref().expect("coverage was recorded"));
+    }
+    let reports = macro_reports(&src, &ast, &coverage);
+    print_summary(&args.filename, &reports);
+
+    let mut lcov = std::io::BufWriter::new(std::fs::File::create(&args.lcov)?);
+    write_lcov(&mut lcov, &args.filename, &reports)?;
+    lcov.flush()?;
+    println!("Wrote LCOV report to {}", args.lcov.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use huff_ast::parse;
+
+    const SRC: &str = "#define macro CHECK() = {
+    iszero fail jumpi
+    0x01
+    fail:
+}";
+
+    #[test]
+    fn reports_statements_and_branches() {
+        let ast = parse(SRC).unwrap();
+        let RootSection::Definition(Definition::Macro(m)) = &ast.0[0] else {
+            unreachable!()
+        };
+        let spans: Vec<_> = m.body.iter().map(|stmt| stmt.get_span()).collect();
+        let frames = |idx: usize| vec![("CHECK", spans[idx])].into_boxed_slice();
+        // ISZERO PUSH1 JUMPI PUSH1 JUMPDEST
+        let pc_table: PcTable = [0, 1, 3, 4, 6]
+            .into_iter()
+            .enumerate()
+            .map(|(idx, pc)| (pc, frames(idx)))
+            .collect();
+
+        let mut recorder = PcRecorder::default();
+        recorder.hits.extend([(0, 2), (1, 2), (3, 2), (4, 2)]);
+        recorder.jumps.insert(3, [0, 2]);
+        let mut coverage = Coverage::from_recorded(&pc_table, &recorder);
+        let other_run = coverage.clone();
+        coverage.merge(&other_run);
+
+        let reports = macro_reports(SRC, &ast, &coverage);
+        let [report] = &reports[..] else {
+            unreachable!()
+        };
+        assert_eq!(report.statements, [(2, 4), (2, 4), (2, 4), (3, 4), (4, 0)]);
+        assert_eq!(report.labels, [(4, "fail", 0)]);
+        assert_eq!(report.branches, [(2, Some([0, 4]))]);
+
+        let mut lcov = Vec::new();
+        write_lcov(&mut lcov, "check.huff", &reports).unwrap();
+        assert_eq!(
+            String::from_utf8(lcov).unwrap(),
+            "TN:
+SF:check.huff
+FN:1,CHECK
+FNDA:4,CHECK
+FNF:1
+FNH:1
+BRDA:2,0,0,0
+BRDA:2,0,1,4
+BRF:2
+BRH:1
+DA:2,4
+DA:3,4
+DA:4,0
+LF:3
+LH:2
+end_of_record
+"
+        );
+    }
+}
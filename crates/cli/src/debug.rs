@@ -0,0 +1,399 @@
+use crate::{
+    analyze,
+    decode::AbiDecoder,
+    generate, parse_source, read_source,
+    run::{execute, CallArgs, Execution, CONTRACT},
+    CompileArgs,
+};
+use alloy_primitives::{hex, U256};
+use ariadne::{Color, Fmt};
+use clap::Args;
+use huff_ast::Span;
+use huff_compilation::PcTable;
+use huff_evm::{opcode, Inspector, Status, Step};
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, Write},
+};
+
+/// Source lines shown before and after the current one.
+const CONTEXT_LINES: usize = 2;
+
+const HELP: &str = "Commands:
+  s, step          Step to the next statement, into invoked macros
+  n, next          Step to the next statement, over invoked macros
+  o, out           Step out of the current macro
+  i, stepi         Step a single instruction
+  c, continue      Run until a breakpoint or the end
+  b <label|line>   Break at a label or a line of the file
+  d, delete        Delete all breakpoints
+  p, print         Print the current state
+  q, quit          Quit the debugger
+An empty line repeats the last command.";
+
+#[derive(Args)]
+pub struct DebugArgs {
+    #[clap(help = "Root huff file to compile")]
+    filename: String,
+
+    #[clap(help = "Name of the macro to compile and debug as the contract's runtime code")]
+    entry_point: String,
+
+    #[command(flatten)]
+    call: CallArgs,
+
+    #[command(flatten)]
+    compile: CompileArgs,
+}
+
+/// Machine state right before an instruction of the traced execution.
+#[derive(Debug, Clone, Default)]
+struct TraceStep {
+    depth: usize,
+    /// Whether the debugged code is being executed, rather than code it called into.
+    in_contract: bool,
+    pc: usize,
+    opcode: u8,
+    gas_remaining: u64,
+    stack: Vec<U256>,
+    memory: Vec<u8>,
+    storage: BTreeMap<U256, U256>,
+}
+
+/// Records every step of the execution.
+#[derive(Default)]
+struct Tracer {
+    steps: Vec<TraceStep>,
+}
+
+impl Inspector for Tracer {
+    fn step(&mut self, step: &Step<'_>) {
+        self.steps.push(TraceStep {
+            depth: step.depth,
+            in_contract: step.depth == 0 && step.address == CONTRACT,
+            pc: step.pc,
+            opcode: step.opcode,
+            gas_remaining: step.gas_remaining,
+            stack: step.stack.to_vec(),
+            memory: step.memory.to_vec(),
+            storage: step.storage.clone(),
+        });
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Breakpoint {
+    Label(String),
+    Line(usize),
+}
+
+/// Navigates a recorded execution by the macro statements it executes.
+struct Debugger<'a> {
+    src: &'a str,
+    pc_table: &'a PcTable<'a>,
+    steps: &'a [TraceStep],
+    line_starts: Vec<usize>,
+    /// Index of the current step, the length of the trace once the execution ended.
+    current: usize,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl<'a> Debugger<'a> {
+    fn new(src: &'a str, pc_table: &'a PcTable<'a>, steps: &'a [TraceStep]) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        let mut debugger = Self {
+            src,
+            pc_table,
+            steps,
+            line_starts,
+            current: 0,
+            breakpoints: Vec::new(),
+        };
+        // Start at the first statement rather than code without a source, if any.
+        if !debugger.enters_statement(0) {
+            let first = debugger.step_in();
+            if first < steps.len() {
+                debugger.current = first;
+            }
+        }
+        debugger
+    }
+
+    fn line(&self, span: &Span) -> usize {
+        self.line_starts
+            .partition_point(|&start| start <= span.start)
+    }
+
+    /// Macro frames of the statement the step was generated from, `None` for code without source.
+    fn frames(&self, idx: usize) -> Option<&'a [(&'a str, Span)]> {
+        let step = self.steps.get(idx)?;
+        if !step.in_contract {
+            return None;
+        }
+        self.pc_table.get(&step.pc).map(|frames| &frames[..])
+    }
+
+    /// Whether the step starts executing a statement.
+    fn enters_statement(&self, idx: usize) -> bool {
+        self.frames(idx).is_some() && (idx == 0 || self.frames(idx) != self.frames(idx - 1))
+    }
+
+    fn at_breakpoint(&self, idx: usize) -> bool {
+        let Some(&(_, span)) = self.frames(idx).and_then(|frames| frames.last()) else {
+            return false;
+        };
+        self.breakpoints.iter().any(|breakpoint| match breakpoint {
+            Breakpoint::Label(label) => {
+                self.steps[idx].opcode == opcode::JUMPDEST
+                    && self.src.get(span.start..span.end) == Some(label.as_str())
+            }
+            Breakpoint::Line(line) => self.line(&span) == *line,
+        })
+    }
+
+    /// Index of the next step entering a statement whose frames satisfy `target`, or a
+    /// breakpoint. The length of the trace if there's none.
+    fn advance(&self, target: impl Fn(&Self, &[(&str, Span)]) -> bool) -> usize {
+        (self.current + 1..self.steps.len())
+            .find(|&idx| {
+                self.enters_statement(idx)
+                    && (self.at_breakpoint(idx) || target(self, self.frames(idx).unwrap()))
+            })
+            .unwrap_or(self.steps.len())
+    }
+
+    fn step_in(&self) -> usize {
+        self.advance(|_, _| true)
+    }
+
+    fn step_over(&self) -> usize {
+        let depth = self.frames(self.current).map_or(usize::MAX, <[_]>::len);
+        self.advance(|_, frames| frames.len() <= depth)
+    }
+
+    fn step_out(&self) -> usize {
+        let depth = self.frames(self.current).map_or(usize::MAX, <[_]>::len);
+        self.advance(|_, frames| frames.len() < depth)
+    }
+
+    fn resume(&self) -> usize {
+        self.advance(|_, _| false)
+    }
+
+    fn print_state(&self) {
+        let Some(step) = self.steps.get(self.current) else {
+            return;
+        };
+        let name = opcode::name(step.opcode).unwrap_or_else(|| format!("{:#04x}", step.opcode));
+        println!(
+            "[step {}/{}] pc: {:#x} {} gas: {}",
+            self.current + 1,
+            self.steps.len(),
+            step.pc,
+            name.fg(Color::Cyan),
+            step.gas_remaining
+        );
+
+        match self.frames(self.current) {
+            Some(frames) => {
+                let chain: Vec<_> = frames.iter().map(|(m, _)| *m).collect();
+                println!("{}", chain.join(" > ").fg(Color::Yellow));
+                let (_, span) = frames[frames.len() - 1];
+                let line = self.line(&span);
+                let lines: Vec<_> = self.src.lines().collect();
+                for idx in line.saturating_sub(CONTEXT_LINES).max(1)
+                    ..=(line + CONTEXT_LINES).min(lines.len())
+                {
+                    let text = format!("{:>5} | {}", idx, lines[idx - 1]);
+                    if idx == line {
+                        println!("{} {}", ">".fg(Color::Green), text.fg(Color::Green));
+                    } else {
+                        println!("  {}", text);
+                    }
+                }
+            }
+            None => println!("(no source, call depth {})", step.depth),
+        }
+
+        println!("Stack (top first):");
+        for (idx, value) in step.stack.iter().rev().enumerate() {
+            println!("  {:>2}: {:#x}", idx, value);
+        }
+        if !step.memory.is_empty() {
+            println!("Memory:");
+            for (idx, word) in step.memory.chunks(32).enumerate() {
+                println!("  {:#06x}: {}", idx * 32, hex::encode(word));
+            }
+        }
+        if !step.storage.is_empty() {
+            println!("Storage:");
+            for (slot, value) in &step.storage {
+                println!("  {:#x}: {:#x}", slot, value);
+            }
+        }
+    }
+}
+
+pub fn run(args: DebugArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let src = read_source(&args.filename)?;
+    let mut included = Vec::new();
+    let (files, ast) = parse_source(&args.filename, &src, &mut included);
+    let mut config = analyze(
+        files,
+        &ast,
+        [args.entry_point.as_str()],
+        false,
+        &args.compile,
+    );
+    let (code, pc_table) = generate(&mut config, &args.entry_point);
+
+    let mut tracer = Tracer::default();
+    let Execution { result, .. } = execute(
+        args.compile.evm_version.into(),
+        code,
+        &args.call,
+        &mut tracer,
+        |_| {},
+    )?;
+    let decoder = AbiDecoder::new(&ast);
+
+    let mut debugger = Debugger::new(&src, &pc_table, &tracer.steps);
+    println!(
+        "Debugging {} ({} steps), `h` for help",
+        args.entry_point,
+        tracer.steps.len()
+    );
+    debugger.print_state();
+
+    let mut last_command = String::new();
+    let mut lines = std::io::stdin().lock().lines();
+    loop {
+        print!("(huff-debug) ");
+        std::io::stdout().flush()?;
+        let Some(line) = lines.next().transpose()? else {
+            break;
+        };
+        let line = match line.trim() {
+            "" => last_command.clone(),
+            line => line.to_owned(),
+        };
+        last_command.clone_from(&line);
+
+        let mut words = line.split_whitespace();
+        let next = match (words.next().unwrap_or_default(), words.next()) {
+            ("s" | "step", None) => debugger.step_in(),
+            ("n" | "next", None) => debugger.step_over(),
+            ("o" | "out", None) => debugger.step_out(),
+            ("i" | "stepi", None) => (debugger.current + 1).min(tracer.steps.len()),
+            ("c" | "continue", None) => debugger.resume(),
+            ("b" | "break", Some(location)) => {
+                let breakpoint = match location.parse() {
+                    Ok(line) => Breakpoint::Line(line),
+                    Err(_) => Breakpoint::Label(location.to_owned()),
+                };
+                println!(
+                    "Breakpoint {} at {}",
+                    debugger.breakpoints.len() + 1,
+                    location
+                );
+                debugger.breakpoints.push(breakpoint);
+                continue;
+            }
+            ("d" | "delete", None) => {
+                debugger.breakpoints.clear();
+                continue;
+            }
+            ("p" | "print", None) => {
+                debugger.print_state();
+                continue;
+            }
+            ("q" | "quit", None) => break,
+            _ => {
+                println!("{}", HELP);
+                continue;
+            }
+        };
+
+        debugger.current = next;
+        if next == tracer.steps.len() {
+            match &result.status {
+                Status::Success => println!(
+                    "{}, returned {}",
+                    "Execution finished".fg(Color::Green),
+                    hex::encode_prefixed(&result.output)
+                ),
+                Status::Revert => println!(
+                    "{}, reverted with {}",
+                    "Execution finished".fg(Color::Red),
+                    decoder
+                        .decode_error(&result.output)
+                        .unwrap_or_else(|| hex::encode_prefixed(&result.output))
+                ),
+                Status::Halt(reason) => {
+                    println!(
+                        "{}, halted: {}",
+                        "Execution finished".fg(Color::Red),
+                        reason
+                    )
+                }
+            }
+            continue;
+        }
+        debugger.print_state();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: &str = "#define macro INNER() = {
+    0x01
+}
+#define macro MAIN() = {
+    INNER()
+    done:
+}";
+
+    #[test]
+    fn steps_by_statement() {
+        let span = |text: &str| {
+            let start = SRC.find(text).unwrap();
+            Span::new(start, start + text.len())
+        };
+        let invoke = ("MAIN", span("INNER()"));
+        // PUSH1 0x01, JUMPDEST, then code without source.
+        let pc_table: PcTable = BTreeMap::from([
+            (0, vec![invoke, ("INNER", span("0x01"))].into_boxed_slice()),
+            (2, vec![("MAIN", span("done"))].into_boxed_slice()),
+        ]);
+        let step = |pc, opcode| TraceStep {
+            in_contract: true,
+            pc,
+            opcode,
+            ..Default::default()
+        };
+        let steps = [step(0, opcode::PUSH1), step(2, opcode::JUMPDEST), step(3, opcode::STOP)];
+
+        let mut debugger = Debugger::new(SRC, &pc_table, &steps);
+        assert_eq!(debugger.current, 0);
+        assert_eq!(debugger.step_in(), 1);
+        assert_eq!(debugger.step_out(), 1);
+        debugger.current = 1;
+        assert_eq!(debugger.step_in(), 3);
+        assert_eq!(debugger.step_over(), 3);
+
+        debugger.current = 0;
+        assert_eq!(debugger.resume(), 3);
+        debugger
+            .breakpoints
+            .push(Breakpoint::Label("done".to_owned()));
+        assert_eq!(debugger.resume(), 1);
+        debugger.breakpoints = vec![Breakpoint::Line(6)];
+        assert_eq!(debugger.resume(), 1);
+    }
+}
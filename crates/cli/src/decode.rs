@@ -0,0 +1,224 @@
+use alloy_dyn_abi::{DynSolType, DynSolValue};
+use alloy_primitives::{hex, FixedBytes, Log, B256};
+use huff_ast::{compute_event_topic, compute_selector, Definition, Root, RootSection};
+
+/// Selector of the `Error(string)` revert reason.
+const ERROR_STRING: FixedBytes<4> = FixedBytes([0x08, 0xc3, 0x79, 0xa0]);
+/// Selector of the `Panic(uint256)` error.
+const PANIC: FixedBytes<4> = FixedBytes([0x4e, 0x48, 0x7b, 0x71]);
+
+struct Signature<'src, S> {
+    id: S,
+    name: &'src str,
+    types: Vec<DynSolType>,
+    /// Which of the parameters are indexed, empty for errors.
+    indexed: Vec<bool>,
+}
+
+/// Decodes revert data and logs with the errors and events defined in a file.
+pub(crate) struct AbiDecoder<'src> {
+    errors: Vec<Signature<'src, FixedBytes<4>>>,
+    events: Vec<Signature<'src, B256>>,
+}
+
+impl<'src> AbiDecoder<'src> {
+    pub fn new(ast: &Root<'src>) -> Self {
+        let mut errors = vec![
+            Signature {
+                id: ERROR_STRING,
+                name: "Error",
+                types: vec![DynSolType::String],
+                indexed: vec![],
+            },
+            Signature {
+                id: PANIC,
+                name: "Panic",
+                types: vec![DynSolType::Uint(256)],
+                indexed: vec![],
+            },
+        ];
+        let mut events = Vec::new();
+        for section in ast.0.iter() {
+            match section {
+                RootSection::Definition(Definition::SolError(error)) => errors.push(Signature {
+                    id: compute_selector(&error.name, &error.args),
+                    name: error.name.0,
+                    types: error.args.iter().map(|arg| arg.ty.0.clone()).collect(),
+                    indexed: vec![],
+                }),
+                // Anonymous events can't be told apart by their topics.
+                RootSection::Definition(Definition::SolEvent(event)) if !event.anonymous => events
+                    .push(Signature {
+                        id: compute_event_topic(&event.name, &event.args),
+                        name: event.name.0,
+                        types: event.args.iter().map(|arg| arg.ty.0.clone()).collect(),
+                        indexed: event.args.iter().map(|arg| arg.indexed).collect(),
+                    }),
+                _ => {}
+            }
+        }
+        Self { errors, events }
+    }
+
+    /// Revert data as `Name(args...)`, `None` if no error matches it.
+    pub fn decode_error(&self, data: &[u8]) -> Option<String> {
+        let (selector, params) = data.split_first_chunk::<4>()?;
+        self.errors
+            .iter()
+            .filter(|error| error.id == selector)
+            .find_map(|error| {
+                let values = decode_params(&error.types, params)?;
+                Some(format!("{}({})", error.name, values.join(", ")))
+            })
+    }
+
+    /// Log as `Name(args...)`, `None` if no event matches it. Events without any `indexed`
+    /// parameters are taken to index their leading ones, as many as there are topics besides the
+    /// event's own.
+    pub fn decode_log(&self, log: &Log) -> Option<String> {
+        let (topic, topics) = log.topics().split_first()?;
+        self.events
+            .iter()
+            .filter(|event| event.id == *topic)
+            .find_map(|event| {
+                let indexed: Vec<bool> = if event.indexed.contains(&true) {
+                    event.indexed.clone()
+                } else {
+                    (0..event.types.len())
+                        .map(|idx| idx < topics.len())
+                        .collect()
+                };
+                if indexed.iter().filter(|indexed| **indexed).count() != topics.len() {
+                    return None;
+                }
+
+                let data_types: Vec<_> = event
+                    .types
+                    .iter()
+                    .zip(&indexed)
+                    .filter(|(_, indexed)| !**indexed)
+                    .map(|(ty, _)| ty.clone())
+                    .collect();
+                let mut data = decode_params(&data_types, &log.data.data)?.into_iter();
+                let mut topics = topics.iter();
+                let values: Vec<String> = event
+                    .types
+                    .iter()
+                    .zip(&indexed)
+                    .filter_map(|(ty, indexed)| {
+                        if *indexed {
+                            Some(decode_topic(ty, topics.next()?))
+                        } else {
+                            data.next()
+                        }
+                    })
+                    .collect();
+                Some(format!("{}({})", event.name, values.join(", ")))
+            })
+    }
+}
+
+fn decode_params(types: &[DynSolType], data: &[u8]) -> Option<Vec<String>> {
+    let values = DynSolType::Tuple(types.to_vec())
+        .abi_decode_sequence(data)
+        .ok()?;
+    Some(values.as_tuple()?.iter().map(format_value).collect())
+}
+
+/// Indexed value types are stored as is, others only by their hash.
+fn decode_topic(ty: &DynSolType, topic: &B256) -> String {
+    match ty {
+        DynSolType::Address
+        | DynSolType::Bool
+        | DynSolType::Int(_)
+        | DynSolType::Uint(_)
+        | DynSolType::FixedBytes(_)
+        | DynSolType::Function => ty
+            .abi_decode(topic.as_slice())
+            .map_or_else(|_| topic.to_string(), |value| format_value(&value)),
+        _ => topic.to_string(),
+    }
+}
+
+pub(crate) fn format_value(value: &DynSolValue) -> String {
+    let join = |values: &[DynSolValue]| {
+        values
+            .iter()
+            .map(format_value)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match value {
+        DynSolValue::Bool(value) => value.to_string(),
+        DynSolValue::Int(value, _) => value.to_string(),
+        DynSolValue::Uint(value, _) => value.to_string(),
+        DynSolValue::FixedBytes(word, size) => hex::encode_prefixed(&word[..*size]),
+        DynSolValue::Address(address) => address.to_string(),
+        DynSolValue::Function(function) => function.to_string(),
+        DynSolValue::Bytes(bytes) => hex::encode_prefixed(bytes),
+        DynSolValue::String(string) => format!("{:?}", string),
+        DynSolValue::Array(values) | DynSolValue::FixedArray(values) => {
+            format!("[{}]", join(values))
+        }
+        DynSolValue::Tuple(values) => format!("({})", join(values)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, Bytes, U256};
+    use huff_ast::parse;
+
+    const SRC: &str = "#define error Unauthorized(address)
+#define event Transfer(address,address,uint256)
+#define event Approval(address indexed owner, uint256 amount, address indexed spender)";
+
+    #[test]
+    fn decodes_errors() {
+        let ast = parse(SRC).unwrap();
+        let decoder = AbiDecoder::new(&ast);
+
+        let owner = Address::with_last_byte(1);
+        let mut data = hex::decode("8e4a23d6").unwrap();
+        data.extend_from_slice(owner.into_word().as_slice());
+        assert_eq!(
+            decoder.decode_error(&data).unwrap(),
+            format!("Unauthorized({})", owner)
+        );
+
+        let reason = DynSolValue::String("nope".to_owned()).abi_encode_params();
+        let data = [ERROR_STRING.as_slice(), &reason].concat();
+        assert_eq!(decoder.decode_error(&data).unwrap(), "Error(\"nope\")");
+        assert_eq!(decoder.decode_error(&[0xde, 0xad, 0xbe, 0xef]), None);
+    }
+
+    #[test]
+    fn decodes_logs() {
+        let ast = parse(SRC).unwrap();
+        let decoder = AbiDecoder::new(&ast);
+
+        let (from, to) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let topic = alloy_primitives::keccak256("Transfer(address,address,uint256)");
+        let log = Log::new_unchecked(
+            Address::ZERO,
+            vec![topic, from.into_word(), to.into_word()],
+            Bytes::from(U256::from(100).to_be_bytes::<32>()),
+        );
+        assert_eq!(
+            decoder.decode_log(&log).unwrap(),
+            format!("Transfer({}, {}, 100)", from, to)
+        );
+
+        let topic = alloy_primitives::keccak256("Approval(address,uint256,address)");
+        let log = Log::new_unchecked(
+            Address::ZERO,
+            vec![topic, from.into_word(), to.into_word()],
+            Bytes::from(U256::from(100).to_be_bytes::<32>()),
+        );
+        assert_eq!(
+            decoder.decode_log(&log).unwrap(),
+            format!("Approval({}, 100, {})", from, to)
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{config::ProjectConfig, report_parse_errors};
+use ariadne::{Color, Fmt};
+use clap::Args;
+use huff_ast::collect_huff_files;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct FmtArgs {
+    #[clap(help = "Files or directories to format, defaults to the current directory")]
+    paths: Vec<PathBuf>,
+
+    #[clap(
+        long = "check",
+        help = "Only check formatting, exits with an error if any file would be reformatted"
+    )]
+    check: bool,
+}
+
+pub fn run(args: FmtArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config = ProjectConfig::load()?.fmt;
+
+    let paths = if args.paths.is_empty() {
+        vec![PathBuf::from(".")]
+    } else {
+        args.paths
+    };
+    let mut files = Vec::new();
+    for path in &paths {
+        collect_huff_files(path, &mut files)?;
+    }
+
+    let mut failed = false;
+    for file in files {
+        let filename = file.display().to_string();
+        let src = std::fs::read_to_string(&file)?;
+        let formatted = match huff_fmt::format(&src, &config) {
+            Ok(formatted) => formatted,
+            Err(errs) => {
+                report_parse_errors(&filename, &src, errs);
+                failed = true;
+                continue;
+            }
+        };
+        if formatted == src {
+            continue;
+        }
+        if args.check {
+            eprintln!(
+                "{}: {} is not formatted",
+                "Error".fg(Color::Red),
+                filename.fg(Color::Yellow)
+            );
+            failed = true;
+        } else {
+            std::fs::write(&file, formatted)?;
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
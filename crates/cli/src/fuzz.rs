@@ -0,0 +1,411 @@
+use crate::decode::format_value;
+use alloy_dyn_abi::{DynSolType, DynSolValue};
+use alloy_primitives::{Address, Function, B256, I256, U256};
+use std::path::{Path, PathBuf};
+
+/// Upper bound of inputs tried while shrinking a counterexample.
+const MAX_SHRINK_ATTEMPTS: usize = 2048;
+/// Maximum length of generated dynamic arrays, bytes and strings.
+const MAX_DYNAMIC_LEN: u64 = 64;
+
+/// Fuzzing settings, read from the `[fuzz]` section of `huff.toml`.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FuzzConfig {
+    /// Random inputs each fuzz test is run with.
+    pub runs: u32,
+    /// Seed of the inputs, random if not set.
+    pub seed: Option<u64>,
+    /// Directory the seeds of failing inputs are saved to, to be replayed first by later runs.
+    pub failures_dir: PathBuf,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        Self {
+            runs: 256,
+            seed: None,
+            failures_dir: PathBuf::from("cache/fuzz"),
+        }
+    }
+}
+
+/// SplitMix64, deterministic for a given seed.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n`, ignoring the negligible modulo bias.
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    fn word(&mut self) -> U256 {
+        U256::from_limbs(std::array::from_fn(|_| self.next_u64()))
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_u64() as u8).collect()
+    }
+}
+
+fn uint_max(bits: usize) -> U256 {
+    U256::MAX >> (256 - bits)
+}
+
+/// Random unsigned value, biased towards the edges of its range.
+fn random_uint(rng: &mut Rng, bits: usize) -> U256 {
+    let max = uint_max(bits);
+    let small = U256::from(rng.below(256)) & max;
+    match rng.below(8) {
+        0 => U256::ZERO,
+        1 => max,
+        2 => small,
+        3 => max - small,
+        // Random magnitude rather than uniform, which would almost always be huge.
+        _ => (rng.word() >> rng.below(bits as u64) as usize) & max,
+    }
+}
+
+/// Random value of the type, its bytes, strings and arrays kept short.
+pub(crate) fn random_value(rng: &mut Rng, ty: &DynSolType) -> DynSolValue {
+    match ty {
+        DynSolType::Bool => DynSolValue::Bool(rng.below(2) == 1),
+        DynSolType::Uint(bits) => DynSolValue::Uint(random_uint(rng, *bits), *bits),
+        DynSolType::Int(bits) => {
+            let raw = random_uint(rng, *bits);
+            // Sign extend from the type's width.
+            let raw = if *bits < 256 && raw.bit(bits - 1) {
+                raw | !uint_max(*bits)
+            } else {
+                raw
+            };
+            DynSolValue::Int(I256::from_raw(raw), *bits)
+        }
+        DynSolType::Address => DynSolValue::Address(match rng.below(4) {
+            0 => Address::ZERO,
+            _ => Address::from_slice(&rng.bytes(20)),
+        }),
+        DynSolType::FixedBytes(size) => {
+            let mut word = B256::ZERO;
+            word[..*size].copy_from_slice(&rng.bytes(*size));
+            DynSolValue::FixedBytes(word, *size)
+        }
+        DynSolType::Function => DynSolValue::Function(Function::from_slice(&rng.bytes(24))),
+        DynSolType::Bytes => {
+            let len = rng.below(MAX_DYNAMIC_LEN + 1) as usize;
+            DynSolValue::Bytes(rng.bytes(len))
+        }
+        DynSolType::String => {
+            let len = rng.below(MAX_DYNAMIC_LEN + 1) as usize;
+            let string = (0..len)
+                .map(|_| char::from(b' ' + rng.below(95) as u8))
+                .collect();
+            DynSolValue::String(string)
+        }
+        DynSolType::Array(inner) => {
+            let len = rng.below(5);
+            DynSolValue::Array((0..len).map(|_| random_value(rng, inner)).collect())
+        }
+        DynSolType::FixedArray(inner, len) => {
+            DynSolValue::FixedArray((0..*len).map(|_| random_value(rng, inner)).collect())
+        }
+        DynSolType::Tuple(types) => {
+            DynSolValue::Tuple(types.iter().map(|ty| random_value(rng, ty)).collect())
+        }
+    }
+}
+
+/// Smaller magnitudes than `value`, the biggest reductions first.
+fn smaller(value: U256) -> Vec<U256> {
+    if value.is_zero() {
+        return vec![];
+    }
+    let mut candidates = vec![U256::ZERO];
+    candidates.extend((1..value.bit_len()).map(|shift| value - (value >> shift)));
+    candidates.push(value - U256::from(1));
+    candidates.dedup();
+    candidates
+}
+
+/// Simpler variants of the value, the simplest first.
+fn shrink_value(value: &DynSolValue) -> Vec<DynSolValue> {
+    let shrink_elements = |values: &[DynSolValue], wrap: fn(Vec<DynSolValue>) -> DynSolValue| {
+        let mut candidates = Vec::new();
+        for (i, element) in values.iter().enumerate() {
+            for shrunk in shrink_value(element) {
+                let mut values = values.to_vec();
+                values[i] = shrunk;
+                candidates.push(wrap(values));
+            }
+        }
+        candidates
+    };
+    let shorter = |len: usize| {
+        [0, len / 2, len.saturating_sub(1)]
+            .into_iter()
+            .filter(move |&l| l < len)
+    };
+
+    match value {
+        DynSolValue::Bool(true) => vec![DynSolValue::Bool(false)],
+        DynSolValue::Uint(value, bits) => smaller(*value)
+            .into_iter()
+            .map(|value| DynSolValue::Uint(value, *bits))
+            .collect(),
+        DynSolValue::Int(value, bits) => {
+            let magnitude = value.unsigned_abs();
+            let signed = |magnitude: U256, negative: bool| {
+                let value = I256::from_raw(magnitude);
+                DynSolValue::Int(if negative { -value } else { value }, *bits)
+            };
+            let mut candidates: Vec<_> = smaller(magnitude)
+                .into_iter()
+                .map(|magnitude| signed(magnitude, value.is_negative()))
+                .collect();
+            if value.is_negative() && magnitude <= I256::MAX.into_raw() {
+                candidates.insert(1, signed(magnitude, false));
+            }
+            candidates
+        }
+        DynSolValue::Address(address) if !address.is_zero() => {
+            vec![DynSolValue::Address(Address::ZERO)]
+        }
+        DynSolValue::FixedBytes(word, size) if !word.is_zero() => {
+            vec![DynSolValue::FixedBytes(B256::ZERO, *size)]
+        }
+        DynSolValue::Function(function) if !function.is_zero() => {
+            vec![DynSolValue::Function(Function::ZERO)]
+        }
+        DynSolValue::Bytes(bytes) => shorter(bytes.len())
+            .map(|len| DynSolValue::Bytes(bytes[..len].to_vec()))
+            .collect(),
+        DynSolValue::String(string) => shorter(string.len())
+            .filter(|&len| string.is_char_boundary(len))
+            .map(|len| DynSolValue::String(string[..len].to_owned()))
+            .collect(),
+        DynSolValue::Array(values) => {
+            let mut candidates: Vec<_> = shorter(values.len())
+                .map(|len| DynSolValue::Array(values[..len].to_vec()))
+                .collect();
+            candidates.extend(shrink_elements(values, DynSolValue::Array));
+            candidates
+        }
+        DynSolValue::FixedArray(values) => shrink_elements(values, DynSolValue::FixedArray),
+        DynSolValue::Tuple(values) => shrink_elements(values, DynSolValue::Tuple),
+        _ => vec![],
+    }
+}
+
+/// Greedily shrinks the failing arguments to a simpler set that still fails.
+pub(crate) fn shrink(
+    mut args: Vec<DynSolValue>,
+    mut fails: impl FnMut(&[DynSolValue]) -> bool,
+) -> Vec<DynSolValue> {
+    let mut attempts = 0;
+    'shrinking: loop {
+        for candidate in shrink_value(&DynSolValue::Tuple(args.clone())) {
+            if attempts == MAX_SHRINK_ATTEMPTS {
+                break 'shrinking;
+            }
+            attempts += 1;
+            let DynSolValue::Tuple(candidate) = candidate else {
+                unreachable!("shrinking preserves the value's type")
+            };
+            if fails(&candidate) {
+                args = candidate;
+                continue 'shrinking;
+            }
+        }
+        break;
+    }
+    args
+}
+
+/// `prefix` followed by the ABI encoded arguments.
+fn encode_calldata(prefix: &[u8], args: &[DynSolValue]) -> Vec<u8> {
+    let mut calldata = prefix.to_vec();
+    calldata.extend(DynSolValue::Tuple(args.to_vec()).abi_encode_params());
+    calldata
+}
+
+/// The smallest failing input found by a fuzz campaign.
+pub(crate) struct Counterexample {
+    /// Seed the failing input was generated from.
+    pub seed: u64,
+    pub args: Vec<DynSolValue>,
+    pub failure: String,
+}
+
+impl Counterexample {
+    pub fn describe(&self) -> String {
+        format!(
+            "{}; counterexample: {} (seed: {:#x})",
+            self.failure,
+            format_value(&DynSolValue::Tuple(self.args.clone())),
+            self.seed
+        )
+    }
+}
+
+pub(crate) struct Campaign {
+    pub runs: u32,
+    /// Mean gas used by the passing runs.
+    pub mean_gas: u64,
+    pub counterexample: Option<Counterexample>,
+}
+
+/// Runs `exec` with inputs generated from each seed until one fails, which is then shrunk.
+/// `exec` returns the gas used and the reason of the failure, if any.
+pub(crate) fn fuzz(
+    types: &[DynSolType],
+    prefix: &[u8],
+    seeds: impl IntoIterator<Item = u64>,
+    mut exec: impl FnMut(&[u8]) -> (u64, Option<String>),
+) -> Campaign {
+    let mut runs = 0;
+    let mut total_gas = 0u128;
+    for seed in seeds {
+        let mut rng = Rng::new(seed);
+        let args: Vec<_> = types.iter().map(|ty| random_value(&mut rng, ty)).collect();
+        let (gas, failure) = exec(&encode_calldata(prefix, &args));
+        runs += 1;
+        let Some(failure) = failure else {
+            total_gas += u128::from(gas);
+            continue;
+        };
+
+        let mut failure = failure;
+        let args = shrink(args, |candidate| {
+            match exec(&encode_calldata(prefix, candidate)).1 {
+                Some(shrunk_failure) => {
+                    failure = shrunk_failure;
+                    true
+                }
+                None => false,
+            }
+        });
+        return Campaign {
+            runs,
+            mean_gas: mean(total_gas, runs - 1),
+            counterexample: Some(Counterexample {
+                seed,
+                args,
+                failure,
+            }),
+        };
+    }
+    Campaign {
+        runs,
+        mean_gas: mean(total_gas, runs),
+        counterexample: None,
+    }
+}
+
+fn mean(total: u128, count: u32) -> u64 {
+    total
+        .checked_div(u128::from(count))
+        .map_or(0, |mean| mean as u64)
+}
+
+/// File the failing seed of a test is saved in.
+fn failure_file(dir: &Path, filename: &str, test: &str) -> PathBuf {
+    let stem = Path::new(filename)
+        .file_stem()
+        .map_or_else(|| filename.into(), |stem| stem.to_string_lossy());
+    dir.join(stem.as_ref()).join(test)
+}
+
+/// The previously failing seed of the test, if any.
+pub(crate) fn load_failure(dir: &Path, filename: &str, test: &str) -> Option<u64> {
+    let content = std::fs::read_to_string(failure_file(dir, filename, test)).ok()?;
+    let seed = content.trim();
+    u64::from_str_radix(seed.strip_prefix("0x").unwrap_or(seed), 16).ok()
+}
+
+/// Saves the failing seed of the test, or removes it once no seed fails.
+pub(crate) fn persist_failure(
+    dir: &Path,
+    filename: &str,
+    test: &str,
+    seed: Option<u64>,
+) -> std::io::Result<()> {
+    let file = failure_file(dir, filename, test);
+    match seed {
+        Some(seed) => {
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(file, format!("{:#x}\n", seed))
+        }
+        None => match std::fs::remove_file(file) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_values_of_the_type() {
+        let ty = DynSolType::parse("(uint8,int16,address,bytes3,bytes,string,bool[2],uint256[])")
+            .unwrap();
+        let mut rng = Rng::new(1);
+        for _ in 0..256 {
+            let value = random_value(&mut rng, &ty);
+            assert!(ty.matches(&value));
+            assert_eq!(ty.abi_decode(&value.abi_encode()).unwrap(), value);
+            assert!(shrink_value(&value).iter().all(|shrunk| ty.matches(shrunk)));
+        }
+    }
+
+    #[test]
+    fn shrinks_to_minimal_counterexample() {
+        let uint = |value: u64| DynSolValue::Uint(U256::from(value), 256);
+        let fails = |args: &[DynSolValue]| {
+            let (DynSolValue::Uint(a, _), DynSolValue::Address(b)) = (&args[0], &args[1]) else {
+                unreachable!()
+            };
+            *a > U256::from(1000) || !b.is_zero()
+        };
+
+        let shrunk = shrink(
+            vec![uint(123_456_789), DynSolValue::Address(Address::ZERO)],
+            fails,
+        );
+        assert_eq!(shrunk, [uint(1001), DynSolValue::Address(Address::ZERO)]);
+
+        let shrunk = shrink(
+            vec![uint(5), DynSolValue::Address(Address::with_last_byte(7))],
+            fails,
+        );
+        assert_eq!(
+            shrunk,
+            [uint(0), DynSolValue::Address(Address::with_last_byte(7))]
+        );
+    }
+
+    #[test]
+    fn shrinks_signed_towards_zero() {
+        let bound = I256::try_from(-7).unwrap();
+        let shrunk = shrink(
+            vec![DynSolValue::Int(I256::MINUS_ONE << 100, 256)],
+            |args| matches!(args[0], DynSolValue::Int(value, _) if value < bound),
+        );
+        assert_eq!(shrunk, [DynSolValue::Int(I256::try_from(-8).unwrap(), 256)]);
+    }
+}
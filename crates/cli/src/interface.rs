@@ -0,0 +1,227 @@
+use alloy_dyn_abi::DynSolType;
+use huff_ast::{Cst, Definition, IdentifiableNode, Root, RootSection, SolParam, StateMutability};
+use std::fmt::Write;
+
+const INDENT: &str = "    ";
+
+/// Renders the types of parameters, declaring a struct for every distinct tuple type.
+#[derive(Default)]
+struct Types {
+    /// Tuple types along with the rendered types of their fields.
+    structs: Vec<(Vec<DynSolType>, Vec<String>)>,
+}
+
+impl Types {
+    fn render(&mut self, ty: &DynSolType) -> String {
+        match ty {
+            DynSolType::Array(inner) => format!("{}[]", self.render(inner)),
+            DynSolType::FixedArray(inner, len) => format!("{}[{}]", self.render(inner), len),
+            DynSolType::Tuple(components) => {
+                let idx = match self.structs.iter().position(|(ty, _)| ty == components) {
+                    Some(idx) => idx,
+                    None => {
+                        let fields = components.iter().map(|ty| self.render(ty)).collect();
+                        self.structs.push((components.clone(), fields));
+                        self.structs.len() - 1
+                    }
+                };
+                format!("Struct{}", idx)
+            }
+            ty => ty.to_string(),
+        }
+    }
+
+    /// Comma separated parameters, `location` being added to reference types.
+    fn params(&mut self, args: &[SolParam], location: Option<&str>, indexable: bool) -> String {
+        args.iter()
+            .map(|arg| {
+                let mut param = self.render(&arg.ty.0);
+                let is_reference = matches!(
+                    arg.ty.0,
+                    DynSolType::Bytes
+                        | DynSolType::String
+                        | DynSolType::Array(_)
+                        | DynSolType::FixedArray(..)
+                        | DynSolType::Tuple(_)
+                );
+                if let Some(location) = location.filter(|_| is_reference) {
+                    param = format!("{} {}", param, location);
+                }
+                if indexable && arg.indexed {
+                    param.push_str(" indexed");
+                }
+                if let Some((name, _)) = arg.name {
+                    param = format!("{} {}", param, name);
+                }
+                param
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Natspec comments (`///` and `/** */`) directly preceding the definition, indented.
+fn natspec(cst: Option<&Cst>, def: &Definition) -> String {
+    // Definitions included from ABIs don't point into the file.
+    let Some(cst) = cst.filter(|cst| cst.src.get(def.span().into_range()) == Some(def.ident()))
+    else {
+        return String::new();
+    };
+    let Some(first) = cst.node_tokens(cst.node_for(def)).first() else {
+        return String::new();
+    };
+    let mut docs = String::new();
+    for (comment, _) in first.leading_comments() {
+        let comment = comment.text();
+        if !(comment.starts_with("///") || comment.starts_with("/**")) {
+            continue;
+        }
+        for line in comment.lines().map(str::trim) {
+            // Aligns the continuation lines of block comments with their opening `/**`.
+            let align = if line.starts_with('*') { " " } else { "" };
+            writeln!(docs, "{}{}{}", INDENT, align, line).unwrap();
+        }
+    }
+    docs
+}
+
+/// Name of the interface generated for a file, `I` followed by the file's name.
+pub(crate) fn interface_name(filename: &str) -> String {
+    let stem = std::path::Path::new(filename)
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    let name: String = stem
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+    format!("I{}", name)
+}
+
+/// Solidity interface declaring the events, errors and functions defined in the file, in that
+/// order. Tuples are declared as structs since they have no name in Huff.
+pub(crate) fn sol_interface(name: &str, src: &str, ast: &Root) -> String {
+    let cst = Cst::parse(src).ok();
+    let mut types = Types::default();
+    let (mut events, mut errors, mut functions) = (String::new(), String::new(), String::new());
+
+    for section in ast.0.iter() {
+        let RootSection::Definition(def) = section else {
+            continue;
+        };
+        match def {
+            Definition::SolEvent(event) => {
+                events.push_str(&natspec(cst.as_ref(), def));
+                writeln!(
+                    events,
+                    "{}event {}({}){};",
+                    INDENT,
+                    event.name.0,
+                    types.params(&event.args, None, true),
+                    if event.anonymous { " anonymous" } else { "" }
+                )
+                .unwrap();
+            }
+            Definition::SolError(error) => {
+                errors.push_str(&natspec(cst.as_ref(), def));
+                writeln!(
+                    errors,
+                    "{}error {}({});",
+                    INDENT,
+                    error.name.0,
+                    types.params(&error.args, None, false)
+                )
+                .unwrap();
+            }
+            Definition::SolFunction(func) => {
+                functions.push_str(&natspec(cst.as_ref(), def));
+                write!(
+                    functions,
+                    "{}function {}({}) external",
+                    INDENT,
+                    func.name.0,
+                    types.params(&func.args, Some("calldata"), false)
+                )
+                .unwrap();
+                if func.state_mutability != StateMutability::NonPayable {
+                    write!(functions, " {}", func.state_mutability.as_str()).unwrap();
+                }
+                if !func.rets.is_empty() {
+                    write!(
+                        functions,
+                        " returns ({})",
+                        types.params(&func.rets, Some("memory"), false)
+                    )
+                    .unwrap();
+                }
+                functions.push_str(";\n");
+            }
+            _ => {}
+        }
+    }
+
+    let mut structs = String::new();
+    for (idx, (_, fields)) in types.structs.iter().enumerate() {
+        writeln!(structs, "{}struct Struct{} {{", INDENT, idx).unwrap();
+        for (field_idx, field) in fields.iter().enumerate() {
+            writeln!(structs, "{}{}{} field{};", INDENT, INDENT, field, field_idx).unwrap();
+        }
+        writeln!(structs, "{}}}", INDENT).unwrap();
+    }
+
+    let body: Vec<_> = [structs, events, errors, functions]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect();
+    format!(
+        "// SPDX-License-Identifier: UNLICENSED\npragma solidity ^0.8.4;\n\ninterface {} {{\n{}}}\n",
+        name,
+        body.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use huff_ast::parse;
+
+    #[test]
+    fn renders_interface() {
+        let src = "/// @notice Moves `amount` tokens to `to`.
+/// @param to The recipient.
+#define function transfer(address to, uint256 amount) nonpayable returns (bool)
+// Not natspec.
+#define function positions((address,uint256)[2] owners) view returns (uint256[] amounts, string)
+/**
+ * @notice Emitted on approvals.
+ */
+#define event Approval(address indexed owner, address indexed, uint256 amount) anonymous
+#define error Unauthorized(address caller, (address,uint256) position)";
+        let ast = parse(src).unwrap();
+        assert_eq!(interface_name("src/My-Token.huff"), "IMyToken");
+        assert_eq!(
+            sol_interface("IToken", src, &ast),
+            "// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.4;
+
+interface IToken {
+    struct Struct0 {
+        address field0;
+        uint256 field1;
+    }
+
+    /**
+     * @notice Emitted on approvals.
+     */
+    event Approval(address indexed owner, address indexed, uint256 amount) anonymous;
+
+    error Unauthorized(address caller, Struct0 position);
+
+    /// @notice Moves `amount` tokens to `to`.
+    /// @param to The recipient.
+    function transfer(address to, uint256 amount) external returns (bool);
+    function positions(Struct0[2] calldata owners) external view returns (uint256[] memory amounts, string memory);
+}
+"
+        );
+    }
+}
@@ -1,24 +1,79 @@
 use ariadne::{sources, Color, Config, Fmt, IndexType, Label, Report, ReportKind};
-use clap::Parser as ClapParser;
+use chumsky::error::Rich;
+use clap::{Parser as ClapParser, Subcommand};
 use huff_analysis::{const_overrides::*, *};
-use huff_ast::{parse, RootSection};
-use huff_compilation::{generate_default_constructor, generate_for_entrypoint, CompileGlobals};
-use std::collections::BTreeSet;
+use huff_ast::{parse, parse_abi_include, Definition, IdentifiableNode, Root, RootSection, Token};
+use huff_compilation::{generate_with_pc_table, CompileGlobals, Metadata, PcTable};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
 
+mod abi;
+mod artifact;
+mod config;
+mod constructor_args;
+mod coverage;
+mod debug;
+mod decode;
+mod fmt;
+mod fuzz;
+mod interface;
+mod metadata;
+mod mutate;
+mod run;
+mod snapshot;
+mod standard_json;
+mod testing;
 mod versions;
+use artifact::Contract;
 use versions::EvmVersion;
 
 /// Huff Language Compiler
 #[derive(ClapParser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    build: CliArguments,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Report the statements and branches the tests execute
+    Coverage(coverage::CoverageArgs),
+    /// Step through a macro's execution in a local EVM statement by statement
+    Debug(debug::DebugArgs),
+    /// Format Huff source files
+    Fmt(fmt::FmtArgs),
+    /// Run the language server over stdio
+    Lsp,
+    /// Check that the tests fail against mutated versions of the macros
+    Mutate(mutate::MutateArgs),
+    /// Execute a macro as a contract's runtime code in a local EVM
+    Run(run::RunArgs),
+    /// Write or check a snapshot of the gas used by the tests
+    Snapshot(snapshot::SnapshotArgs),
+    /// Run the `#define test` blocks of a file
+    Test(testing::TestArgs),
+}
+
+#[derive(clap::Args)]
 struct CliArguments {
     /// filename
-    #[clap(help = "Root huff file to compile")]
-    filename: String,
+    #[clap(
+        required_unless_present = "standard_json",
+        help = "Root huff file to compile"
+    )]
+    filename: Option<String>,
 
     #[clap(
+        required_unless_present = "standard_json",
         help = "Name of Huff entrypoint macro to compile to EVM bytecode. NOTE: Will compile the entry point *as is*, no implicit initcode wrapper."
     )]
-    entry_point: String,
+    entry_point: Option<String>,
 
     #[clap(
         short = 'f',
@@ -27,13 +82,79 @@ struct CliArguments {
     )]
     add_default_constructor: bool,
 
+    #[clap(
+        long = "constructor-args",
+        num_args = 1..,
+        allow_negative_numbers = true,
+        value_names = ["SIGNATURE", "ARGS"],
+        help = "ABI encode arguments, e.g. 'constructor(address,uint256)' 0x.. 42, and append them to the printed code"
+    )]
+    constructor_args: Vec<String>,
+
+    #[clap(
+        long = "metadata",
+        help = "Append a CBOR trailer with the compiler version, source hash, EVM version and constant overrides to the entry point's code"
+    )]
+    metadata: bool,
+
+    #[clap(
+        long = "emit",
+        value_enum,
+        help = "Additional outputs to print after the bytecode, in the order given"
+    )]
+    emit: Vec<Emit>,
+
+    #[clap(
+        long = "out",
+        help = "Directory to write a Foundry style artifact to, as <OUT>/<FILE>/<NAME>.json"
+    )]
+    out: Option<PathBuf>,
+
+    #[clap(
+        long = "runtime",
+        value_name = "MACRO",
+        requires = "out",
+        conflicts_with = "add_default_constructor",
+        help = "Macro deployed by the entry point, compiled as the `--out` artifact's deployed code in place of `-f`"
+    )]
+    runtime: Option<String>,
+
+    #[clap(
+        long = "standard-json",
+        conflicts_with_all = ["filename", "entry_point"],
+        help = "Read a solc style standard JSON input from stdin and print the output to stdout"
+    )]
+    standard_json: bool,
+
+    #[command(flatten)]
+    compile: CompileArgs,
+}
+
+/// Outputs of the compiler besides the bytecode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Emit {
+    /// Solc style source map of the printed bytecode
+    SourceMap,
+    /// Solc style source map of the entry point's code, without the constructor
+    RuntimeSourceMap,
+    /// JSON ABI of the file's function, event and error definitions
+    Abi,
+    /// Solidity interface declaring the file's function, event and error definitions
+    SolInterface,
+    /// JSON document of the build settings and metadata trailer, to reproduce the build
+    Metadata,
+}
+
+/// Options of the commands compiling code.
+#[derive(clap::Args)]
+pub(crate) struct CompileArgs {
     #[clap(
         short = 'e',
         long = "evm-version",
         help = "What EVM version to use, NOTE: Pre-EOF this option only affects the use of PUSH0.",
         default_value = "paris"
     )]
-    evm_version: EvmVersion,
+    pub evm_version: EvmVersion,
 
     #[clap(
         short = 'c',
@@ -41,25 +162,305 @@ struct CliArguments {
         value_parser = parse_constant_override,
         help = "Add override to list in format <CONSTANT_NAME>=<HEX/DEC VALUE>"
     )]
-    constant_overrides: Vec<ConstantOverride>,
+    pub constant_overrides: Vec<ConstantOverride>,
+}
+
+pub(crate) fn report_parse_errors(filename: &str, src: &str, errs: Vec<Rich<'_, Token<'_>>>) {
+    errs.into_iter().for_each(|e| {
+        Report::build(ReportKind::Error, filename.to_string(), e.span().start)
+            .with_config(Config::default().with_index_type(IndexType::Byte))
+            // .with_message(e.reason())
+            .with_label(
+                Label::new((filename.to_string(), e.span().into_range()))
+                    .with_message(e.reason())
+                    .with_color(Color::Red),
+            )
+            .finish()
+            .eprint(sources([(filename.to_string(), src)]))
+            .unwrap()
+    });
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = CliArguments::parse();
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Coverage(args)) => coverage::run(args),
+        Some(Command::Debug(args)) => debug::run(args),
+        Some(Command::Fmt(args)) => fmt::run(args),
+        Some(Command::Lsp) => huff_lsp::run_stdio(),
+        Some(Command::Mutate(args)) => mutate::run(args),
+        Some(Command::Run(args)) => run::run(args),
+        Some(Command::Snapshot(args)) => snapshot::run(args),
+        Some(Command::Test(args)) => testing::run(args),
+        None => build(cli.build),
+    }
+}
 
-    let src_res = std::fs::read_to_string(&args.filename);
+fn build(args: CliArguments) -> Result<(), Box<dyn std::error::Error>> {
+    if args.standard_json {
+        return standard_json::run();
+    }
+    let (Some(filename), Some(entry_point)) = (args.filename, args.entry_point) else {
+        unreachable!("required arguments are enforced by clap")
+    };
+    let constructor_args = match args.constructor_args.split_first() {
+        Some((signature, values)) => {
+            constructor_args::encode(signature, values).unwrap_or_else(|err| {
+                eprintln!("{}: {}", "Error".fg(Color::Red), err);
+                std::process::exit(1);
+            })
+        }
+        None => Vec::new(),
+    };
+    if args.out.is_some() && !args.add_default_constructor && args.runtime.is_none() {
+        eprintln!(
+            "{}: --out needs the deployed code, pass -f or --runtime <MACRO>",
+            "Error".fg(Color::Red)
+        );
+        std::process::exit(1);
+    }
+    let src = read_source(&filename)?;
+    let mut included = Vec::new();
+    let (files, ast) = parse_source(&filename, &src, &mut included);
+    let entry_points = std::iter::once(entry_point.as_str()).chain(args.runtime.as_deref());
+    let mut config = analyze(
+        files,
+        &ast,
+        entry_points,
+        args.add_default_constructor,
+        &args.compile,
+    );
+    let metadata = Metadata::new(
+        files.contents(),
+        args.compile.evm_version.name(),
+        &args.compile.constant_overrides,
+    );
+    if args.metadata {
+        config.metadata_trailer = Some(metadata.trailer());
+        config.trailer_macro = args.runtime.as_deref();
+    }
+    let contract = Contract::compile(
+        &mut config,
+        &entry_point,
+        args.add_default_constructor,
+        args.runtime.as_deref(),
+    );
+
+    // Arguments are passed along with the code when deploying, so the artifact doesn't hold them.
+    println!(
+        "0x{}{}",
+        hex::encode(&contract.code),
+        hex::encode(&constructor_args)
+    );
+    if let Some(out) = &args.out {
+        artifact::write(out, &filename, &artifact::artifact(&ast, &contract))?;
+    }
+    for emit in args.emit {
+        match emit {
+            Emit::SourceMap => println!("Source map:\n{}", contract.source_map),
+            Emit::RuntimeSourceMap => {
+                let runtime_source_map = if args.add_default_constructor {
+                    &contract.deployed_source_map
+                } else {
+                    &contract.source_map
+                };
+                println!("Runtime source map:\n{}", runtime_source_map)
+            }
+            Emit::Abi => println!("ABI:\n{}", serde_json::to_string_pretty(&abi::abi(&ast))?),
+            Emit::SolInterface => print!(
+                "Solidity interface:\n{}",
+                interface::sol_interface(&interface::interface_name(&filename), &src, &ast)
+            ),
+            Emit::Metadata => {
+                let document = metadata::metadata(
+                    &filename,
+                    &entry_point,
+                    args.add_default_constructor,
+                    args.runtime.as_deref(),
+                    &constructor_args,
+                    args.metadata,
+                    &metadata,
+                );
+                println!("Metadata:\n{}", serde_json::to_string_pretty(&document)?)
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the source file, exiting if it doesn't exist.
+pub(crate) fn read_source(filename: &str) -> std::io::Result<String> {
+    let src_res = std::fs::read_to_string(filename);
 
     if let Err(err) = &src_res {
         if let std::io::ErrorKind::NotFound = err.kind() {
             eprintln!(
                 "{}: File with path '{}' not found",
                 "Error".fg(Color::Red),
-                args.filename.escape_debug()
+                filename.escape_debug()
             );
             std::process::exit(1);
         }
     };
 
+    src_res
+}
+
+/// Parses the source along with the ABIs it includes, reporting errors and exiting if any of them
+/// can't be read or doesn't parse. The included ABIs are read into `included`, which the included
+/// definitions refer into.
+pub(crate) fn parse_source<'src>(
+    filename: &'src str,
+    src: &'src str,
+    included: &'src mut Vec<IncludedAbi>,
+) -> (Files<'src>, Root<'src>) {
+    let ast = match parse(src) {
+        Ok(ast) => ast,
+        Err(errs) => {
+            report_parse_errors(filename, src, errs);
+            std::process::exit(1);
+        }
+    };
+    *included = read_included_abis(filename, &ast, read_source).unwrap_or_else(|(path, err)| {
+        eprintln!(
+            "{}: Failed to read '{}': {}",
+            "Error".fg(Color::Red),
+            path.escape_debug(),
+            err
+        );
+        std::process::exit(1);
+    });
+    let included = &**included;
+    match include_abis(ast, included) {
+        Ok(ast) => (
+            Files {
+                filename,
+                src,
+                included,
+            },
+            ast,
+        ),
+        Err((abi, err)) => {
+            report_parse_errors(&abi.path, &abi.src, vec![err]);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The files an AST refers into, the root file and the ABIs it includes.
+#[derive(Clone, Copy)]
+pub(crate) struct Files<'a> {
+    pub filename: &'a str,
+    pub src: &'a str,
+    pub included: &'a [IncludedAbi],
+}
+
+impl<'a> Files<'a> {
+    /// Path of the file the definition was parsed from, told by which source its name points into.
+    pub fn file_of(&self, def: &Definition) -> &'a str {
+        let name = def.ident().as_ptr();
+        self.included
+            .iter()
+            .find(|abi| abi.src.as_bytes().as_ptr_range().contains(&name))
+            .map_or(self.filename, |abi| &abi.path)
+    }
+
+    /// Prints the error's report, its labels pointing into the files their spans are in.
+    pub fn report(&self, err: &errors::AnalysisError) {
+        err.report(self.filename.to_owned(), |def| self.file_of(def).to_owned())
+            .eprint(self.sources())
+            .unwrap()
+    }
+
+    /// Paths and contents of the root file followed by the ABIs it includes.
+    pub fn contents(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+        let included = self
+            .included
+            .iter()
+            .map(|abi| (abi.path.as_str(), abi.src.as_str()));
+        std::iter::once((self.filename, self.src)).chain(included)
+    }
+
+    fn sources(&self) -> impl ariadne::Cache<String> + 'a {
+        sources(self.contents().map(|(path, src)| (path.to_owned(), src)))
+    }
+}
+
+/// An ABI included by a file, owned by the caller as the included definitions refer into it.
+pub(crate) struct IncludedAbi {
+    /// Path relative to the working directory.
+    pub path: String,
+    pub src: String,
+}
+
+/// Reads the ABIs the file includes with `read`, their paths being relative to the including file.
+/// Fails with the path that couldn't be read.
+pub(crate) fn read_included_abis(
+    filename: &str,
+    ast: &Root,
+    mut read: impl FnMut(&str) -> std::io::Result<String>,
+) -> Result<Vec<IncludedAbi>, (String, std::io::Error)> {
+    let dir = Path::new(filename).parent().unwrap_or(Path::new(""));
+    ast.0
+        .iter()
+        .filter_map(|section| match section {
+            RootSection::IncludeAbi((path, _)) => {
+                Some(dir.join(path).to_string_lossy().into_owned())
+            }
+            _ => None,
+        })
+        .map(|path| match read(&path) {
+            Ok(src) => Ok(IncludedAbi { path, src }),
+            Err(err) => Err((path, err)),
+        })
+        .collect()
+}
+
+/// Replaces the `#include_abi` sections with the definitions of the ABIs read by
+/// [`read_included_abis`]. Fails with the ABI that doesn't parse.
+pub(crate) fn include_abis<'src>(
+    ast: Root<'src>,
+    included: &'src [IncludedAbi],
+) -> Result<Root<'src>, (&'src IncludedAbi, Rich<'src, Token<'src>>)> {
+    let mut included = included.iter();
+    let mut sections = Vec::with_capacity(ast.0.len());
+    for section in ast.0.into_vec() {
+        let RootSection::IncludeAbi(_) = section else {
+            sections.push(section);
+            continue;
+        };
+        let abi = included.next().expect("included ABI read");
+        match parse_abi_include(&abi.path, &abi.src) {
+            Ok(defs) => sections.extend(defs.into_iter().map(RootSection::Definition)),
+            Err(err) => return Err((abi, err)),
+        }
+    }
+    Ok(Root(sections.into_boxed_slice()))
+}
+
+/// Analyzes and compiles the entry point as is, reporting errors and exiting if there are any.
+pub(crate) fn compile<'a>(
+    files: Files,
+    ast: &'a Root<'a>,
+    entry_point: &'a str,
+    args: &'a CompileArgs,
+) -> (CompileGlobals<'a, 'a>, Vec<u8>) {
+    let mut config = analyze(files, ast, [entry_point], false, args);
+    let (entry_point_code, _) = generate(&mut config, entry_point);
+    (config, entry_point_code)
+}
+
+/// Analyzes the file for the given entry points, reporting errors and exiting if there are any.
+/// `default_constructor` tells whether they're deployed by the default constructor.
+pub(crate) fn analyze<'a>(
+    files: Files,
+    ast: &'a Root<'a>,
+    entry_points: impl IntoIterator<Item = &'a str>,
+    default_constructor: bool,
+    args: &'a CompileArgs,
+) -> CompileGlobals<'a, 'a> {
     {
         let mut unique_overrids = BTreeSet::new();
         let mut found_duplicate = false;
@@ -78,31 +479,33 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    let src = src_res?;
-
-    let filename: String = args.filename;
-
-    let ast = match parse(&src) {
-        Ok(ast) => ast,
-        Err(errs) => {
-            errs.into_iter().for_each(|e| {
-                Report::build(ReportKind::Error, filename.clone(), e.span().start)
-                    .with_config(Config::default().with_index_type(IndexType::Byte))
-                    // .with_message(e.reason())
-                    .with_label(
-                        Label::new((filename.clone(), e.span().into_range()))
-                            .with_message(e.reason())
-                            .with_color(Color::Red),
-                    )
-                    .finish()
-                    .eprint(sources([(filename.clone(), &src)]))
-                    .unwrap()
-            });
-
+    match analyze_definitions(
+        ast,
+        entry_points,
+        default_constructor,
+        &args.constant_overrides,
+    ) {
+        Ok(unique_defs) => CompileGlobals::new(
+            true,
+            args.evm_version.allows_push0(),
+            unique_defs,
+            &args.constant_overrides,
+        ),
+        Err(analysis_errors) => {
+            analysis_errors.iter().for_each(|err| files.report(err));
             std::process::exit(1);
         }
-    };
+    }
+}
 
+/// Analyzes the file for the given entry points, returning the definitions to compile or the errors
+/// found. `default_constructor` tells whether they're deployed by the default constructor.
+pub(crate) fn analyze_definitions<'a>(
+    ast: &'a Root<'a>,
+    entry_points: impl IntoIterator<Item = &'a str>,
+    default_constructor: bool,
+    overrides: &'a [ConstantOverride],
+) -> Result<BTreeMap<&'a str, &'a Definition<'a>>, Vec<errors::AnalysisError<'a, 'a>>> {
     let mut analysis_errors = Vec::with_capacity(5);
     let global_defs = build_ident_map(ast.0.iter().filter_map(|section| match section {
         RootSection::Include(huff_include) => {
@@ -113,55 +516,104 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             None
         }
         RootSection::Definition(def) => Some(def),
+        // Replaced by the included definitions in `parse_source`.
+        RootSection::IncludeAbi(_) => None,
     }));
     let unique_defs = analyze_global_for_dups(&global_defs, |err| analysis_errors.push(err));
-    verify_constants_to_be_overriden_defined(&global_defs, &args.constant_overrides, |err| {
+    verify_constants_to_be_overriden_defined(&global_defs, overrides, |err| {
         analysis_errors.push(err)
     });
 
-    {
-        let mut to_analyze_stack = vec![CodeInclusionFrame::top(args.entry_point.as_str())];
-        let mut analyzed_macros = BTreeSet::new();
-        while let Some(next_entrypoint) = to_analyze_stack.last() {
-            let idx_to_remove = to_analyze_stack.len() - 1;
-            if analyzed_macros.insert(next_entrypoint.name) {
-                analyze_entry_point(
-                    &global_defs,
-                    next_entrypoint.name,
-                    |err| analysis_errors.push(err),
-                    &mut to_analyze_stack,
-                );
+    for entry_point in entry_points {
+        // Entry points share macros, only report their errors once.
+        let emit_error = |err| {
+            if !analysis_errors.contains(&err) {
+                analysis_errors.push(err)
             }
-            to_analyze_stack.remove(idx_to_remove);
+        };
+        if default_constructor {
+            analyze_deployed_entry_point_with_inclusions(&global_defs, entry_point, emit_error);
+        } else {
+            analyze_entry_point_with_inclusions(&global_defs, entry_point, emit_error);
         }
     }
 
-    if !analysis_errors.is_empty() {
-        analysis_errors.into_iter().for_each(|err| {
-            err.report(filename.clone())
-                .eprint(sources([(filename.clone(), &src)]))
-                .unwrap()
-        });
-        std::process::exit(1);
+    if analysis_errors.is_empty() {
+        Ok(unique_defs)
+    } else {
+        Err(analysis_errors)
     }
+}
 
-    let mut config = CompileGlobals::new(
-        true,
-        args.evm_version.allows_push0(),
-        unique_defs,
-        &args.constant_overrides,
-    );
-
-    let entry_point_macro = match config.defs.get(args.entry_point.as_str()) {
-        Some(huff_ast::Definition::Macro(entry_point)) => entry_point,
+/// Compiles an analyzed entry point macro or test, along with the statements each of its
+/// instructions originates from.
+pub(crate) fn generate<'a>(
+    config: &mut CompileGlobals<'a, 'a>,
+    entry_point: &str,
+) -> (Vec<u8>, PcTable<'a>) {
+    let entry_point_macro = match config.defs.get(entry_point) {
+        Some(
+            huff_ast::Definition::Macro(entry_point)
+            | huff_ast::Definition::Test(huff_ast::Test {
+                r#macro: entry_point,
+                ..
+            }),
+        ) => entry_point,
         _ => panic!("macro not found despite no errors in analysis"),
     };
-    let mut entry_point_code = generate_for_entrypoint(&mut config, entry_point_macro);
-    if args.add_default_constructor {
-        entry_point_code = config.assemble(&generate_default_constructor(entry_point_code));
+    generate_with_pc_table(config, entry_point_macro)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use huff_analysis::errors::AnalysisError;
+
+    #[test]
+    fn reports_errors_in_included_files() {
+        let src = "#define macro owner() = {}\n#include_abi \"IOwned.json\"";
+        let ast = parse(src).unwrap();
+        let included = read_included_abis("src/Token.huff", &ast, |path| {
+            assert_eq!(path, "src/IOwned.json");
+            Ok(r#"[{"type": "error", "name": "owner", "inputs": []}]"#.to_owned())
+        })
+        .unwrap();
+        let Ok(ast) = include_abis(ast, &included) else {
+            panic!("included ABI doesn't parse")
+        };
+        let files = Files {
+            filename: "src/Token.huff",
+            src,
+            included: &included,
+        };
+
+        let errs = analyze_definitions(&ast, [], false, &[]).unwrap_err();
+        let [err @ AnalysisError::DefinitionNameCollision { collided, .. }] = &errs[..] else {
+            panic!("expected a collision")
+        };
+        let collided_files: Vec<_> = collided.iter().map(|def| files.file_of(def)).collect();
+        assert_eq!(collided_files, ["src/Token.huff", "src/IOwned.json"]);
+
+        let mut report = Vec::new();
+        err.report(files.filename.to_owned(), |def| {
+            files.file_of(def).to_owned()
+        })
+        .write(files.sources(), &mut report)
+        .unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.contains("src/IOwned.json"));
+        assert!(report.contains("src/Token.huff"));
     }
 
-    println!("0x{}", hex::encode(entry_point_code));
+    #[test]
+    fn rejects_args_offset_deployed_by_default_constructor() {
+        let ast = parse("#define macro MAIN() = { __argsoffset() }").unwrap();
 
-    Ok(())
+        assert!(analyze_definitions(&ast, ["MAIN"], false, &[]).is_ok());
+        let errs = analyze_definitions(&ast, ["MAIN"], true, &[]).unwrap_err();
+        assert!(matches!(
+            &errs[..],
+            [AnalysisError::ArgsOffsetInDeployedCode { scope, .. }] if scope.ident() == "MAIN"
+        ));
+    }
 }
@@ -0,0 +1,114 @@
+use alloy_primitives::hex;
+use huff_compilation::{Metadata, COMPILER_VERSION};
+use serde_json::{json, Map, Value};
+
+/// Build settings of the compiled code along with its CBOR trailer, for verifiers to reproduce it.
+/// `runtime` is the macro deployed by the entry point and `constructor_args` the ABI encoded
+/// arguments appended to its code, if any.
+pub(crate) fn metadata(
+    filename: &str,
+    entry_point: &str,
+    default_constructor: bool,
+    runtime: Option<&str>,
+    constructor_args: &[u8],
+    appended: bool,
+    metadata: &Metadata,
+) -> Value {
+    let sources: Map<String, Value> = metadata
+        .source_hashes
+        .iter()
+        .map(|(path, hash)| (path.to_string(), json!({ "keccak256": hash.to_string() })))
+        .collect();
+    let overrides: Map<String, Value> = metadata
+        .constant_overrides
+        .iter()
+        .map(|(name, value)| (name.to_string(), format!("{:#x}", value).into()))
+        .collect();
+    json!({
+        "compiler": { "name": "huff2", "version": COMPILER_VERSION },
+        "language": "Huff",
+        "sources": sources,
+        "settings": {
+            "compilationTarget": filename,
+            "evmVersion": metadata.evm_version,
+            "entryPoint": entry_point,
+            "defaultConstructor": default_constructor,
+            "runtime": runtime,
+            "constructorArgs": hex::encode_prefixed(constructor_args),
+            "constantOverrides": overrides,
+            "appendMetadata": appended,
+        },
+        "trailer": hex::encode_prefixed(metadata.trailer()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{keccak256, U256};
+    use huff_analysis::const_overrides::ConstantOverride;
+
+    #[test]
+    fn describes_build() {
+        let overrides = [
+            ConstantOverride {
+                name: "OWNER".to_owned(),
+                value: U256::from(0xbeef),
+            },
+            ConstantOverride {
+                name: "FEE".to_owned(),
+                value: U256::from(30),
+            },
+        ];
+        let sources = [
+            (
+                "src/Token.huff",
+                "#define macro MAIN() = {}\n#include_abi \"IOwned.json\"",
+            ),
+            ("src/IOwned.json", "[]"),
+        ];
+        let metadata = Metadata::new(sources, "cancun", &overrides);
+        let trailer = metadata.trailer();
+        let len = usize::from(u16::from_be_bytes([
+            trailer[trailer.len() - 2],
+            trailer[trailer.len() - 1],
+        ]));
+        assert_eq!(len, trailer.len() - 2);
+
+        let constructor_args = [0x2a; 32];
+        let json = super::metadata(
+            "src/Token.huff",
+            "CONSTRUCTOR",
+            false,
+            Some("MAIN"),
+            &constructor_args,
+            false,
+            &metadata,
+        );
+        assert_eq!(
+            json["sources"],
+            json!({
+                "src/Token.huff": { "keccak256": keccak256(sources[0].1).to_string() },
+                "src/IOwned.json": { "keccak256": keccak256("[]").to_string() },
+            })
+        );
+        assert_eq!(
+            metadata.sources_hash(),
+            keccak256([keccak256(sources[0].1).0, keccak256("[]").0].concat())
+        );
+        assert_eq!(
+            json["settings"],
+            json!({
+                "compilationTarget": "src/Token.huff",
+                "evmVersion": "cancun",
+                "entryPoint": "CONSTRUCTOR",
+                "defaultConstructor": false,
+                "runtime": "MAIN",
+                "constructorArgs": hex::encode_prefixed(constructor_args),
+                "constantOverrides": { "FEE": "0x1e", "OWNER": "0xbeef" },
+                "appendMetadata": false,
+            })
+        );
+        assert_eq!(json["trailer"], hex::encode_prefixed(&trailer));
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{config::ProjectConfig, parse_source, read_source, testing::run_tests, CompileArgs};
+use alloy_primitives::U256;
+use ariadne::{Color, Fmt};
+use clap::Args;
+use evm_glue::opcodes::Opcode;
+use huff_ast::{Definition, Instruction, MacroStatement, Root, RootSection, Span};
+
+#[derive(Args)]
+pub struct MutateArgs {
+    #[clap(help = "Root huff file containing the macros and their tests")]
+    filename: String,
+
+    #[clap(
+        short = 'm',
+        long = "match",
+        help = "Only run tests whose name contains the pattern"
+    )]
+    filter: Option<String>,
+
+    #[command(flatten)]
+    compile: CompileArgs,
+}
+
+/// A change to a single statement of a macro.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Mutant<'src> {
+    /// Index of the macro's section in the root.
+    section: usize,
+    r#macro: &'src str,
+    /// Index of the mutated statement in the macro's body.
+    statement: usize,
+    span: Span,
+    description: String,
+    /// Statements replacing the mutated one.
+    replacement: Vec<MacroStatement<'src>>,
+}
+
+impl<'src> Mutant<'src> {
+    /// The root with the mutated macro.
+    fn apply(&self, ast: &Root<'src>) -> Root<'src> {
+        let mut ast = ast.clone();
+        let RootSection::Definition(Definition::Macro(m)) = &mut ast.0[self.section] else {
+            unreachable!("mutants are only generated for macros")
+        };
+        let mut body = m.body.to_vec();
+        body.splice(
+            self.statement..=self.statement,
+            self.replacement.iter().cloned(),
+        );
+        m.body = body.into_boxed_slice();
+        ast
+    }
+}
+
+/// Replacements of the statement along with their descriptions, stack effects being preserved.
+fn mutations<'src>(stmt: &MacroStatement<'src>) -> Vec<(String, Vec<MacroStatement<'src>>)> {
+    let MacroStatement::Instruction(instruction) = stmt else {
+        return vec![];
+    };
+    let span = instruction.get_span();
+    let op = |op: Opcode| MacroStatement::Instruction(Instruction::Op((op, span)));
+
+    match instruction {
+        Instruction::Op((Opcode::LT, _)) => vec![("lt → gt".to_owned(), vec![op(Opcode::GT)])],
+        Instruction::Op((Opcode::GT, _)) => vec![("gt → lt".to_owned(), vec![op(Opcode::LT)])],
+        Instruction::Op((Opcode::ISZERO, _)) => vec![("iszero removed".to_owned(), vec![])],
+        // Negating the condition swaps the jump and fall through targets.
+        Instruction::Op((Opcode::JUMPI, _)) => vec![(
+            "jumpi targets swapped".to_owned(),
+            vec![op(Opcode::SWAP1), op(Opcode::ISZERO), op(Opcode::SWAP1), op(Opcode::JUMPI)],
+        )],
+        Instruction::Op((Opcode::SSTORE, _)) => vec![(
+            "sstore removed".to_owned(),
+            vec![op(Opcode::POP), op(Opcode::POP)],
+        )],
+        Instruction::VariablePush((value, _)) => {
+            let mutated = if *value == U256::MAX {
+                value - U256::from(1)
+            } else {
+                value + U256::from(1)
+            };
+            vec![(
+                format!("push {:#x} → {:#x}", value, mutated),
+                vec![MacroStatement::Instruction(Instruction::VariablePush((mutated, span)))],
+            )]
+        }
+        _ => vec![],
+    }
+}
+
+/// Every mutant of the file's macros, in source order.
+fn mutants<'src>(ast: &Root<'src>) -> Vec<Mutant<'src>> {
+    let mut mutants = Vec::new();
+    for (section, root_section) in ast.0.iter().enumerate() {
+        let RootSection::Definition(Definition::Macro(m)) = root_section else {
+            continue;
+        };
+        for (statement, stmt) in m.body.iter().enumerate() {
+            for (description, replacement) in mutations(stmt) {
+                mutants.push(Mutant {
+                    section,
+                    r#macro: m.name.0,
+                    statement,
+                    span: stmt.get_span(),
+                    description,
+                    replacement,
+                });
+            }
+        }
+    }
+    mutants
+}
+
+fn line_col(src: &str, offset: usize) -> (usize, usize) {
+    let before = &src[..offset];
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    (before.matches('\n').count() + 1, offset - line_start + 1)
+}
+
+pub fn run(args: MutateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut fuzz = ProjectConfig::load()?.fuzz;
+    // Inputs failing against mutants are not worth replaying against the actual code.
+    fuzz.failures_dir = std::env::temp_dir().join("huff-mutate");
+    let src = read_source(&args.filename)?;
+    let mut included = Vec::new();
+    let (files, ast) = parse_source(&args.filename, &src, &mut included);
+
+    // The name of the first failing test, if any.
+    let run_suite = |ast: &Root| {
+        let (outcomes, _) = run_tests(
+            files,
+            ast,
+            args.filter.as_deref(),
+            &args.compile,
+            &fuzz,
+            false,
+        );
+        if outcomes.is_empty() {
+            return Err("No tests to run against the mutants".to_owned());
+        }
+        Ok(outcomes
+            .into_iter()
+            .find(|outcome| outcome.failure.is_some())
+            .map(|outcome| outcome.name.to_owned()))
+    };
+    if let Some(failing) = run_suite(&ast)? {
+        return Err(format!("Tests must pass before mutating, {} fails", failing).into());
+    }
+
+    let mutants = mutants(&ast);
+    println!(
+        "Testing {} mutant{} of {}",
+        mutants.len(),
+        if mutants.len() == 1 { "" } else { "s" },
+        args.filename
+    );
+    let mut survivors = Vec::new();
+    for mutant in &mutants {
+        let (line, col) = line_col(&src, mutant.span.start);
+        let location = format!("{}:{}:{}", args.filename, line, col);
+        match run_suite(&mutant.apply(&ast))? {
+            Some(killer) => println!(
+                "[{}] {} {}: {} (by {})",
+                "KILLED".fg(Color::Green),
+                location,
+                mutant.r#macro,
+                mutant.description,
+                killer
+            ),
+            None => {
+                println!(
+                    "[{}] {} {}: {}",
+                    "SURVIVED".fg(Color::Red),
+                    location,
+                    mutant.r#macro,
+                    mutant.description
+                );
+                survivors.push((location, mutant));
+            }
+        }
+    }
+
+    let killed = mutants.len() - survivors.len();
+    println!(
+        "Mutation score: {}/{} killed ({:.2}%)",
+        killed,
+        mutants.len(),
+        killed as f64 * 100.0 / mutants.len().max(1) as f64
+    );
+    if !survivors.is_empty() {
+        println!("Surviving mutants:");
+        for (location, mutant) in &survivors {
+            println!(
+                "    {} {}: {}",
+                location, mutant.r#macro, mutant.description
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use huff_ast::parse;
+
+    #[test]
+    fn mutates_statements() {
+        let src = "#define macro CHECK() = {
+    0x01 lt iszero fail jumpi
+    0x00 sstore
+    fail:
+}";
+        let ast = parse(src).unwrap();
+        let mutants = mutants(&ast);
+        let descriptions: Vec<_> = mutants.iter().map(|m| m.description.as_str()).collect();
+        assert_eq!(
+            descriptions,
+            [
+                "push 0x1 → 0x2",
+                "lt → gt",
+                "iszero removed",
+                "jumpi targets swapped",
+                "push 0x0 → 0x1",
+                "sstore removed"
+            ]
+        );
+        assert_eq!(line_col(src, mutants[1].span.start), (2, 10));
+
+        let RootSection::Definition(Definition::Macro(m)) = &mutants[2].apply(&ast).0[0] else {
+            unreachable!()
+        };
+        assert_eq!(m.body.len(), 7);
+        assert!(!m.body.iter().any(|stmt| matches!(
+            stmt,
+            MacroStatement::Instruction(Instruction::Op((Opcode::ISZERO, _)))
+        )));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{compile, decode::AbiDecoder, parse_source, read_source, CompileArgs};
+use alloy_primitives::{hex, Address, Bytes, U256};
+use ariadne::{Color, Fmt};
+use clap::Args;
+use huff_evm::{Env, Evm, ExecutionResult, Fork, Inspector, Status, StorageChange, Transaction};
+
+/// Address the executed code is deployed at.
+pub(crate) const CONTRACT: Address = Address::new([
+    0x5f, 0x5f, 0x5f, 0x5f, 0x5f, 0x5f, 0x5f, 0x5f, 0x5f, 0x5f, 0x5f, 0x5f, 0x5f, 0x5f, 0x5f, 0x5f,
+    0x5f, 0x5f, 0x5f, 0x5f,
+]);
+/// Default sender of the executing transaction.
+pub(crate) const CALLER: Address = Address::new([
+    0xca, 0x11, 0xe7, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00,
+]);
+
+#[derive(Args)]
+pub struct RunArgs {
+    #[clap(help = "Root huff file to compile")]
+    filename: String,
+
+    #[clap(help = "Name of the macro to compile and execute as the contract's runtime code")]
+    entry_point: String,
+
+    #[command(flatten)]
+    call: CallArgs,
+
+    #[command(flatten)]
+    compile: CompileArgs,
+}
+
+/// Parameters of the transaction calling the code.
+#[derive(Args, Clone)]
+pub(crate) struct CallArgs {
+    #[clap(long = "calldata", help = "Hex encoded calldata", default_value = "0x")]
+    pub calldata: Bytes,
+
+    #[clap(
+        long = "value",
+        help = "Wei sent along with the call, decimal or 0x prefixed hex",
+        default_value = "0"
+    )]
+    pub value: U256,
+
+    #[clap(long = "caller", help = "Sender of the call", default_value_t = CALLER)]
+    pub caller: Address,
+
+    #[clap(
+        long = "gas-limit",
+        help = "Gas limit of the call",
+        default_value = "30000000"
+    )]
+    pub gas_limit: u64,
+}
+
+impl Default for CallArgs {
+    fn default() -> Self {
+        Self {
+            calldata: Bytes::new(),
+            value: U256::ZERO,
+            caller: CALLER,
+            gas_limit: 30_000_000,
+        }
+    }
+}
+
+/// Result of executing code with [`execute`].
+pub(crate) struct Execution {
+    pub result: ExecutionResult,
+    pub storage_changes: Vec<StorageChange>,
+}
+
+/// Calls `code` deployed at [`CONTRACT`], funding the caller with the value sent. `setup` can
+/// prepare the world state beforehand.
+pub(crate) fn execute<I: Inspector>(
+    fork: Fork,
+    code: Vec<u8>,
+    call: &CallArgs,
+    inspector: I,
+    setup: impl FnOnce(&mut Evm<I>),
+) -> Result<Execution, Box<dyn std::error::Error>> {
+    let mut evm = Evm::with_inspector(
+        Env {
+            fork,
+            ..Default::default()
+        },
+        inspector,
+    );
+    evm.state.set_code(CONTRACT, code);
+    evm.state.set_balance(call.caller, call.value);
+    setup(&mut evm);
+
+    let before = evm.state.clone();
+    let result = evm.transact(&Transaction {
+        caller: call.caller,
+        to: Some(CONTRACT),
+        value: call.value,
+        data: call.calldata.clone(),
+        gas_limit: call.gas_limit,
+        ..Default::default()
+    })?;
+    let storage_changes = before.storage_changes(&evm.state);
+
+    Ok(Execution {
+        result,
+        storage_changes,
+    })
+}
+
+pub fn run(args: RunArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let src = read_source(&args.filename)?;
+    let mut included = Vec::new();
+    let (files, ast) = parse_source(&args.filename, &src, &mut included);
+    let (_, code) = compile(files, &ast, &args.entry_point, &args.compile);
+
+    let Execution {
+        result,
+        storage_changes,
+    } = execute(
+        args.compile.evm_version.into(),
+        code,
+        &args.call,
+        (),
+        |_| {},
+    )?;
+    let decoder = AbiDecoder::new(&ast);
+
+    match &result.status {
+        Status::Success => {
+            println!("{}", "Success".fg(Color::Green));
+            println!("Return data: {}", hex::encode_prefixed(&result.output));
+        }
+        Status::Revert => {
+            println!("{}", "Revert".fg(Color::Red));
+            println!("Revert data: {}", hex::encode_prefixed(&result.output));
+            if let Some(error) = decoder.decode_error(&result.output) {
+                println!("Error: {}", error);
+            }
+        }
+        Status::Halt(reason) => println!("{}: {}", "Halt".fg(Color::Red), reason),
+    }
+    println!("Gas used: {}", result.gas_used);
+
+    if !result.logs.is_empty() {
+        println!("Logs:");
+        for log in &result.logs {
+            match decoder.decode_log(log) {
+                Some(event) => println!("  {}", event),
+                None => {
+                    let topics: Vec<String> =
+                        log.topics().iter().map(ToString::to_string).collect();
+                    println!(
+                        "  topics: [{}] data: {}",
+                        topics.join(", "),
+                        hex::encode_prefixed(&log.data.data)
+                    );
+                }
+            }
+        }
+    }
+
+    if !storage_changes.is_empty() {
+        println!("Storage changes:");
+        for change in &storage_changes {
+            println!(
+                "  {} slot {:#x}: {:#x} -> {:#x}",
+                change.address, change.slot, change.before, change.after
+            );
+        }
+    }
+
+    if !result.status.is_success() {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use huff_evm::opcode::{CALLVALUE, PUSH0, SSTORE, STOP};
+
+    #[test]
+    fn executes_against_contract() {
+        let call = CallArgs {
+            value: U256::from(42),
+            ..Default::default()
+        };
+        let code = vec![CALLVALUE, PUSH0, SSTORE, STOP];
+        let execution = execute(Fork::Cancun, code, &call, (), |_| {}).unwrap();
+
+        assert_eq!(execution.result.status, Status::Success);
+        assert_eq!(
+            execution.storage_changes,
+            [StorageChange {
+                address: CONTRACT,
+                slot: U256::ZERO,
+                before: U256::ZERO,
+                after: U256::from(42),
+            }]
+        );
+    }
+}
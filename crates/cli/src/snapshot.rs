@@ -0,0 +1,239 @@
+use crate::{
+    config::ProjectConfig,
+    parse_source, read_source,
+    testing::{report, run_tests, TestOutcome},
+    CompileArgs,
+};
+use ariadne::{Color, Fmt};
+use clap::Args;
+use std::{collections::BTreeMap, path::PathBuf};
+
+#[derive(Args)]
+pub struct SnapshotArgs {
+    #[clap(help = "Root huff file containing the tests")]
+    filename: String,
+
+    #[clap(
+        short = 'm',
+        long = "match",
+        help = "Only run tests whose name contains the pattern"
+    )]
+    filter: Option<String>,
+
+    #[clap(
+        long = "snap",
+        help = "Path of the snapshot file",
+        default_value = ".gas-snapshot"
+    )]
+    snap: PathBuf,
+
+    #[clap(
+        long = "check",
+        help = "Compare against the snapshot instead of writing it, failing if gas regressed"
+    )]
+    check: bool,
+
+    #[clap(
+        long = "diff",
+        help = "Print the gas changes against the snapshot instead of writing it"
+    )]
+    diff: bool,
+
+    #[clap(
+        long = "tolerance",
+        help = "Gas increase in percent tolerated by `--check`",
+        default_value = "0"
+    )]
+    tolerance: f64,
+
+    #[command(flatten)]
+    compile: CompileArgs,
+}
+
+/// Snapshot of the tests' gas, one `NAME (gas: N)` or `NAME (runs: N, μ: N)` line per test
+/// sorted by name.
+fn format_snapshot(outcomes: &[TestOutcome]) -> String {
+    let lines: BTreeMap<&str, String> = outcomes
+        .iter()
+        .map(|outcome| {
+            (
+                outcome.name,
+                format!("{} ({})\n", outcome.name, outcome.gas()),
+            )
+        })
+        .collect();
+    lines.into_values().collect()
+}
+
+/// The gas of each test in a snapshot, the mean for fuzz tests.
+fn parse_snapshot(content: &str) -> Result<BTreeMap<&str, u64>, String> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let invalid = || format!("Invalid snapshot line `{}`", line);
+            let (name, gas) = line
+                .strip_suffix(')')
+                .and_then(|line| line.split_once(" ("))
+                .ok_or_else(invalid)?;
+            let gas = gas
+                .rsplit_once(": ")
+                .and_then(|(_, gas)| gas.parse().ok())
+                .ok_or_else(invalid)?;
+            Ok((name, gas))
+        })
+        .collect()
+}
+
+/// Tests whose gas increased by more than `tolerance` percent, with their gas before and after.
+fn regressions<'a>(
+    before: &BTreeMap<&'a str, u64>,
+    after: &BTreeMap<&str, u64>,
+    tolerance: f64,
+) -> Vec<(&'a str, u64, u64)> {
+    before
+        .iter()
+        .filter_map(|(name, &before)| {
+            let after = *after.get(name)?;
+            (after as f64 > before as f64 * (1.0 + tolerance / 100.0))
+                .then_some((*name, before, after))
+        })
+        .collect()
+}
+
+fn delta(before: u64, after: u64) -> String {
+    let delta = after as i128 - before as i128;
+    let text = match before {
+        0 => format!("{:+}", delta),
+        _ => format!("{:+} {:+.2}%", delta, delta as f64 * 100.0 / before as f64),
+    };
+    let color = match delta.signum() {
+        1 => Color::Red,
+        -1 => Color::Green,
+        _ => Color::White,
+    };
+    format!("{}", text.fg(color))
+}
+
+fn print_diff(before: &BTreeMap<&str, u64>, after: &BTreeMap<&str, u64>) {
+    for (name, &gas) in after {
+        match before.get(name) {
+            Some(&previous) if previous != gas => {
+                println!(
+                    "{} (gas: {} → {} | {})",
+                    name,
+                    previous,
+                    gas,
+                    delta(previous, gas)
+                )
+            }
+            Some(_) => {}
+            None => println!("{} (gas: {}, {})", name, gas, "new".fg(Color::Yellow)),
+        }
+    }
+    for name in before.keys().filter(|name| !after.contains_key(*name)) {
+        println!("{} ({})", name, "removed".fg(Color::Yellow));
+    }
+
+    let (total_before, total_after) = before
+        .iter()
+        .filter_map(|(name, &previous)| Some((previous, *after.get(name)?)))
+        .fold((0, 0), |(total_before, total_after), (previous, gas)| {
+            (total_before + previous, total_after + gas)
+        });
+    println!("Overall gas change: {}", delta(total_before, total_after));
+}
+
+pub fn run(args: SnapshotArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let fuzz = ProjectConfig::load()?.fuzz;
+    let src = read_source(&args.filename)?;
+    let mut included = Vec::new();
+    let (files, ast) = parse_source(&args.filename, &src, &mut included);
+    let (outcomes, filtered_out) = run_tests(
+        files,
+        &ast,
+        args.filter.as_deref(),
+        &args.compile,
+        &fuzz,
+        false,
+    );
+    if !report(
+        &args.filename,
+        &outcomes,
+        filtered_out,
+        fuzz.seed.unwrap_or_default(),
+    ) {
+        std::process::exit(1);
+    }
+
+    let snapshot = format_snapshot(&outcomes);
+    if !args.check && !args.diff {
+        std::fs::write(&args.snap, snapshot)?;
+        println!("Wrote gas snapshot to {}", args.snap.display());
+        return Ok(());
+    }
+
+    let stored = std::fs::read_to_string(&args.snap)
+        .map_err(|err| format!("Failed to read {}: {}", args.snap.display(), err))?;
+    let before = parse_snapshot(&stored)?;
+    let after = parse_snapshot(&snapshot)?;
+    if args.diff {
+        print_diff(&before, &after);
+    }
+    if args.check {
+        let regressions = regressions(&before, &after, args.tolerance);
+        for (name, before, after) in &regressions {
+            println!(
+                "{}: {} (gas: {} → {} | {})",
+                "Gas regression".fg(Color::Red),
+                name,
+                before,
+                after,
+                delta(*before, *after)
+            );
+        }
+        if !regressions.is_empty() {
+            std::process::exit(1);
+        }
+        println!("No gas regressions against {}", args.snap.display());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_snapshot() {
+        let outcome = |name, gas_used, runs| TestOutcome {
+            name,
+            gas_used,
+            runs,
+            failure: None,
+            coverage: None,
+        };
+        let snapshot = format_snapshot(&[
+            outcome("TRANSFER", 120, None),
+            outcome("FUZZ_APPROVE", 80, Some(256)),
+        ]);
+        assert_eq!(
+            snapshot,
+            "FUZZ_APPROVE (runs: 256, μ: 80)\nTRANSFER (gas: 120)\n"
+        );
+        assert_eq!(
+            parse_snapshot(&snapshot).unwrap(),
+            BTreeMap::from([("FUZZ_APPROVE", 80), ("TRANSFER", 120)])
+        );
+        assert!(parse_snapshot("TRANSFER 120").is_err());
+    }
+
+    #[test]
+    fn finds_regressions_beyond_tolerance() {
+        let before = BTreeMap::from([("A", 100), ("B", 100), ("C", 100)]);
+        let after = BTreeMap::from([("A", 105), ("B", 95), ("D", 1000)]);
+        assert_eq!(regressions(&before, &after, 0.0), [("A", 100, 105)]);
+        assert!(regressions(&before, &after, 5.0).is_empty());
+    }
+}
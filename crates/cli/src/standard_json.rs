@@ -0,0 +1,530 @@
+use crate::{
+    analyze_definitions,
+    artifact::{artifact, Contract},
+    include_abis, metadata, read_included_abis,
+    versions::EvmVersion,
+    Files,
+};
+use huff_analysis::{
+    const_overrides::{parse_constant_override, ConstantOverride},
+    errors::AnalysisError,
+};
+use huff_ast::{parse, Definition, RootSection};
+use huff_compilation::{CompileGlobals, Metadata};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Read,
+    ops::Range,
+};
+
+/// Outputs to select per contract, by file and contract name, `*` matching any of them.
+type OutputSelection = BTreeMap<String, BTreeMap<String, Vec<String>>>;
+
+/// Compiler input, the equivalent of solc's standard JSON input.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Input {
+    language: Option<String>,
+    /// Sources by their path, including the ABIs `#include_abi` reads, only `.huff` files being
+    /// compiled.
+    sources: BTreeMap<String, Source>,
+    #[serde(default)]
+    settings: Settings,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Source {
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Settings {
+    evm_version: EvmVersion,
+    optimizer: Optimizer,
+    /// Constant values by name, as hex or decimal strings or as numbers.
+    constant_overrides: BTreeMap<String, Value>,
+    /// Macros compiled as contracts of every source defining them.
+    entry_points: Vec<String>,
+    /// Wraps the entry points in a minimal constructor that deploys them.
+    default_constructor: bool,
+    metadata: MetadataSettings,
+    /// All outputs are selected if not given.
+    output_selection: Option<OutputSelection>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            evm_version: EvmVersion::Paris,
+            optimizer: Optimizer::default(),
+            constant_overrides: BTreeMap::new(),
+            entry_points: vec!["MAIN".to_owned()],
+            default_constructor: false,
+            metadata: MetadataSettings::default(),
+            output_selection: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct Optimizer {
+    /// Pushes labels with the fewest bytes rather than always as `PUSH2`.
+    enabled: bool,
+}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct MetadataSettings {
+    /// Appends the CBOR metadata trailer to the entry points' code.
+    #[serde(rename = "appendCBOR")]
+    append_cbor: bool,
+}
+
+/// Reads the input from stdin and prints the output to stdout. Errors are part of the output.
+pub(crate) fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+    let output = match serde_json::from_str(&input) {
+        Ok(input) => compile(&input),
+        Err(err) => json!({ "errors": [error("JSONError", err.to_string(), None)] }),
+    };
+    println!("{}", serde_json::to_string(&output)?);
+    Ok(())
+}
+
+/// Output error in solc's format, `location` being the file and byte range it occurred at.
+fn error(kind: &str, message: String, location: Option<(&str, Range<usize>)>) -> Value {
+    let mut error = json!({
+        "component": "general",
+        "severity": "error",
+        "type": kind,
+        "message": message,
+    });
+    if let Some((file, range)) = location {
+        error["sourceLocation"] = json!({ "file": file, "start": range.start, "end": range.end });
+    }
+    error
+}
+
+/// Compiles the entry points of every source, sources being numbered in the order of their paths.
+pub(crate) fn compile(input: &Input) -> Value {
+    let mut errors = Vec::new();
+    if let Some(language) = input.language.as_deref().filter(|lang| *lang != "Huff") {
+        let message = format!(
+            "Only \"Huff\" is supported as a language, got {:?}",
+            language
+        );
+        return json!({ "errors": [error("JSONError", message, None)] });
+    }
+    let mut overrides = Vec::with_capacity(input.settings.constant_overrides.len());
+    for (name, value) in &input.settings.constant_overrides {
+        let value = match value {
+            Value::String(value) => value.clone(),
+            Value::Number(value) => value.to_string(),
+            _ => {
+                let message = format!("Expected hex or decimal value for constant {}", name);
+                errors.push(error("JSONError", message, None));
+                continue;
+            }
+        };
+        match parse_constant_override(&format!("{}={}", name, value)) {
+            Ok(const_override) => overrides.push(const_override),
+            Err(message) => errors.push(error("JSONError", message, None)),
+        }
+    }
+    if !errors.is_empty() {
+        return json!({ "errors": errors });
+    }
+
+    let mut sources = Map::new();
+    let mut contracts = Map::new();
+    let mut overridden = BTreeSet::new();
+    for (id, path) in input.sources.keys().enumerate() {
+        sources.insert(path.clone(), json!({ "id": id }));
+        // Other files, e.g. ABIs, are only read by `#include_abi`.
+        if !path.ends_with(".huff") {
+            continue;
+        }
+        let file_contracts =
+            compile_source(input, id, path, &overrides, &mut overridden, &mut errors);
+        if !file_contracts.is_empty() {
+            contracts.insert(path.clone(), file_contracts.into());
+        }
+    }
+    // Overrides apply to the sources defining the constant, only missing from all of them is an
+    // error.
+    for const_override in &overrides {
+        let name = const_override.name.as_str();
+        if !overridden.contains(name) {
+            let message = AnalysisError::NoConstantToOverride { name }.message();
+            errors.push(error("AnalysisError", message, None));
+        }
+    }
+    json!({ "errors": errors, "sources": sources, "contracts": contracts })
+}
+
+/// Compiles the entry points the source defines, pushing the errors found and the names of the
+/// overridden constants it defines.
+fn compile_source(
+    input: &Input,
+    id: usize,
+    path: &str,
+    overrides: &[ConstantOverride],
+    overridden: &mut BTreeSet<String>,
+    errors: &mut Vec<Value>,
+) -> Map<String, Value> {
+    let settings = &input.settings;
+    let ast = match parse(&input.sources[path].content) {
+        Ok(ast) => ast,
+        Err(errs) => {
+            errors.extend(errs.into_iter().map(|err| {
+                let location = (path, err.span().into_range());
+                error("ParserError", err.reason().to_string(), Some(location))
+            }));
+            return Map::new();
+        }
+    };
+    // The input holds every file, so builds don't depend on the file system.
+    let read = |included: &str| match input.sources.get(included) {
+        Some(source) => Ok(source.content.clone()),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "not in the input's sources",
+        )),
+    };
+    let included = match read_included_abis(path, &ast, read) {
+        Ok(included) => included,
+        Err((included, err)) => {
+            let message = format!("Failed to read '{}': {}", included, err);
+            errors.push(error("IOError", message, None));
+            return Map::new();
+        }
+    };
+    let ast = match include_abis(ast, &included) {
+        Ok(ast) => ast,
+        Err((abi, err)) => {
+            let location = (abi.path.as_str(), err.span().into_range());
+            errors.push(error(
+                "ParserError",
+                err.reason().to_string(),
+                Some(location),
+            ));
+            return Map::new();
+        }
+    };
+
+    let files = Files {
+        filename: path,
+        src: &input.sources[path].content,
+        included: &included,
+    };
+    let defines_constant = |name: &str| {
+        ast.0.iter().any(|section| match section {
+            RootSection::Definition(Definition::Constant(c)) => c.name.0 == name,
+            _ => false,
+        })
+    };
+    let overrides: Vec<ConstantOverride> = overrides
+        .iter()
+        .filter(|const_override| defines_constant(&const_override.name))
+        .cloned()
+        .collect();
+    overridden.extend(
+        overrides
+            .iter()
+            .map(|const_override| const_override.name.clone()),
+    );
+
+    let defines_macro = |name: &str| {
+        ast.0.iter().any(|section| match section {
+            RootSection::Definition(Definition::Macro(m)) => m.name.0 == name,
+            _ => false,
+        })
+    };
+    let entry_points: Vec<&str> = settings
+        .entry_points
+        .iter()
+        .map(String::as_str)
+        .filter(|entry_point| defines_macro(entry_point))
+        .collect();
+    // Sources without entry points are only included by others.
+    if entry_points.is_empty() {
+        return Map::new();
+    }
+    let unique_defs = match analyze_definitions(
+        &ast,
+        entry_points.iter().copied(),
+        settings.default_constructor,
+        &overrides,
+    ) {
+        Ok(unique_defs) => unique_defs,
+        Err(analysis_errors) => {
+            errors.extend(analysis_errors.into_iter().map(|err| {
+                let file = err
+                    .spanned_definition()
+                    .map_or(path, |def| files.file_of(def));
+                let location = err.span().map(|span| (file, span.into_range()));
+                error("AnalysisError", err.message(), location)
+            }));
+            return Map::new();
+        }
+    };
+    let mut config = CompileGlobals::new(
+        settings.optimizer.enabled,
+        settings.evm_version.allows_push0(),
+        unique_defs,
+        &overrides,
+    );
+    let metadata = Metadata::new(files.contents(), settings.evm_version.name(), &overrides);
+    if settings.metadata.append_cbor {
+        config.metadata_trailer = Some(metadata.trailer());
+    }
+
+    let mut contracts = Map::new();
+    for entry_point in entry_points {
+        let mut contract =
+            Contract::compile(&mut config, entry_point, settings.default_constructor, None);
+        contract.source_map = with_source_id(&contract.source_map, id);
+        contract.deployed_source_map = with_source_id(&contract.deployed_source_map, id);
+        let artifact = artifact(&ast, &contract);
+        let document = metadata::metadata(
+            path,
+            entry_point,
+            settings.default_constructor,
+            None,
+            &[],
+            settings.metadata.append_cbor,
+            &metadata,
+        );
+        let outputs = json!({
+            "abi": artifact["abi"],
+            "metadata": document.to_string(),
+            "evm": {
+                "bytecode": artifact["bytecode"],
+                "deployedBytecode": artifact["deployedBytecode"],
+                "methodIdentifiers": artifact["methodIdentifiers"],
+            },
+        });
+        let selected = selection(settings.output_selection.as_ref(), path, entry_point);
+        if let Some(outputs) = select("", outputs, &selected) {
+            contracts.insert(entry_point.to_owned(), outputs);
+        }
+    }
+    contracts
+}
+
+/// Points the entries of a source map generated for a single source to the source's `id`.
+fn with_source_id(source_map: &str, id: usize) -> String {
+    let id = id.to_string();
+    source_map
+        .split(';')
+        .map(|entry| {
+            let mut fields: Vec<&str> = entry.split(':').collect();
+            if fields.get(2) == Some(&"0") {
+                fields[2] = &id;
+            }
+            fields.join(":")
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Outputs selected for the contract, everything if there's no selection.
+fn selection<'a>(
+    output_selection: Option<&'a OutputSelection>,
+    file: &str,
+    contract: &str,
+) -> Vec<&'a str> {
+    let Some(output_selection) = output_selection else {
+        return vec!["*"];
+    };
+    [file, "*"]
+        .into_iter()
+        .filter_map(|file| output_selection.get(file))
+        .flat_map(|contracts| [contract, "*"].map(|contract| contracts.get(contract)))
+        .flatten()
+        .flatten()
+        .map(String::as_str)
+        .collect()
+}
+
+/// Keeps the outputs that are selected, `path` being the output's dotted path. Selecting an output
+/// selects everything nested in it, e.g. `evm.bytecode` selects `evm.bytecode.object`.
+fn select(path: &str, outputs: Value, selected: &[&str]) -> Option<Value> {
+    let is_selected = selected.iter().any(|selected| {
+        *selected == "*"
+            || path
+                .strip_prefix(selected)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    });
+    if is_selected {
+        return Some(outputs);
+    }
+    let Value::Object(outputs) = outputs else {
+        return None;
+    };
+    let outputs: Map<String, Value> = outputs
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let path = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            Some((key, select(&path, value, selected)?))
+        })
+        .collect();
+    (!outputs.is_empty()).then(|| outputs.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compiles_selected_outputs() {
+        let input: Input = serde_json::from_value(json!({
+            "language": "Huff",
+            "sources": {
+                "src/Lib.huff": { "content": "#define constant OWNER = 0x01" },
+                "src/Token.huff": {
+                    "content": "#define function owner() view returns (address)
+#define constant OWNER = 0x01
+#define macro MAIN() = takes(0) returns(0) { [OWNER] }"
+                },
+                "src/Broken.huff": {
+                    "content": "#define constant OWNER = 0x01
+        #define macro MAIN() = takes(0) returns(0) { UNKNOWN() }"
+                },
+            },
+            "settings": {
+                "evmVersion": "shanghai",
+                "constantOverrides": { "OWNER": 2 },
+                "outputSelection": {
+                    "*": { "*": ["evm.bytecode.object", "evm.methodIdentifiers"] },
+                },
+            },
+        }))
+        .unwrap();
+        let output = compile(&input);
+
+        assert_eq!(
+            output["sources"],
+            json!({
+                "src/Broken.huff": { "id": 0 },
+                "src/Lib.huff": { "id": 1 },
+                "src/Token.huff": { "id": 2 },
+            })
+        );
+        assert_eq!(
+            output["contracts"],
+            json!({
+                "src/Token.huff": {
+                    "MAIN": {
+                        "evm": {
+                            "bytecode": { "object": "0x6002" },
+                            "methodIdentifiers": { "owner()": "8da5cb5b" },
+                        },
+                    },
+                },
+            })
+        );
+        let errors = output["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0]["type"], "AnalysisError");
+        assert_eq!(errors[0]["sourceLocation"]["file"], "src/Broken.huff");
+    }
+
+    #[test]
+    fn overrides_constants_of_sources_defining_them() {
+        let input: Input = serde_json::from_value(json!({
+            "sources": {
+                "src/Owned.huff": {
+                    "content": "#define constant OWNER = 0x01
+#define macro MAIN() = takes(0) returns(0) { [OWNER] }"
+                },
+                "src/Token.huff": { "content": "#define macro MAIN() = takes(0) returns(0) { 0x03 }" },
+            },
+            "settings": {
+                "constantOverrides": { "OWNER": 2, "FEE": 30 },
+                "outputSelection": { "*": { "*": ["evm.bytecode.object"] } },
+            },
+        }))
+        .unwrap();
+        let output = compile(&input);
+
+        assert_eq!(
+            output["contracts"],
+            json!({
+                "src/Owned.huff": { "MAIN": { "evm": { "bytecode": { "object": "0x6002" } } } },
+                "src/Token.huff": { "MAIN": { "evm": { "bytecode": { "object": "0x6003" } } } },
+            })
+        );
+        assert_eq!(
+            output["errors"],
+            json!([{
+                "component": "general",
+                "severity": "error",
+                "type": "AnalysisError",
+                "message": "Constant FEE to be overriden not found",
+            }])
+        );
+    }
+
+    #[test]
+    fn includes_abis_from_sources() {
+        let input: Input = serde_json::from_value(json!({
+            "sources": {
+                "src/IOwned.json": { "content": r#"[{"type": "function", "name": "owner", "inputs": [], "outputs": [], "stateMutability": "view"}]"# },
+                "src/Owned.huff": {
+                    "content": "#include_abi \"IOwned.json\"
+#define macro MAIN() = takes(0) returns(0) { __FUNC_SIG(owner) }"
+                },
+                "src/Token.huff": {
+                    "content": "#include_abi \"Cargo.toml\"
+#define macro MAIN() = takes(0) returns(0) { 0x03 }"
+                },
+            },
+            "settings": {
+                "outputSelection": { "*": { "*": ["evm.bytecode.object"] } },
+            },
+        }))
+        .unwrap();
+        let output = compile(&input);
+
+        assert_eq!(
+            output["contracts"],
+            json!({
+                "src/Owned.huff": { "MAIN": { "evm": { "bytecode": { "object": "0x638da5cb5b" } } } },
+            })
+        );
+        assert_eq!(
+            output["errors"],
+            json!([{
+                "component": "general",
+                "severity": "error",
+                "type": "IOError",
+                "message": "Failed to read 'src/Cargo.toml': not in the input's sources",
+            }])
+        );
+    }
+
+    #[test]
+    fn points_source_maps_to_source() {
+        assert_eq!(
+            with_source_id("0:2:0:-;;-1:-1:-1:-;4:1::i", 3),
+            "0:2:3:-;;-1:-1:-1:-;4:1::i"
+        );
+    }
+}
@@ -0,0 +1,514 @@
+use crate::{
+    analyze,
+    config::ProjectConfig,
+    coverage::{Coverage, PcRecorder},
+    decode::AbiDecoder,
+    fuzz::{self, FuzzConfig, Rng},
+    generate, parse_source, read_source,
+    run::{execute, CallArgs, Execution, CONTRACT},
+    CompileArgs, Files,
+};
+use alloy_dyn_abi::DynSolType;
+use alloy_primitives::{hex, Bytes, FixedBytes, U256};
+use ariadne::{Color, Fmt};
+use clap::Args;
+use huff_ast::{
+    compute_selector, Decorator, Definition, IdentifiableNode, Root, RootSection, WordRef,
+};
+use huff_compilation::{CompileGlobals, PcTable};
+use huff_evm::{gas, Fork, Inspector, Status};
+use std::{
+    hash::{BuildHasher, RandomState},
+    num::NonZeroUsize,
+};
+
+#[derive(Args)]
+pub struct TestArgs {
+    #[clap(help = "Root huff file containing the tests")]
+    filename: String,
+
+    #[clap(
+        short = 'm',
+        long = "match",
+        help = "Only run tests whose name contains the pattern"
+    )]
+    filter: Option<String>,
+
+    #[clap(
+        long = "fuzz-runs",
+        help = "Random inputs each fuzz test is run with, overrides `fuzz.runs` of huff.toml"
+    )]
+    fuzz_runs: Option<u32>,
+
+    #[clap(
+        long = "fuzz-seed",
+        value_parser = parse_seed,
+        help = "Seed of the fuzz inputs, decimal or 0x prefixed hex, random by default"
+    )]
+    fuzz_seed: Option<u64>,
+
+    #[command(flatten)]
+    compile: CompileArgs,
+}
+
+fn parse_seed(seed: &str) -> Result<u64, std::num::ParseIntError> {
+    match seed.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => seed.parse(),
+    }
+}
+
+pub(crate) struct TestOutcome<'src> {
+    pub name: &'src str,
+    /// Gas used by the test's code, excluding the intrinsic transaction cost. The mean of the
+    /// passing runs for fuzz tests.
+    pub gas_used: u64,
+    /// Inputs a fuzz test was run with, `None` for other tests.
+    pub runs: Option<u32>,
+    /// Why the test failed, `None` if it passed.
+    pub failure: Option<String>,
+    /// Statements and branches executed by the test, if coverage was recorded.
+    pub coverage: Option<Coverage>,
+}
+
+impl TestOutcome<'_> {
+    pub fn gas(&self) -> String {
+        match self.runs {
+            Some(runs) => format!("runs: {}, μ: {}", runs, self.gas_used),
+            None => format!("gas: {}", self.gas_used),
+        }
+    }
+}
+
+/// Compiles and runs the file's tests whose name contains the filter, in parallel. Returns the
+/// outcomes in definition order along with the number of tests filtered out. Fuzz inputs are
+/// generated from seed zero unless the config sets one.
+pub(crate) fn run_tests<'a>(
+    files: Files,
+    ast: &'a Root<'a>,
+    filter: Option<&str>,
+    args: &'a CompileArgs,
+    fuzz: &FuzzConfig,
+    coverage: bool,
+) -> (Vec<TestOutcome<'a>>, usize) {
+    let tests: Vec<&str> = ast
+        .0
+        .iter()
+        .filter_map(|section| match section {
+            RootSection::Definition(def @ Definition::Test(_)) => Some(def.ident()),
+            _ => None,
+        })
+        .collect();
+    let total = tests.len();
+    let tests: Vec<&str> = tests
+        .into_iter()
+        .filter(|name| filter.is_none_or(|filter| name.contains(filter)))
+        .collect();
+    let filtered_out = total - tests.len();
+
+    let mut config = analyze(files, ast, tests.iter().copied(), false, args);
+    let cases: Vec<TestCase> = tests
+        .into_iter()
+        .map(|name| TestCase::new(&mut config, name))
+        .collect();
+
+    let decoder = AbiDecoder::new(ast);
+    let fork = args.evm_version.into();
+    let jobs = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let chunk_size = cases.len().div_ceil(jobs).max(1);
+    let outcomes = std::thread::scope(|scope| {
+        let handles: Vec<_> = cases
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let decoder = &decoder;
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|case| case.run(fork, decoder, files.filename, fuzz, coverage))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("test thread panicked"))
+            .collect()
+    });
+
+    (outcomes, filtered_out)
+}
+
+/// A compiled test along with the setup and expectation given by its decorators.
+struct TestCase<'src> {
+    name: &'src str,
+    code: Vec<u8>,
+    pc_table: PcTable<'src>,
+    call: CallArgs,
+    storage: Vec<(U256, U256)>,
+    /// `Some` if the test has to revert, with the error's name and selector if given.
+    expect_revert: Option<Option<(&'src str, FixedBytes<4>)>>,
+    /// Argument types of a fuzz test, appended to the calldata.
+    fuzz: Option<Vec<DynSolType>>,
+}
+
+impl<'src> TestCase<'src> {
+    fn new(config: &mut CompileGlobals<'src, 'src>, name: &'src str) -> Self {
+        let (code, pc_table) = generate(config, name);
+        let Some(Definition::Test(test)) = config.defs.get(name) else {
+            panic!("test not found despite no errors in analysis")
+        };
+
+        let word = |word: &WordRef| match word {
+            WordRef::Literal((value, _)) => *value,
+            WordRef::Constant((name, _)) => config.constants[name],
+        };
+        let mut case = Self {
+            name,
+            code,
+            pc_table,
+            call: CallArgs::default(),
+            storage: Vec::new(),
+            expect_revert: None,
+            fuzz: test
+                .fuzz
+                .as_ref()
+                .map(|args| args.iter().map(|(ty, _)| ty.clone()).collect()),
+        };
+        for (decorator, _) in test.decorators.iter() {
+            match decorator {
+                Decorator::Calldata(data) => case.call.calldata = Bytes::from(data.to_vec()),
+                Decorator::Value(value) => case.call.value = *value,
+                Decorator::Caller(caller) => case.call.caller = *caller,
+                Decorator::Storage { slot, value } => case.storage.push((word(slot), word(value))),
+                Decorator::ExpectRevert(error) => {
+                    case.expect_revert = Some(error.map(|(error, _)| {
+                        let Some(Definition::SolError(def)) = config.defs.get(error) else {
+                            panic!("error not found despite no errors in analysis")
+                        };
+                        (error, compute_selector(&def.name, &def.args))
+                    }))
+                }
+            }
+        }
+        case
+    }
+
+    fn run(
+        &self,
+        fork: Fork,
+        decoder: &AbiDecoder,
+        filename: &str,
+        config: &FuzzConfig,
+        coverage: bool,
+    ) -> TestOutcome<'src> {
+        let mut recorder = coverage.then(PcRecorder::default);
+        let coverage = |recorder: Option<PcRecorder>| {
+            recorder.map(|recorder| Coverage::from_recorded(&self.pc_table, &recorder))
+        };
+        let Some(types) = &self.fuzz else {
+            let (gas_used, failure) =
+                self.run_once(fork, decoder, &self.call.calldata, &mut recorder);
+            return TestOutcome {
+                name: self.name,
+                gas_used,
+                runs: None,
+                failure,
+                coverage: coverage(recorder),
+            };
+        };
+
+        // A previously failing input is replayed first, until it passes.
+        let saved = fuzz::load_failure(&config.failures_dir, filename, self.name);
+        let mut rng = Rng::new(config.seed.unwrap_or_default());
+        let seeds = saved
+            .into_iter()
+            .chain(std::iter::repeat_with(|| rng.next_u64()).take(config.runs as usize));
+        let campaign = fuzz::fuzz(types, &self.call.calldata, seeds, |calldata| {
+            self.run_once(
+                fork,
+                decoder,
+                &Bytes::copy_from_slice(calldata),
+                &mut recorder,
+            )
+        });
+
+        let failing_seed = campaign.counterexample.as_ref().map(|example| example.seed);
+        if let Err(err) =
+            fuzz::persist_failure(&config.failures_dir, filename, self.name, failing_seed)
+        {
+            eprintln!(
+                "{}: failed to save the failing input of {}: {}",
+                "Warning".fg(Color::Yellow),
+                self.name,
+                err
+            );
+        }
+        TestOutcome {
+            name: self.name,
+            gas_used: campaign.mean_gas,
+            runs: Some(campaign.runs),
+            failure: campaign
+                .counterexample
+                .map(|counterexample| counterexample.describe()),
+            coverage: coverage(recorder),
+        }
+    }
+
+    /// Runs the test with the given calldata, returning the gas used and why it failed if it did.
+    fn run_once(
+        &self,
+        fork: Fork,
+        decoder: &AbiDecoder,
+        calldata: &Bytes,
+        inspector: impl Inspector,
+    ) -> (u64, Option<String>) {
+        let call = CallArgs {
+            calldata: calldata.clone(),
+            ..self.call.clone()
+        };
+        let execution = execute(fork, self.code.clone(), &call, inspector, |evm| {
+            for (slot, value) in &self.storage {
+                evm.state.set_storage(CONTRACT, *slot, *value);
+            }
+        });
+        let Execution { result, .. } = match execution {
+            Ok(execution) => execution,
+            Err(err) => return (0, Some(err.to_string())),
+        };
+
+        let reverted = || match decoder.decode_error(&result.output) {
+            Some(error) => format!("reverted with {}", error),
+            None if result.output.is_empty() => "reverted".to_owned(),
+            None => format!("reverted with {}", hex::encode_prefixed(&result.output)),
+        };
+        let failure = match (&result.status, self.expect_revert) {
+            (Status::Halt(reason), _) => Some(reason.to_string()),
+            (Status::Success, None) => None,
+            (Status::Revert, None) => Some(reverted()),
+            (Status::Success, Some(_)) => Some("expected a revert".to_owned()),
+            (Status::Revert, Some(None)) => None,
+            (Status::Revert, Some(Some((error, selector)))) => {
+                if result.output.starts_with(selector.as_slice()) {
+                    None
+                } else {
+                    Some(format!("expected a revert with {}, {}", error, reverted()))
+                }
+            }
+        };
+        let gas_used = result
+            .gas_used
+            .saturating_sub(gas::intrinsic(fork, calldata, false));
+        (gas_used, failure)
+    }
+}
+
+pub fn run(args: TestArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut fuzz = ProjectConfig::load()?.fuzz;
+    fuzz.runs = args.fuzz_runs.unwrap_or(fuzz.runs);
+    let seed = args
+        .fuzz_seed
+        .or(fuzz.seed)
+        .unwrap_or_else(|| RandomState::new().hash_one(std::time::SystemTime::now()));
+    fuzz.seed = Some(seed);
+
+    let src = read_source(&args.filename)?;
+    let mut included = Vec::new();
+    let (files, ast) = parse_source(&args.filename, &src, &mut included);
+    let (outcomes, filtered_out) = run_tests(
+        files,
+        &ast,
+        args.filter.as_deref(),
+        &args.compile,
+        &fuzz,
+        false,
+    );
+
+    if !report(&args.filename, &outcomes, filtered_out, seed) {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Prints the outcome of each test along with a summary, returns whether all of them passed.
+pub(crate) fn report(
+    filename: &str,
+    outcomes: &[TestOutcome],
+    filtered_out: usize,
+    seed: u64,
+) -> bool {
+    println!(
+        "Running {} test{} for {}",
+        outcomes.len(),
+        if outcomes.len() == 1 { "" } else { "s" },
+        filename
+    );
+    if outcomes.iter().any(|outcome| outcome.runs.is_some()) {
+        println!("Fuzzing with seed {:#x}", seed);
+    }
+    for outcome in outcomes {
+        match &outcome.failure {
+            None => println!(
+                "[{}] {} ({})",
+                "PASS".fg(Color::Green),
+                outcome.name,
+                outcome.gas()
+            ),
+            Some(reason) => println!(
+                "[{}] {} ({}): {}",
+                "FAIL".fg(Color::Red),
+                outcome.name,
+                outcome.gas(),
+                reason
+            ),
+        }
+    }
+
+    let failed = outcomes
+        .iter()
+        .filter(|outcome| outcome.failure.is_some())
+        .count();
+    let result = if failed == 0 {
+        "ok".fg(Color::Green)
+    } else {
+        "FAILED".fg(Color::Red)
+    };
+    println!(
+        "Test result: {}. {} passed; {} failed; {} filtered out",
+        result,
+        outcomes.len() - failed,
+        failed,
+        filtered_out
+    );
+
+    failed == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versions::EvmVersion;
+    use huff_ast::parse;
+
+    /// Runs the tests of the source, returning the name, gas used and failure of each.
+    fn run_source(src: &str, filter: Option<&str>) -> (Vec<(String, u64, Option<String>)>, usize) {
+        let ast = parse(src).unwrap();
+        let args = CompileArgs {
+            evm_version: EvmVersion::Paris,
+            constant_overrides: Vec::new(),
+        };
+        let files = Files {
+            filename: "test.huff",
+            src,
+            included: &[],
+        };
+        let (outcomes, filtered_out) =
+            run_tests(files, &ast, filter, &args, &FuzzConfig::default(), false);
+        let outcomes = outcomes
+            .into_iter()
+            .map(|outcome| (outcome.name.to_owned(), outcome.gas_used, outcome.failure))
+            .collect();
+        (outcomes, filtered_out)
+    }
+
+    const SRC: &str = "#define test PASSES() = {
+    0x01 0x00 mstore
+}
+#define test REVERTS() = {
+    0x00 dup1 revert
+}
+#define test HALTS() = {
+    invalid
+}";
+
+    #[test]
+    fn classifies_outcomes() {
+        let (outcomes, filtered_out) = run_source(SRC, None);
+        assert_eq!(filtered_out, 0);
+        // PUSH1, PUSH1 and MSTORE expanding memory by a word, without the intrinsic cost.
+        assert_eq!(outcomes[0], ("PASSES".to_owned(), 12, None));
+        assert_eq!(outcomes[1].0, "REVERTS");
+        assert_eq!(outcomes[1].2.as_deref(), Some("reverted"));
+        assert_eq!(outcomes[2].0, "HALTS");
+        assert_eq!(outcomes[2].2.as_deref(), Some("invalid opcode 0xfe"));
+    }
+
+    #[test]
+    fn filters_by_name() {
+        let (outcomes, filtered_out) = run_source(SRC, Some("RE"));
+        assert_eq!(filtered_out, 2);
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].0, "REVERTS");
+    }
+
+    #[test]
+    fn collects_outcomes_in_definition_order() {
+        // More tests than threads, so that some run on the same thread.
+        let src: String = (0..64)
+            .map(|i| format!("#define test T{}() = {{\n    {:#x} pop\n}}\n", i, i + 1))
+            .collect();
+        let (outcomes, _) = run_source(&src, None);
+        let names: Vec<_> = outcomes.iter().map(|(name, ..)| name.clone()).collect();
+        assert_eq!(
+            names,
+            (0..64).map(|i| format!("T{}", i)).collect::<Vec<_>>()
+        );
+        assert!(outcomes
+            .iter()
+            .all(|(_, gas_used, failure)| *gas_used == 5 && failure.is_none()));
+    }
+
+    #[test]
+    fn configures_calls_by_decorators() {
+        let src = "#define constant SLOT = 0x01
+#define error Unauthorized()
+#define error Other()
+
+#[value(42)]
+#define test VALUE() = {
+    callvalue 0x2a eq ok jumpi
+    0x00 dup1 revert
+    ok:
+}
+
+#[caller(0xbeef)]
+#define test CALLER() = {
+    caller 0xbeef eq ok jumpi
+    0x00 dup1 revert
+    ok:
+}
+
+#[storage([SLOT], 0x2a)]
+#define test STORAGE() = {
+    [SLOT] sload 0x2a eq ok jumpi
+    0x00 dup1 revert
+    ok:
+}
+
+#[expect_revert(Unauthorized)]
+#define test REVERTS_WITH_ERROR() = {
+    __FUNC_SIG(\"Unauthorized()\") 0xe0 shl 0x00 mstore
+    0x04 0x00 revert
+}
+
+#[expect_revert(Other)]
+#define test REVERTS_WITH_OTHER_ERROR() = {
+    __FUNC_SIG(\"Unauthorized()\") 0xe0 shl 0x00 mstore
+    0x04 0x00 revert
+}";
+        let (outcomes, _) = run_source(src, None);
+        let failures: Vec<_> = outcomes
+            .iter()
+            .map(|(name, _, failure)| (name.as_str(), failure.as_deref()))
+            .collect();
+        assert_eq!(
+            failures[..4],
+            [("VALUE", None), ("CALLER", None), ("STORAGE", None), ("REVERTS_WITH_ERROR", None)]
+        );
+        let (name, failure) = failures[4];
+        assert_eq!(name, "REVERTS_WITH_OTHER_ERROR");
+        assert!(failure
+            .unwrap()
+            .starts_with("expected a revert with Other, reverted with "));
+    }
+}
@@ -1,6 +1,8 @@
 use clap::ValueEnum;
+use huff_evm::Fork;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum EvmVersion {
     Paris,
     Shanghai,
@@ -11,4 +13,22 @@ impl EvmVersion {
     pub(crate) fn allows_push0(&self) -> bool {
         matches!(self, Self::Shanghai | Self::Cancun)
     }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Paris => "paris",
+            Self::Shanghai => "shanghai",
+            Self::Cancun => "cancun",
+        }
+    }
+}
+
+impl From<EvmVersion> for Fork {
+    fn from(version: EvmVersion) -> Self {
+        match version {
+            EvmVersion::Paris => Self::Paris,
+            EvmVersion::Shanghai => Self::Shanghai,
+            EvmVersion::Cancun => Self::Cancun,
+        }
+    }
 }
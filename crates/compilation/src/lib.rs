@@ -7,7 +7,10 @@ use evm_glue::{
 use huff_analysis::const_overrides::ConstantOverride;
 use huff_analysis::label_stack::LabelStack;
 use huff_ast::*;
-use std::collections::BTreeMap;
+use std::{cmp::Ordering, collections::BTreeMap};
+
+mod metadata;
+pub use metadata::{Metadata, COMPILER_VERSION};
 
 #[derive(Debug, Clone)]
 pub struct IncludedMacro<'src> {
@@ -33,6 +36,14 @@ impl IncludedMacro<'_> {
             set_size: None,
         }
     }
+
+    fn end_ref(&self) -> MarkRef {
+        MarkRef {
+            ref_type: RefType::Direct(self.end_id),
+            is_pushed: true,
+            set_size: None,
+        }
+    }
 }
 
 struct IncludedCodeTable<'src, 'ast> {
@@ -66,10 +77,39 @@ impl<'src, 'ast> IncludedCodeTable<'src, 'ast> {
     }
 }
 
+/// The macro statements each instruction was generated from, keyed by program counter. Frames go
+/// from the entry point down to the statement generating the instruction, each frame being a
+/// macro along with the span of its statement that was being generated, e.g. an invocation.
+pub type PcTable<'src> = BTreeMap<usize, Box<[(&'src str, Span)]>>;
+
+/// Assembly indices along with the frames of the statements they were generated from.
+type AsmOrigins<'src> = Vec<(usize, Vec<(&'src str, Span)>)>;
+
 pub fn generate_for_entrypoint<'src, 'ast: 'src>(
     globals: &mut CompileGlobals<'src, 'ast>,
     entry_point: &Macro<'src>,
 ) -> Vec<u8> {
+    generate_with_pc_table(globals, entry_point).0
+}
+
+/// Compiles the entry point like [`generate_for_entrypoint`], also mapping its instructions back
+/// to the statements they were generated from. Code included with `__codesize`/`__codeoffset` is
+/// not mapped.
+pub fn generate_with_pc_table<'src, 'ast: 'src>(
+    globals: &mut CompileGlobals<'src, 'ast>,
+    entry_point: &Macro<'src>,
+) -> (Vec<u8>, PcTable<'src>) {
+    generate_section(globals, entry_point, true)
+}
+
+/// Compiles a macro along with the code it includes, the metadata trailer only being appended to
+/// the entry point's code and not to the macros it includes, unless one of them is the
+/// [`CompileGlobals::trailer_macro`].
+fn generate_section<'src, 'ast: 'src>(
+    globals: &mut CompileGlobals<'src, 'ast>,
+    entry_point: &Macro<'src>,
+    is_entry_point: bool,
+) -> (Vec<u8>, PcTable<'src>) {
     let mut mark_tracker = MarkTracker::default();
     let mut label_stack: LabelStack<usize> = LabelStack::default();
 
@@ -83,8 +123,8 @@ pub fn generate_for_entrypoint<'src, 'ast: 'src>(
     });
     let included_code_tables: Vec<IncludedCodeTable<'src, 'ast>> = globals
         .defs
-        .iter()
-        .filter_map(|(_, def)| {
+        .values()
+        .filter_map(|def| {
             let Definition::CodeTable { name, data } = def else {
                 return None;
             };
@@ -105,7 +145,7 @@ pub fn generate_for_entrypoint<'src, 'ast: 'src>(
 
     let mut asm = Vec::with_capacity(10_000);
     asm.push(Asm::Mark(start_id));
-    generate_for_macro(
+    let origins = generate_for_macro(
         globals,
         entry_point,
         Box::new([]),
@@ -127,7 +167,8 @@ pub fn generate_for_entrypoint<'src, 'ast: 'src>(
                     panic!("Section macro {} not found", included.name);
                 };
             asm.push(Asm::Mark(included.start_id));
-            asm.push(Asm::Data(generate_for_entrypoint(globals, section_macro)));
+            let (section, _) = generate_section(globals, section_macro, false);
+            asm.push(Asm::Data(section));
             asm.push(Asm::Mark(included.end_id));
         });
 
@@ -141,9 +182,114 @@ pub fn generate_for_entrypoint<'src, 'ast: 'src>(
             asm.push(Asm::Mark(included.end_id));
         });
 
+    let gets_trailer = match globals.trailer_macro {
+        Some(name) => entry_point.ident() == name,
+        None => is_entry_point,
+    };
+    if let Some(trailer) = globals.metadata_trailer.as_ref().filter(|_| gets_trailer) {
+        asm.push(Asm::Data(trailer.clone()));
+    }
     asm.push(Asm::Mark(end_id));
 
-    globals.assemble(asm.as_slice())
+    let code = globals.assemble(asm.as_slice());
+    let pc_table = pc_table(&asm, &code, origins);
+    (code, pc_table)
+}
+
+/// Locates the assembly items in the assembled code, relying on each operation and reference
+/// being assembled to a single instruction.
+fn pc_table<'src>(asm: &[Asm], code: &[u8], origins: AsmOrigins<'src>) -> PcTable<'src> {
+    let mut pcs = Vec::with_capacity(asm.len());
+    let mut pc = 0;
+    for item in asm {
+        pcs.push(pc);
+        pc += match item {
+            Asm::Mark(_) => 0,
+            Asm::Data(data) => data.len(),
+            Asm::Op(_) | Asm::Ref(_) => match code[pc] {
+                push @ 0x60..=0x7f => 1 + usize::from(push - 0x5f),
+                _ => 1,
+            },
+        };
+    }
+
+    origins
+        .into_iter()
+        .filter(|(idx, _)| matches!(asm[*idx], Asm::Op(_) | Asm::Ref(_)))
+        .map(|(idx, frames)| (pcs[idx], frames.into_boxed_slice()))
+        .collect()
+}
+
+/// Solc style source map of the code, one `s:l:f:j` entry per instruction, spans pointing into
+/// the root file (source `0`). Instructions not generated from a statement, e.g. included code,
+/// are mapped to `-1:-1:-1`. Jumps to a label pushed right before them are marked `i` if the label
+/// is defined in a macro invoked deeper down and `o` if it is defined in an invoking macro, macros
+/// being inlined there are no other jumps into or out of them.
+pub fn source_map(code: &[u8], pc_table: &PcTable) -> String {
+    const JUMP: u8 = 0x56;
+    const JUMPI: u8 = 0x57;
+    const JUMPDEST: u8 = 0x5b;
+
+    let mut entries = Vec::new();
+    let mut previous: [String; 4] = Default::default();
+    let mut pushed: Option<usize> = None;
+    let mut pc = 0;
+    while pc < code.len() {
+        let op = code[pc];
+        let frames = pc_table.get(&pc);
+        let jump_type = match (op, frames, pushed) {
+            (JUMP | JUMPI, Some(frames), Some(target)) if code.get(target) == Some(&JUMPDEST) => {
+                match pc_table
+                    .get(&target)
+                    .map(|target| target.len().cmp(&frames.len()))
+                {
+                    Some(Ordering::Greater) => "i",
+                    Some(Ordering::Less) => "o",
+                    _ => "-",
+                }
+            }
+            _ => "-",
+        };
+        let fields = match frames.and_then(|frames| frames.last()) {
+            Some((_, span)) => [
+                span.start.to_string(),
+                (span.end - span.start).to_string(),
+                "0".to_owned(),
+                jump_type.to_owned(),
+            ],
+            None => ["-1", "-1", "-1", jump_type].map(str::to_owned),
+        };
+
+        // Fields equal to the previous entry's are left empty, trailing empty ones omitted.
+        let changed = fields
+            .iter()
+            .zip(&previous)
+            .rposition(|(field, previous)| field != previous)
+            .map_or(0, |idx| idx + 1);
+        let entry: Vec<&str> = fields[..changed]
+            .iter()
+            .zip(&previous)
+            .map(|(field, previous)| if field == previous { "" } else { field })
+            .collect();
+        entries.push(entry.join(":"));
+        previous = fields;
+
+        let size = match op {
+            push @ 0x60..=0x7f => usize::from(push - 0x5f),
+            _ => 0,
+        };
+        pushed = (size > 0).then(|| {
+            code[(pc + 1).min(code.len())..(pc + 1 + size).min(code.len())]
+                .iter()
+                .fold(0usize, |target, byte| {
+                    target
+                        .saturating_mul(256)
+                        .saturating_add(usize::from(*byte))
+                })
+        });
+        pc += 1 + size;
+    }
+    entries.join(";")
 }
 
 /// WARNING: Only to be used as standalone constructor, may break if added after other code due to
@@ -208,7 +354,7 @@ fn generate_for_macro<'src: 'cmp, 'cmp, 'ast>(
     label_stack: &'cmp mut LabelStack<'src, usize>,
     program_data_deps: &'cmp mut ProgramDataDeps<'src, 'ast>,
     asm: &mut Vec<Asm>,
-) {
+) -> AsmOrigins<'src> {
     let current_args: BTreeMap<&str, Asm> = BTreeMap::from_iter(
         current
             .args
@@ -226,118 +372,167 @@ fn generate_for_macro<'src: 'cmp, 'cmp, 'ast>(
         }
     });
 
-    current.body.iter().for_each(|stmt| match stmt {
-        MacroStatement::LabelDefinition(name) => {
-            asm.extend([
-                Asm::Mark(*label_stack.get(name.ident()).unwrap()),
-                Asm::Op(Opcode::JUMPDEST),
-            ]);
-        }
-        MacroStatement::Invoke(invoke) => match invoke {
-            Invoke::Macro { name, args } => {
-                let target =
-                    if let Definition::Macro(target) = globals.defs.get(name.ident()).unwrap() {
+    let mut origins = AsmOrigins::new();
+    for stmt in current.body.iter() {
+        let start = asm.len();
+        match stmt {
+            MacroStatement::LabelDefinition(name) => {
+                asm.extend([
+                    Asm::Mark(*label_stack.get(name.ident()).unwrap()),
+                    Asm::Op(Opcode::JUMPDEST),
+                ]);
+            }
+            MacroStatement::Invoke(invoke) => match invoke {
+                Invoke::Macro { name, args } => {
+                    let target = if let Definition::Macro(target) =
+                        globals.defs.get(name.ident()).unwrap()
+                    {
                         target
                     } else {
                         panic!("Target should've been validated to be macro")
                     };
-                generate_for_macro(
-                    globals,
-                    target,
-                    args.0
+                    let inner = generate_for_macro(
+                        globals,
+                        target,
+                        args.0
+                            .iter()
+                            .map(|arg| instruction_to_asm(globals, &current_args, label_stack, arg))
+                            .collect(),
+                        mark_tracker,
+                        label_stack,
+                        program_data_deps,
+                        asm,
+                    );
+                    origins.extend(inner.into_iter().map(|(idx, mut frames)| {
+                        frames.insert(0, (current.ident(), stmt.get_span()));
+                        (idx, frames)
+                    }));
+                    continue;
+                }
+                Invoke::BuiltinCodeSize(code_ref) => {
+                    let mref: MarkRef = if let Some(included) = program_data_deps
+                        .included_macros
                         .iter()
-                        .map(|arg| instruction_to_asm(globals, &current_args, label_stack, arg))
-                        .collect(),
-                    mark_tracker,
-                    label_stack,
-                    program_data_deps,
-                    asm,
-                )
-            }
-            Invoke::BuiltinCodeSize(code_ref) => {
-                let mref: MarkRef = if let Some(included) = program_data_deps
-                    .included_macros
-                    .iter()
-                    .find(|m| m.name == code_ref.ident())
-                {
-                    included.size_ref()
-                } else {
-                    let start_id = mark_tracker.next_mark();
-                    let end_id = mark_tracker.next_mark();
-                    let included = IncludedMacro {
-                        name: code_ref.ident(),
-                        start_id,
-                        end_id,
+                        .find(|m| m.name == code_ref.ident())
+                    {
+                        included.size_ref()
+                    } else {
+                        let start_id = mark_tracker.next_mark();
+                        let end_id = mark_tracker.next_mark();
+                        let included = IncludedMacro {
+                            name: code_ref.ident(),
+                            start_id,
+                            end_id,
+                        };
+                        let mref = included.size_ref();
+                        program_data_deps.included_macros.push(included);
+                        mref
                     };
-                    let mref = included.size_ref();
-                    program_data_deps.included_macros.push(included);
-                    mref
-                };
-                asm.push(Asm::Ref(mref));
-            }
-            Invoke::BuiltinCodeOffset(code_ref) => {
-                let mref: MarkRef = if let Some(included) = program_data_deps
-                    .included_macros
-                    .iter()
-                    .find(|m| m.name == code_ref.ident())
-                {
-                    included.start_ref()
-                } else {
-                    let start_id = mark_tracker.next_mark();
-                    let end_id = mark_tracker.next_mark();
-                    let included = IncludedMacro {
-                        name: code_ref.ident(),
-                        start_id,
-                        end_id,
+                    asm.push(Asm::Ref(mref));
+                }
+                Invoke::BuiltinCodeOffset(code_ref) => {
+                    let mref: MarkRef = if let Some(included) = program_data_deps
+                        .included_macros
+                        .iter()
+                        .find(|m| m.name == code_ref.ident())
+                    {
+                        included.start_ref()
+                    } else {
+                        let start_id = mark_tracker.next_mark();
+                        let end_id = mark_tracker.next_mark();
+                        let included = IncludedMacro {
+                            name: code_ref.ident(),
+                            start_id,
+                            end_id,
+                        };
+                        let mref = included.start_ref();
+                        program_data_deps.included_macros.push(included);
+                        mref
                     };
-                    let mref = included.start_ref();
-                    program_data_deps.included_macros.push(included);
-                    mref
-                };
-                asm.push(Asm::Ref(mref));
-            }
-            Invoke::BuiltinTableStart(table_ref) => {
-                let target_table = program_data_deps
-                    .included_code_tables
-                    .iter_mut()
-                    .find(|t| t.name == table_ref.ident())
-                    .expect("Table not found (might be jumptable)");
-                target_table.referenced = true;
-                asm.push(Asm::Ref(target_table.start_ref()));
-            }
-            Invoke::BuiltinTableSize(table_ref) => {
-                let target_table = program_data_deps
-                    .included_code_tables
-                    .iter_mut()
-                    .find(|t| t.name == table_ref.ident())
-                    .expect("Table not found (might be jumptable)");
-                target_table.referenced = true;
-                asm.push(Asm::Ref(target_table.size_ref()));
-            }
-            Invoke::BuiltinFuncSig(func) => {
-                let Definition::SolFunction(sol_func) = globals.defs[func.ident()] else {
-                    unreachable!(
-                        "Reached codegen even though \"{}\" not found in global defs",
-                        func.ident()
-                    )
-                };
-                let selector = compute_selector(&sol_func.name, &sol_func.args);
-                asm.push(u256_to_asm(
-                    U256::from_be_slice(selector.as_slice()),
-                    globals.allow_push0,
-                ));
+                    asm.push(Asm::Ref(mref));
+                }
+                Invoke::BuiltinTableStart(table_ref) => {
+                    let target_table = program_data_deps
+                        .included_code_tables
+                        .iter_mut()
+                        .find(|t| t.name == table_ref.ident())
+                        .expect("Table not found (might be jumptable)");
+                    target_table.referenced = true;
+                    asm.push(Asm::Ref(target_table.start_ref()));
+                }
+                Invoke::BuiltinTableSize(table_ref) => {
+                    let target_table = program_data_deps
+                        .included_code_tables
+                        .iter_mut()
+                        .find(|t| t.name == table_ref.ident())
+                        .expect("Table not found (might be jumptable)");
+                    target_table.referenced = true;
+                    asm.push(Asm::Ref(target_table.size_ref()));
+                }
+                Invoke::BuiltinFuncSig(func) => {
+                    let Definition::SolFunction(sol_func) = globals.defs[func.ident()] else {
+                        unreachable!(
+                            "Reached codegen even though \"{}\" not found in global defs",
+                            func.ident()
+                        )
+                    };
+                    let selector = compute_selector(&sol_func.name, &sol_func.args);
+                    asm.push(u256_to_asm(
+                        U256::from_be_slice(selector.as_slice()),
+                        globals.allow_push0,
+                    ));
+                }
+                Invoke::BuiltinFuncSigLiteral((signature, span)) => {
+                    let selector =
+                        compute_selector(&(signature.name.as_str(), *span), &signature.args);
+                    asm.push(u256_to_asm(
+                        U256::from_be_slice(selector.as_slice()),
+                        globals.allow_push0,
+                    ));
+                }
+                Invoke::BuiltinEventHash(event) => {
+                    let Definition::SolEvent(sol_event) = globals.defs[event.ident()] else {
+                        unreachable!(
+                            "Reached codegen even though \"{}\" not found in global defs",
+                            event.ident()
+                        )
+                    };
+                    let topic = compute_event_topic(&sol_event.name, &sol_event.args);
+                    asm.push(u256_to_asm(
+                        U256::from_be_slice(topic.as_slice()),
+                        globals.allow_push0,
+                    ));
+                }
+                Invoke::BuiltinEventHashLiteral((signature, span)) => {
+                    let topic =
+                        compute_event_topic(&(signature.name.as_str(), *span), &signature.args);
+                    asm.push(u256_to_asm(
+                        U256::from_be_slice(topic.as_slice()),
+                        globals.allow_push0,
+                    ));
+                }
+                Invoke::BuiltinArgsOffset(_) => {
+                    // Only used in the entry point's code, analysis rejecting it in included code.
+                    // The first included macro is the entry point, its end mark follows the
+                    // included code and metadata trailer.
+                    let compiled = &program_data_deps.included_macros[0];
+                    asm.push(Asm::Ref(compiled.end_ref()));
+                }
+                _ => panic!(
+                    "Compilation not yet implemented for this invocation type `{:?}`",
+                    invoke
+                ),
+            },
+            MacroStatement::Instruction(ref i) => {
+                asm.push(instruction_to_asm(globals, &current_args, label_stack, i));
             }
-            _ => panic!(
-                "Compilation not yet implemented for this invocation type `{:?}`",
-                invoke
-            ),
-        },
-        MacroStatement::Instruction(ref i) => {
-            asm.push(instruction_to_asm(globals, &current_args, label_stack, i));
         }
-    });
+        origins
+            .extend((start..asm.len()).map(|idx| (idx, vec![(current.ident(), stmt.get_span())])));
+    }
 
     label_stack.leave_context();
+    origins
 }
 
 fn instruction_to_asm(
@@ -372,6 +567,11 @@ pub struct CompileGlobals<'src, 'ast> {
     pub allow_push0: bool,
     pub defs: BTreeMap<&'src str, &'ast Definition<'src>>,
     pub constants: BTreeMap<&'src str, U256>,
+    /// Appended to the entry point's code if set, see [`Metadata::trailer`].
+    pub metadata_trailer: Option<Vec<u8>>,
+    /// Macro whose code gets the metadata trailer in place of the entry point's, e.g. the runtime
+    /// deployed by a constructor.
+    pub trailer_macro: Option<&'src str>,
 }
 
 impl<'src, 'ast> CompileGlobals<'src, 'ast> {
@@ -381,7 +581,7 @@ impl<'src, 'ast> CompileGlobals<'src, 'ast> {
         defs: BTreeMap<&'src str, &'ast Definition<'src>>,
         overrides: &'ast [ConstantOverride],
     ) -> Self {
-        let mut constants = evalute_constants(&defs);
+        let mut constants = evaluate_constants(&defs);
         for const_override in overrides {
             let value = constants
                 .get_mut(const_override.name.as_str())
@@ -393,6 +593,8 @@ impl<'src, 'ast> CompileGlobals<'src, 'ast> {
             allow_push0,
             defs,
             constants,
+            metadata_trailer: None,
+            trailer_macro: None,
         }
     }
 
@@ -407,7 +609,9 @@ impl<'src, 'ast> CompileGlobals<'src, 'ast> {
     }
 }
 
-fn evalute_constants<'a>(
+/// Evaluates all constants, `FREE_STORAGE_POINTER()` slots are assigned in order of the constant
+/// names.
+pub fn evaluate_constants<'a>(
     global_defs: &BTreeMap<&'a str, &Definition<'a>>,
 ) -> BTreeMap<&'a str, U256> {
     let mut free_pointer = 0u32;
@@ -0,0 +1,126 @@
+use alloy_primitives::{keccak256, B256, U256};
+use huff_analysis::const_overrides::ConstantOverride;
+
+/// Version of the compiler recorded in the metadata.
+pub const COMPILER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const UINT: u8 = 0;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const MAP: u8 = 5;
+const TAG: u8 = 6;
+/// CBOR tag of unsigned integers encoded as big-endian bytes.
+const BIGNUM_TAG: u64 = 2;
+
+/// What a verifier needs to reproduce the code, appended to it like solc's metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata<'a> {
+    /// Keccak of each source file by its path, the root file first.
+    pub source_hashes: Vec<(&'a str, B256)>,
+    pub evm_version: &'a str,
+    /// Overridden constants, sorted by name.
+    pub constant_overrides: Vec<(&'a str, U256)>,
+}
+
+impl<'a> Metadata<'a> {
+    /// Metadata of the build of the given sources, by path and content, the root file first.
+    pub fn new(
+        sources: impl IntoIterator<Item = (&'a str, &'a str)>,
+        evm_version: &'a str,
+        overrides: &'a [ConstantOverride],
+    ) -> Self {
+        let mut constant_overrides: Vec<_> = overrides
+            .iter()
+            .map(|const_override| (const_override.name.as_str(), const_override.value))
+            .collect();
+        constant_overrides.sort();
+        Self {
+            source_hashes: sources
+                .into_iter()
+                .map(|(path, src)| (path, keccak256(src)))
+                .collect(),
+            evm_version,
+            constant_overrides,
+        }
+    }
+
+    /// Keccak of the sources' hashes in order, identifying all of them at once.
+    pub fn sources_hash(&self) -> B256 {
+        let hashes: Vec<u8> = self
+            .source_hashes
+            .iter()
+            .flat_map(|(_, hash)| hash.0)
+            .collect();
+        keccak256(hashes)
+    }
+
+    /// CBOR map of the compiler version (`huff`), sources hash (`source`), EVM version (`evm`) and
+    /// overrides (`constants`, if any) followed by its length as 2 big-endian bytes, so that it
+    /// can be located from the end of the code.
+    pub fn trailer(&self) -> Vec<u8> {
+        let mut cbor = Vec::with_capacity(64);
+        let fields = if self.constant_overrides.is_empty() {
+            3
+        } else {
+            4
+        };
+        head(&mut cbor, MAP, fields);
+        text(&mut cbor, "huff");
+        text(&mut cbor, COMPILER_VERSION);
+        text(&mut cbor, "source");
+        head(&mut cbor, BYTES, 32);
+        cbor.extend_from_slice(self.sources_hash().as_slice());
+        text(&mut cbor, "evm");
+        text(&mut cbor, self.evm_version);
+        if !self.constant_overrides.is_empty() {
+            text(&mut cbor, "constants");
+            head(&mut cbor, MAP, self.constant_overrides.len() as u64);
+            for (name, value) in &self.constant_overrides {
+                text(&mut cbor, name);
+                uint(&mut cbor, *value);
+            }
+        }
+        let len = u16::try_from(cbor.len()).expect("metadata larger than 64 KiB");
+        cbor.extend_from_slice(&len.to_be_bytes());
+        cbor
+    }
+}
+
+/// Item head of the given major type, holding the item's value or length.
+fn head(cbor: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    match value {
+        0..=23 => cbor.push(major | value as u8),
+        24..=0xff => cbor.extend_from_slice(&[major | 24, value as u8]),
+        0x100..=0xffff => {
+            cbor.push(major | 25);
+            cbor.extend_from_slice(&(value as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            cbor.push(major | 26);
+            cbor.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+        _ => {
+            cbor.push(major | 27);
+            cbor.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
+
+fn text(cbor: &mut Vec<u8>, text: &str) {
+    head(cbor, TEXT, text.len() as u64);
+    cbor.extend_from_slice(text.as_bytes());
+}
+
+/// Unsigned integer, as a bignum if it doesn't fit into 64 bits.
+fn uint(cbor: &mut Vec<u8>, value: U256) {
+    match u64::try_from(value) {
+        Ok(value) => head(cbor, UINT, value),
+        Err(_) => {
+            let bytes = value.to_be_bytes_trimmed_vec();
+            head(cbor, TAG, BIGNUM_TAG);
+            head(cbor, BYTES, bytes.len() as u64);
+            cbor.extend_from_slice(&bytes);
+        }
+    }
+}
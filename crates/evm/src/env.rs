@@ -0,0 +1,72 @@
+use alloy_primitives::{Address, Bytes, B256, U256};
+
+/// Hard fork whose rules the interpreter follows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Fork {
+    #[default]
+    Paris,
+    /// Adds `PUSH0`, warm coinbase and the initcode size limit.
+    Shanghai,
+    /// Adds transient storage, `MCOPY`, blob opcodes and restricts `SELFDESTRUCT`.
+    Cancun,
+}
+
+/// Block the transactions are executed in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockEnv {
+    pub number: u64,
+    pub timestamp: u64,
+    pub coinbase: Address,
+    pub gas_limit: u64,
+    pub base_fee: U256,
+    pub prevrandao: B256,
+    pub chain_id: u64,
+    pub blob_base_fee: U256,
+}
+
+impl Default for BlockEnv {
+    fn default() -> Self {
+        Self {
+            number: 1,
+            timestamp: 1,
+            coinbase: Address::ZERO,
+            gas_limit: 30_000_000,
+            base_fee: U256::ZERO,
+            prevrandao: B256::ZERO,
+            chain_id: 1,
+            blob_base_fee: U256::from(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Env {
+    pub fork: Fork,
+    pub block: BlockEnv,
+}
+
+/// A transaction, `to` being `None` deploys `data` as initcode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub caller: Address,
+    pub to: Option<Address>,
+    pub value: U256,
+    pub data: Bytes,
+    pub gas_limit: u64,
+    pub gas_price: U256,
+    pub blob_hashes: Vec<B256>,
+}
+
+impl Default for Transaction {
+    fn default() -> Self {
+        Self {
+            caller: Address::ZERO,
+            to: None,
+            value: U256::ZERO,
+            data: Bytes::new(),
+            gas_limit: 30_000_000,
+            gas_price: U256::ZERO,
+            blob_hashes: Vec::new(),
+        }
+    }
+}
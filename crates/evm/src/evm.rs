@@ -0,0 +1,675 @@
+use crate::{
+    env::{Env, Fork, Transaction},
+    gas,
+    inspector::Inspector,
+    interpreter::Message,
+    state::{Checkpoint, State},
+};
+use alloy_primitives::{keccak256, Address, Bytes, Log, B256, U256};
+use revm_precompile::{
+    primitives::Env as PrecompileEnv, Precompile, PrecompileError, PrecompileErrors,
+    PrecompileSpecId, Precompiles,
+};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HaltReason {
+    OutOfGas,
+    StackUnderflow,
+    StackOverflow,
+    InvalidJump,
+    InvalidOpcode(u8),
+    StateChangeDuringStaticCall,
+    ReturnDataOutOfBounds,
+    CreateCollision,
+    CodeSizeLimit,
+    InitcodeSizeLimit,
+    /// Deployed code may not start with `0xEF` (EIP-3541).
+    InvalidCodePrefix,
+    /// Invalid input to a precompile, e.g. a point that's not on the curve.
+    PrecompileFailure(Address),
+}
+
+impl fmt::Display for HaltReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfGas => write!(f, "out of gas"),
+            Self::StackUnderflow => write!(f, "stack underflow"),
+            Self::StackOverflow => write!(f, "stack overflow"),
+            Self::InvalidJump => write!(f, "invalid jump destination"),
+            Self::InvalidOpcode(op) => write!(f, "invalid opcode 0x{:02x}", op),
+            Self::StateChangeDuringStaticCall => write!(f, "state change during static call"),
+            Self::ReturnDataOutOfBounds => write!(f, "return data out of bounds"),
+            Self::CreateCollision => write!(f, "create collision"),
+            Self::CodeSizeLimit => write!(f, "code size limit exceeded"),
+            Self::InitcodeSizeLimit => write!(f, "initcode size limit exceeded"),
+            Self::InvalidCodePrefix => write!(f, "deployed code starts with 0xEF"),
+            Self::PrecompileFailure(address) => write!(f, "precompile {} failed", address),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    Revert,
+    /// Exceptional halt, consuming all gas of the frame.
+    Halt(HaltReason),
+}
+
+impl Status {
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Success)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionResult {
+    pub status: Status,
+    /// Return or revert data, empty for successful deployments.
+    pub output: Bytes,
+    /// Gas used including the intrinsic gas, after refunds.
+    pub gas_used: u64,
+    pub gas_refunded: u64,
+    /// Logs emitted, only kept if the transaction succeeded.
+    pub logs: Vec<Log>,
+    /// Address of the deployed contract for successful creates.
+    pub created: Option<Address>,
+}
+
+/// Reasons a transaction is rejected before it's executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionError {
+    IntrinsicGasTooLow { intrinsic: u64, gas_limit: u64 },
+    InsufficientFunds { required: U256, balance: U256 },
+    NonceOverflow,
+    InitcodeSizeLimit,
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IntrinsicGasTooLow {
+                intrinsic,
+                gas_limit,
+            } => write!(
+                f,
+                "gas limit {} below the intrinsic gas of {}",
+                gas_limit, intrinsic
+            ),
+            Self::InsufficientFunds { required, balance } => write!(
+                f,
+                "insufficient funds, {} wei required but balance is {}",
+                required, balance
+            ),
+            Self::NonceOverflow => write!(f, "nonce overflow"),
+            Self::InitcodeSizeLimit => write!(f, "initcode size limit exceeded"),
+        }
+    }
+}
+
+impl std::error::Error for TransactionError {}
+
+/// State of the transaction that's rolled back alongside the world state when a frame fails.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Substate {
+    pub transient: HashMap<(Address, U256), U256>,
+    pub warm_accounts: HashSet<Address>,
+    pub warm_slots: HashSet<(Address, U256)>,
+    pub logs: Vec<Log>,
+    pub refund: i64,
+    pub created: HashSet<Address>,
+    pub destructed: BTreeSet<Address>,
+    /// Values of the slots written to at the start of the transaction, needed for `SSTORE`
+    /// pricing. Kept on revert, as they don't depend on the frame.
+    original_storage: HashMap<(Address, U256), U256>,
+    /// Insertions into the sets and transient storage writes, newest last.
+    journal: Vec<SubstateChange>,
+}
+
+#[derive(Debug, Clone)]
+enum SubstateChange {
+    WarmAccount(Address),
+    WarmSlot(Address, U256),
+    Transient((Address, U256), U256),
+    Created(Address),
+    Destructed(Address),
+}
+
+/// Lengths of the journal and logs and the refund, which is all that's needed to roll back.
+#[derive(Debug)]
+pub(crate) struct SubstateCheckpoint {
+    journal: usize,
+    logs: usize,
+    refund: i64,
+}
+
+impl Substate {
+    /// Warms the account, `true` if it was cold.
+    pub fn warm_account(&mut self, address: Address) -> bool {
+        let cold = self.warm_accounts.insert(address);
+        if cold {
+            self.journal.push(SubstateChange::WarmAccount(address));
+        }
+        cold
+    }
+
+    /// Warms the storage slot, `true` if it was cold.
+    pub fn warm_slot(&mut self, address: Address, slot: U256) -> bool {
+        let cold = self.warm_slots.insert((address, slot));
+        if cold {
+            self.journal.push(SubstateChange::WarmSlot(address, slot));
+        }
+        cold
+    }
+
+    /// Value of the slot at the start of the transaction, `current` if it wasn't written to yet,
+    /// storage only changing through `SSTORE`.
+    pub fn original_storage(&mut self, address: Address, slot: U256, current: U256) -> U256 {
+        *self
+            .original_storage
+            .entry((address, slot))
+            .or_insert(current)
+    }
+
+    pub fn set_transient(&mut self, key: (Address, U256), value: U256) {
+        let previous = if value.is_zero() {
+            self.transient.remove(&key)
+        } else {
+            self.transient.insert(key, value)
+        };
+        self.journal
+            .push(SubstateChange::Transient(key, previous.unwrap_or_default()));
+    }
+
+    pub fn create(&mut self, address: Address) {
+        if self.created.insert(address) {
+            self.journal.push(SubstateChange::Created(address));
+        }
+    }
+
+    pub fn destruct(&mut self, address: Address) {
+        if self.destructed.insert(address) {
+            self.journal.push(SubstateChange::Destructed(address));
+        }
+    }
+
+    fn checkpoint(&self) -> SubstateCheckpoint {
+        SubstateCheckpoint {
+            journal: self.journal.len(),
+            logs: self.logs.len(),
+            refund: self.refund,
+        }
+    }
+
+    fn revert(&mut self, checkpoint: SubstateCheckpoint) {
+        for change in self.journal.drain(checkpoint.journal..).rev() {
+            match change {
+                SubstateChange::WarmAccount(address) => {
+                    self.warm_accounts.remove(&address);
+                }
+                SubstateChange::WarmSlot(address, slot) => {
+                    self.warm_slots.remove(&(address, slot));
+                }
+                SubstateChange::Transient(key, value) if value.is_zero() => {
+                    self.transient.remove(&key);
+                }
+                SubstateChange::Transient(key, value) => {
+                    self.transient.insert(key, value);
+                }
+                SubstateChange::Created(address) => {
+                    self.created.remove(&address);
+                }
+                SubstateChange::Destructed(address) => {
+                    self.destructed.remove(&address);
+                }
+            }
+        }
+        self.logs.truncate(checkpoint.logs);
+        self.refund = checkpoint.refund;
+    }
+}
+
+pub(crate) struct FrameResult {
+    pub status: Status,
+    pub output: Bytes,
+    pub gas_left: u64,
+}
+
+impl FrameResult {
+    pub fn halt(reason: HaltReason) -> Self {
+        Self {
+            status: Status::Halt(reason),
+            output: Bytes::new(),
+            gas_left: 0,
+        }
+    }
+}
+
+/// Executes transactions against an in-memory world state.
+#[derive(Debug)]
+pub struct Evm<I = ()> {
+    pub state: State,
+    pub env: Env,
+    pub inspector: I,
+    pub(crate) tx: Transaction,
+    pub(crate) substate: Substate,
+}
+
+impl Evm {
+    pub fn new(env: Env) -> Self {
+        Self::with_inspector(env, ())
+    }
+}
+
+impl<I: Inspector> Evm<I> {
+    pub fn with_inspector(env: Env, inspector: I) -> Self {
+        Self {
+            state: State::new(),
+            env,
+            inspector,
+            tx: Transaction::default(),
+            substate: Substate::default(),
+        }
+    }
+
+    pub fn transact(&mut self, tx: &Transaction) -> Result<ExecutionResult, TransactionError> {
+        let fork = self.env.fork;
+        let is_create = tx.to.is_none();
+        if is_create && fork >= Fork::Shanghai && tx.data.len() > gas::MAX_INITCODE_SIZE {
+            return Err(TransactionError::InitcodeSizeLimit);
+        }
+        let intrinsic = gas::intrinsic(fork, &tx.data, is_create);
+        if tx.gas_limit < intrinsic {
+            return Err(TransactionError::IntrinsicGasTooLow {
+                intrinsic,
+                gas_limit: tx.gas_limit,
+            });
+        }
+        let gas_cost = U256::from(tx.gas_limit).saturating_mul(tx.gas_price);
+        let required = gas_cost.saturating_add(tx.value);
+        let balance = self.state.balance(tx.caller);
+        if balance < required {
+            return Err(TransactionError::InsufficientFunds { required, balance });
+        }
+        if self
+            .state
+            .account(tx.caller)
+            .is_some_and(|account| account.nonce == u64::MAX)
+        {
+            return Err(TransactionError::NonceOverflow);
+        }
+
+        self.state.set_balance(tx.caller, balance - gas_cost);
+        self.tx = tx.clone();
+        self.substate = Substate::default();
+        let warm = &mut self.substate.warm_accounts;
+        warm.insert(tx.caller);
+        warm.extend(tx.to);
+        warm.extend(precompiles(fork).addresses());
+        if fork >= Fork::Shanghai {
+            warm.insert(self.env.block.coinbase);
+        }
+
+        let gas = tx.gas_limit - intrinsic;
+        let (result, created) = match tx.to {
+            Some(to) => {
+                let nonce = self.state.nonce(tx.caller);
+                self.state.set_nonce(tx.caller, nonce + 1);
+                let msg = Message {
+                    caller: tx.caller,
+                    address: to,
+                    code: self.state.code(to),
+                    value: tx.value,
+                    data: tx.data.clone(),
+                    gas,
+                    is_static: false,
+                    depth: 0,
+                };
+                (self.execute_call(msg, to, true), None)
+            }
+            None => self.execute_create(tx.caller, tx.value, tx.data.clone(), gas, 0, None),
+        };
+
+        let mut gas_used = tx.gas_limit - result.gas_left;
+        let gas_refunded = match result.status {
+            Status::Success => {
+                (self.substate.refund.max(0) as u64).min(gas_used / gas::MAX_REFUND_QUOTIENT)
+            }
+            _ => 0,
+        };
+        gas_used -= gas_refunded;
+
+        let unused = U256::from(tx.gas_limit - gas_used).saturating_mul(tx.gas_price);
+        let caller_balance = self.state.balance(tx.caller);
+        self.state
+            .set_balance(tx.caller, caller_balance.saturating_add(unused));
+        let tip = tx.gas_price.saturating_sub(self.env.block.base_fee);
+        let coinbase = self.env.block.coinbase;
+        let fees = U256::from(gas_used).saturating_mul(tip);
+        if !fees.is_zero() {
+            let coinbase_balance = self.state.balance(coinbase);
+            self.state
+                .set_balance(coinbase, coinbase_balance.saturating_add(fees));
+        }
+
+        let substate = std::mem::take(&mut self.substate);
+        for address in substate.destructed {
+            self.state.remove(address);
+        }
+
+        Ok(ExecutionResult {
+            status: result.status,
+            output: result.output,
+            gas_used,
+            gas_refunded,
+            logs: substate.logs,
+            created,
+        })
+    }
+
+    fn checkpoint(&mut self) -> (Checkpoint, SubstateCheckpoint) {
+        (self.state.checkpoint(), self.substate.checkpoint())
+    }
+
+    /// Keeps the frame's changes if it succeeded and rolls them back otherwise.
+    fn finish(&mut self, (state, substate): (Checkpoint, SubstateCheckpoint), success: bool) {
+        if success {
+            self.state.commit(state);
+        } else {
+            self.state.revert(state);
+            self.substate.revert(substate);
+        }
+    }
+
+    /// Runs a message call, `code_address` being where the code was loaded from. Value is only
+    /// moved if `transfer` is set, i.e. not for `DELEGATECALL`s.
+    pub(crate) fn execute_call(
+        &mut self,
+        msg: Message,
+        code_address: Address,
+        transfer: bool,
+    ) -> FrameResult {
+        let (depth, gas) = (msg.depth, msg.gas);
+        let checkpoint = self.checkpoint();
+        if transfer && !msg.value.is_zero() {
+            // Balances are checked before the call is made.
+            self.state.transfer(msg.caller, msg.address, msg.value);
+        }
+
+        let result = if precompiles(self.env.fork).contains(&code_address) {
+            precompile(code_address, &msg, self.env.fork)
+        } else {
+            self.run(msg)
+        };
+        self.finish(checkpoint, result.status.is_success());
+        self.inspector
+            .frame_end(depth, &result.status, gas - result.gas_left);
+        result
+    }
+
+    /// Deploys the initcode, returning the address of the new contract on success.
+    pub(crate) fn execute_create(
+        &mut self,
+        caller: Address,
+        value: U256,
+        initcode: Bytes,
+        gas: u64,
+        depth: usize,
+        salt: Option<U256>,
+    ) -> (FrameResult, Option<Address>) {
+        let nonce = self.state.nonce(caller);
+        self.state.set_nonce(caller, nonce + 1);
+        let address = match salt {
+            Some(salt) => {
+                caller.create2(B256::from(salt.to_be_bytes::<32>()), keccak256(&initcode))
+            }
+            None => caller.create(nonce),
+        };
+        self.substate.warm_account(address);
+
+        let result = if self
+            .state
+            .account(address)
+            .is_some_and(|account| account.nonce != 0 || !account.code.is_empty())
+        {
+            FrameResult::halt(HaltReason::CreateCollision)
+        } else {
+            let checkpoint = self.checkpoint();
+            self.state.set_nonce(address, 1);
+            self.state.transfer(caller, address, value);
+            self.substate.create(address);
+
+            let msg = Message {
+                caller,
+                address,
+                code: initcode,
+                value,
+                data: Bytes::new(),
+                gas,
+                is_static: false,
+                depth,
+            };
+            let mut result = self.run(msg);
+            if result.status.is_success() {
+                result = self.deposit_code(address, result);
+            }
+            self.finish(checkpoint, result.status.is_success());
+            result
+        };
+
+        self.inspector
+            .frame_end(depth, &result.status, gas - result.gas_left);
+        let created = result.status.is_success().then_some(address);
+        (result, created)
+    }
+
+    fn deposit_code(&mut self, address: Address, result: FrameResult) -> FrameResult {
+        let code = result.output;
+        let deposit = gas::CODE_DEPOSIT * code.len() as u64;
+        if code.len() > gas::MAX_CODE_SIZE {
+            return FrameResult::halt(HaltReason::CodeSizeLimit);
+        }
+        if code.first() == Some(&0xef) {
+            return FrameResult::halt(HaltReason::InvalidCodePrefix);
+        }
+        let Some(gas_left) = result.gas_left.checked_sub(deposit) else {
+            return FrameResult::halt(HaltReason::OutOfGas);
+        };
+        self.state.set_code(address, code);
+        FrameResult {
+            status: Status::Success,
+            output: Bytes::new(),
+            gas_left,
+        }
+    }
+}
+
+fn precompiles(fork: Fork) -> &'static Precompiles {
+    Precompiles::new(match fork {
+        Fork::Paris | Fork::Shanghai => PrecompileSpecId::BERLIN,
+        // Point evaluation (EIP-4844).
+        Fork::Cancun => PrecompileSpecId::CANCUN,
+    })
+}
+
+/// Runs the precompile, failures consuming all gas like exceptional halts.
+fn precompile(address: Address, msg: &Message, fork: Fork) -> FrameResult {
+    let result = match precompiles(fork).get(&address) {
+        Some(Precompile::Standard(run)) => run(&msg.data, msg.gas),
+        Some(Precompile::Env(run)) => run(&msg.data, msg.gas, &PrecompileEnv::default()),
+        _ => unreachable!("only the standard precompiles are used"),
+    };
+    match result {
+        Ok(output) => FrameResult {
+            status: Status::Success,
+            output: output.bytes,
+            gas_left: msg.gas - output.gas_used,
+        },
+        Err(PrecompileErrors::Error(PrecompileError::OutOfGas)) => {
+            FrameResult::halt(HaltReason::OutOfGas)
+        }
+        Err(_) => FrameResult::halt(HaltReason::PrecompileFailure(address)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inspector::Step;
+    use alloy_primitives::hex;
+
+    const RETURN_42: &str = "602a60005260206000f3";
+
+    fn deploy(evm: &mut Evm<impl Inspector>, code: &str) -> Address {
+        let address = Address::with_last_byte(0xaa);
+        evm.state.set_code(address, hex::decode(code).unwrap());
+        address
+    }
+
+    fn call(evm: &mut Evm<impl Inspector>, to: Address) -> ExecutionResult {
+        let tx = Transaction {
+            to: Some(to),
+            ..Default::default()
+        };
+        evm.transact(&tx).unwrap()
+    }
+
+    #[test]
+    fn returns_and_charges_gas() {
+        let mut evm = Evm::new(Env::default());
+        let address = deploy(&mut evm, RETURN_42);
+        let result = call(&mut evm, address);
+
+        assert_eq!(result.status, Status::Success);
+        assert_eq!(U256::from_be_slice(&result.output), U256::from(42));
+        assert_eq!(result.gas_used, 21_018);
+    }
+
+    #[test]
+    fn refunds_cleared_storage() {
+        let mut evm = Evm::new(Env::default());
+        // sstore(0, 1) sstore(0, 0)
+        let address = deploy(&mut evm, "60016000556000600055");
+        let result = call(&mut evm, address);
+
+        assert_eq!(result.status, Status::Success);
+        assert_eq!(result.gas_refunded, 43_212 / 5);
+        assert_eq!(result.gas_used, 43_212 - 43_212 / 5);
+        assert!(evm.state.account(address).unwrap().storage.is_empty());
+    }
+
+    #[test]
+    fn prices_storage_against_the_transaction_start() {
+        let mut evm = Evm::new(Env::default());
+        // sstore(0, add(sload(0), 1))
+        let address = deploy(&mut evm, "600054600101600055");
+
+        assert_eq!(call(&mut evm, address).gas_used, 43_112);
+        // The slot is no longer zero at the start of the second transaction.
+        assert_eq!(call(&mut evm, address).gas_used, 26_012);
+        assert_eq!(evm.state.storage(address, U256::ZERO), U256::from(2));
+    }
+
+    #[test]
+    fn reverts_state_changes() {
+        let mut evm = Evm::new(Env::default());
+        // sstore(0, 1) revert(0, 0)
+        let address = deploy(&mut evm, "600160005560006000fd");
+        let result = call(&mut evm, address);
+
+        assert_eq!(result.status, Status::Revert);
+        assert_eq!(evm.state.storage(address, U256::ZERO), U256::ZERO);
+    }
+
+    #[test]
+    fn honors_the_fork() {
+        let mut evm = Evm::new(Env::default());
+        let address = deploy(&mut evm, "5f00");
+        assert_eq!(
+            call(&mut evm, address).status,
+            Status::Halt(HaltReason::InvalidOpcode(0x5f))
+        );
+
+        evm.env.fork = Fork::Shanghai;
+        assert_eq!(call(&mut evm, address).status, Status::Success);
+    }
+
+    #[test]
+    fn deploys_and_calls_contracts() {
+        let mut evm = Evm::new(Env::default());
+        let caller = Address::with_last_byte(0xca);
+        // codecopy(0, 12, 10) return(0, 10)
+        let initcode = format!("600a600c600039600a6000f3{}", RETURN_42);
+        let tx = Transaction {
+            caller,
+            data: hex::decode(initcode).unwrap().into(),
+            ..Default::default()
+        };
+        let result = evm.transact(&tx).unwrap();
+        assert_eq!(result.created, Some(caller.create(0)));
+
+        // staticcall(gas, created, 0, 0, 0, 32) pop return(0, 32)
+        let proxy = format!(
+            "60206000600060007f{}5afa5060206000f3",
+            hex::encode(caller.create(0).into_word())
+        );
+        let proxy = deploy(&mut evm, &proxy);
+        let result = call(&mut evm, proxy);
+        assert_eq!(result.status, Status::Success);
+        assert_eq!(U256::from_be_slice(&result.output), U256::from(42));
+    }
+
+    #[test]
+    fn runs_precompiles() {
+        let mut evm = Evm::new(Env::default());
+        // staticcall(gas, 2, 0, 0, 0, 32) pop return(0, 32)
+        let sha256 = deploy(&mut evm, "602060006000600060025afa5060206000f3");
+        let result = call(&mut evm, sha256);
+        assert_eq!(
+            hex::encode(&result.output),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+
+        // mstore(0, 1) mstore(32, 1) staticcall(gas, 6, 0, 64, 0, 32) mstore(0, success)
+        // return(0, 32), (1, 1) not being on the curve
+        let ecadd = deploy(
+            &mut evm,
+            "60016000526001602052602060006040600060065afa60005260206000f3",
+        );
+        let result = call(&mut evm, ecadd);
+        assert_eq!(result.status, Status::Success);
+        assert_eq!(U256::from_be_slice(&result.output), U256::ZERO);
+    }
+
+    #[test]
+    fn inspects_steps() {
+        #[derive(Default)]
+        struct Trace(Vec<(usize, u8, Vec<U256>)>);
+
+        impl Inspector for Trace {
+            fn step(&mut self, step: &Step<'_>) {
+                self.0.push((step.pc, step.opcode, step.stack.to_vec()));
+            }
+        }
+
+        let mut evm = Evm::with_inspector(Env::default(), Trace::default());
+        // push1 1 push1 2 add stop
+        let address = deploy(&mut evm, "600160020100");
+        call(&mut evm, address);
+
+        let one = U256::from(1);
+        assert_eq!(
+            evm.inspector.0,
+            [
+                (0, 0x60, vec![]),
+                (2, 0x60, vec![one]),
+                (4, 0x01, vec![one, U256::from(2)]),
+                (5, 0x00, vec![U256::from(3)]),
+            ]
+        );
+    }
+}
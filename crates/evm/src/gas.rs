@@ -0,0 +1,123 @@
+//! Gas schedule from Paris onwards.
+
+use crate::{opcode::*, Fork};
+
+pub const TX_BASE: u64 = 21_000;
+pub const TX_CREATE: u64 = 32_000;
+pub const TX_DATA_ZERO: u64 = 4;
+pub const TX_DATA_NON_ZERO: u64 = 16;
+
+pub const WARM_ACCESS: u64 = 100;
+pub const COLD_ACCOUNT_ACCESS: u64 = 2_600;
+pub const COLD_SLOAD: u64 = 2_100;
+pub const SSTORE_SET: u64 = 20_000;
+pub const SSTORE_RESET: u64 = 5_000;
+/// Clearing a slot refunds this much since EIP-3529.
+pub const SSTORE_CLEARS_REFUND: i64 = 4_800;
+/// `SSTORE` fails if no more than the call stipend is left (EIP-2200).
+pub const SSTORE_SENTRY: u64 = 2_300;
+
+pub const CALL_VALUE: u64 = 9_000;
+pub const CALL_STIPEND: u64 = 2_300;
+pub const NEW_ACCOUNT: u64 = 25_000;
+pub const CREATE_BASE: u64 = 32_000;
+pub const CODE_DEPOSIT: u64 = 200;
+pub const SELFDESTRUCT_BASE: u64 = 5_000;
+
+pub const COPY_WORD: u64 = 3;
+pub const KECCAK256_WORD: u64 = 6;
+pub const INITCODE_WORD: u64 = 2;
+pub const EXP_BYTE: u64 = 50;
+pub const LOG: u64 = 375;
+pub const LOG_TOPIC: u64 = 375;
+pub const LOG_DATA: u64 = 8;
+
+/// Refunds are capped to a fifth of the gas used since EIP-3529.
+pub const MAX_REFUND_QUOTIENT: u64 = 5;
+
+pub const MAX_CODE_SIZE: usize = 0x6000;
+pub const MAX_INITCODE_SIZE: usize = 2 * MAX_CODE_SIZE;
+
+pub fn words(len: usize) -> u64 {
+    (len as u64).div_ceil(32)
+}
+
+/// Total cost of having the given number of memory words expanded.
+pub fn memory(words: u64) -> u64 {
+    words
+        .saturating_mul(3)
+        .saturating_add(words.saturating_mul(words) / 512)
+}
+
+/// The gas available to a sub call or create, all but one 64th (EIP-150).
+pub fn all_but_one_64th(gas: u64) -> u64 {
+    gas - gas / 64
+}
+
+/// Gas charged before a transaction is executed.
+pub fn intrinsic(fork: Fork, data: &[u8], is_create: bool) -> u64 {
+    let zeros = data.iter().filter(|byte| **byte == 0).count() as u64;
+    let non_zeros = data.len() as u64 - zeros;
+    let mut gas = TX_BASE + zeros * TX_DATA_ZERO + non_zeros * TX_DATA_NON_ZERO;
+    if is_create {
+        gas += TX_CREATE;
+        if fork >= Fork::Shanghai {
+            gas += INITCODE_WORD * words(data.len());
+        }
+    }
+    gas
+}
+
+/// Constant part of the opcode's cost, dynamic costs (memory expansion, cold access, copies, ...)
+/// are charged while executing it.
+pub fn base(op: u8) -> u64 {
+    match op {
+        STOP | RETURN | REVERT => 0,
+        JUMPDEST => 1,
+        ADDRESS | ORIGIN | CALLER | CALLVALUE | CALLDATASIZE | CODESIZE | GASPRICE
+        | RETURNDATASIZE | COINBASE | TIMESTAMP | NUMBER | PREVRANDAO | GASLIMIT | CHAINID
+        | BASEFEE | BLOBBASEFEE | POP | PC | MSIZE | GAS | PUSH0 => 2,
+        ADD | SUB | NOT | LT | GT | SLT | SGT | EQ | ISZERO | AND | OR | XOR | BYTE | SHL | SHR
+        | SAR | CALLDATALOAD | CALLDATACOPY | CODECOPY | RETURNDATACOPY | MLOAD | MSTORE
+        | MSTORE8 | MCOPY | BLOBHASH => 3,
+        PUSH1..=PUSH32 | DUP1..=DUP16 | SWAP1..=SWAP16 => 3,
+        MUL | DIV | SDIV | MOD | SMOD | SIGNEXTEND | SELFBALANCE => 5,
+        ADDMOD | MULMOD | JUMP => 8,
+        EXP | JUMPI => 10,
+        BLOCKHASH => 20,
+        KECCAK256 => 30,
+        TLOAD | TSTORE => WARM_ACCESS,
+        LOG0..=LOG4 => LOG + LOG_TOPIC * u64::from(op - LOG0),
+        CREATE | CREATE2 => CREATE_BASE,
+        SELFDESTRUCT => SELFDESTRUCT_BASE,
+        // Account and storage accesses are charged depending on their warmth.
+        _ => 0,
+    }
+}
+
+/// Least the opcode can cost, account and storage accesses costing at least a warm access.
+pub fn min(op: u8) -> u64 {
+    match op {
+        BALANCE | EXTCODESIZE | EXTCODECOPY | EXTCODEHASH | SLOAD | SSTORE | CALL | CALLCODE
+        | DELEGATECALL | STATICCALL => WARM_ACCESS,
+        _ => base(op),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_intrinsic_gas() {
+        assert_eq!(intrinsic(Fork::Paris, &[0, 1], false), 21_020);
+        assert_eq!(intrinsic(Fork::Paris, &[0; 33], true), 53_132);
+        assert_eq!(intrinsic(Fork::Shanghai, &[0; 33], true), 53_136);
+    }
+
+    #[test]
+    fn expands_memory_quadratically() {
+        assert_eq!(memory(1), 3);
+        assert_eq!(memory(1024), 3 * 1024 + 2048);
+    }
+}
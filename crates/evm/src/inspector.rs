@@ -0,0 +1,56 @@
+use crate::Status;
+use alloy_primitives::{Address, U256};
+use std::collections::BTreeMap;
+
+/// Machine state right before an instruction is executed.
+#[derive(Debug, Clone, Copy)]
+pub struct Step<'a> {
+    /// Call depth, 0 being the transaction's frame.
+    pub depth: usize,
+    /// Account whose storage the code operates on.
+    pub address: Address,
+    pub code: &'a [u8],
+    pub pc: usize,
+    pub opcode: u8,
+    pub gas_remaining: u64,
+    /// Stack from bottom to top.
+    pub stack: &'a [U256],
+    pub memory: &'a [u8],
+    pub storage: &'a BTreeMap<U256, U256>,
+}
+
+/// Hooks into the execution, all of them do nothing by default.
+pub trait Inspector {
+    /// Called before each instruction, including ones that end up failing.
+    fn step(&mut self, _step: &Step<'_>) {}
+
+    /// Called when a call or create frame at the given depth finished.
+    fn frame_end(&mut self, _depth: usize, _status: &Status, _gas_used: u64) {}
+}
+
+impl Inspector for () {}
+
+impl<I: Inspector + ?Sized> Inspector for &mut I {
+    fn step(&mut self, step: &Step<'_>) {
+        (**self).step(step)
+    }
+
+    fn frame_end(&mut self, depth: usize, status: &Status, gas_used: u64) {
+        (**self).frame_end(depth, status, gas_used)
+    }
+}
+
+/// Inspects the execution only if there's an inspector.
+impl<I: Inspector> Inspector for Option<I> {
+    fn step(&mut self, step: &Step<'_>) {
+        if let Some(inspector) = self {
+            inspector.step(step)
+        }
+    }
+
+    fn frame_end(&mut self, depth: usize, status: &Status, gas_used: u64) {
+        if let Some(inspector) = self {
+            inspector.frame_end(depth, status, gas_used)
+        }
+    }
+}
@@ -0,0 +1,817 @@
+use crate::{
+    env::Fork,
+    evm::{Evm, FrameResult, HaltReason, Status},
+    gas,
+    inspector::{Inspector, Step},
+    opcode::{self, *},
+};
+use alloy_primitives::{keccak256, Address, Bytes, Log, B256, U256};
+use std::{collections::BTreeMap, ops::Range};
+
+const STACK_LIMIT: usize = 1024;
+const CALL_DEPTH_LIMIT: usize = 1024;
+
+static EMPTY_STORAGE: BTreeMap<U256, U256> = BTreeMap::new();
+
+/// A call or create frame to be executed.
+#[derive(Debug, Clone)]
+pub(crate) struct Message {
+    pub caller: Address,
+    /// Account whose storage and balance the code operates on.
+    pub address: Address,
+    pub code: Bytes,
+    pub value: U256,
+    pub data: Bytes,
+    pub gas: u64,
+    pub is_static: bool,
+    pub depth: usize,
+}
+
+enum Control {
+    Continue,
+    Stop,
+    Return(Bytes),
+    Revert(Bytes),
+}
+
+struct Frame {
+    msg: Message,
+    jumpdests: Vec<bool>,
+    pc: usize,
+    stack: Vec<U256>,
+    memory: Vec<u8>,
+    gas: u64,
+    return_data: Bytes,
+}
+
+/// Offsets of the `JUMPDEST`s in the code, skipping push data.
+fn jumpdests(code: &[u8]) -> Vec<bool> {
+    let mut jumpdests = vec![false; code.len()];
+    let mut pc = 0;
+    while pc < code.len() {
+        jumpdests[pc] = code[pc] == JUMPDEST;
+        pc += 1 + opcode::immediate_size(code[pc]);
+    }
+    jumpdests
+}
+
+/// Reads `len` bytes at the offset, zero padded where out of bounds.
+fn read_padded(src: &[u8], offset: U256, len: usize) -> Vec<u8> {
+    let mut out = vec![0; len];
+    if let Ok(offset) = usize::try_from(offset) {
+        if offset < src.len() {
+            let end = src.len().min(offset.saturating_add(len));
+            out[..end - offset].copy_from_slice(&src[offset..end]);
+        }
+    }
+    out
+}
+
+fn to_address(word: U256) -> Address {
+    Address::from_word(B256::from(word.to_be_bytes::<32>()))
+}
+
+fn to_word(address: Address) -> U256 {
+    U256::from_be_bytes(address.into_word().0)
+}
+
+fn is_negative(x: U256) -> bool {
+    x.bit(255)
+}
+
+fn abs(x: U256) -> U256 {
+    if is_negative(x) {
+        x.wrapping_neg()
+    } else {
+        x
+    }
+}
+
+fn sdiv(a: U256, b: U256) -> U256 {
+    if b.is_zero() {
+        return U256::ZERO;
+    }
+    let quotient = abs(a) / abs(b);
+    if is_negative(a) != is_negative(b) {
+        quotient.wrapping_neg()
+    } else {
+        quotient
+    }
+}
+
+fn smod(a: U256, b: U256) -> U256 {
+    if b.is_zero() {
+        return U256::ZERO;
+    }
+    let remainder = abs(a) % abs(b);
+    if is_negative(a) {
+        remainder.wrapping_neg()
+    } else {
+        remainder
+    }
+}
+
+fn slt(a: U256, b: U256) -> bool {
+    match (is_negative(a), is_negative(b)) {
+        (true, false) => true,
+        (false, true) => false,
+        _ => a < b,
+    }
+}
+
+fn signextend(byte: U256, x: U256) -> U256 {
+    if byte >= U256::from(31) {
+        return x;
+    }
+    let bit = byte.to::<usize>() * 8 + 7;
+    let mask = (U256::from(1) << (bit + 1)) - U256::from(1);
+    if x.bit(bit) {
+        x | !mask
+    } else {
+        x & mask
+    }
+}
+
+fn shift(by: U256) -> Option<usize> {
+    usize::try_from(by).ok().filter(|by| *by < 256)
+}
+
+impl Frame {
+    fn new(msg: Message) -> Self {
+        Self {
+            jumpdests: jumpdests(&msg.code),
+            gas: msg.gas,
+            msg,
+            pc: 0,
+            stack: Vec::with_capacity(STACK_LIMIT),
+            memory: Vec::new(),
+            return_data: Bytes::new(),
+        }
+    }
+
+    fn charge(&mut self, gas: u64) -> Result<(), HaltReason> {
+        self.gas = self.gas.checked_sub(gas).ok_or(HaltReason::OutOfGas)?;
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<U256, HaltReason> {
+        self.stack.pop().ok_or(HaltReason::StackUnderflow)
+    }
+
+    /// Pops the top `N` items, the top of the stack first.
+    fn pop_n<const N: usize>(&mut self) -> Result<[U256; N], HaltReason> {
+        if self.stack.len() < N {
+            return Err(HaltReason::StackUnderflow);
+        }
+        let mut items = [U256::ZERO; N];
+        for item in items.iter_mut() {
+            *item = self.stack.pop().unwrap();
+        }
+        Ok(items)
+    }
+
+    fn push(&mut self, value: U256) -> Result<(), HaltReason> {
+        if self.stack.len() >= STACK_LIMIT {
+            return Err(HaltReason::StackOverflow);
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn push_bool(&mut self, value: bool) -> Result<(), HaltReason> {
+        self.push(U256::from(value as u8))
+    }
+
+    /// Expands memory to cover the range, charging for the expansion. Empty ranges never expand
+    /// memory, whatever their offset.
+    fn memory_range(&mut self, offset: U256, len: U256) -> Result<Range<usize>, HaltReason> {
+        if len.is_zero() {
+            return Ok(0..0);
+        }
+        // Anything beyond 4 GiB would run out of gas anyway.
+        let (Ok(offset), Ok(len)) = (u32::try_from(offset), u32::try_from(len)) else {
+            return Err(HaltReason::OutOfGas);
+        };
+        let (offset, end) = (offset as usize, offset as usize + len as usize);
+        let words = gas::words(end);
+        let current_words = (self.memory.len() / 32) as u64;
+        if words > current_words {
+            self.charge(gas::memory(words) - gas::memory(current_words))?;
+            self.memory.resize(words as usize * 32, 0);
+        }
+        Ok(offset..end)
+    }
+
+    /// Copies `src[offset..offset + len]`, zero padded, to memory at `dest`.
+    fn copy_to_memory(
+        &mut self,
+        dest: U256,
+        offset: U256,
+        len: U256,
+        src: &[u8],
+    ) -> Result<(), HaltReason> {
+        let range = self.memory_range(dest, len)?;
+        self.charge(gas::COPY_WORD * gas::words(range.len()))?;
+        let data = read_padded(src, offset, range.len());
+        self.memory[range].copy_from_slice(&data);
+        Ok(())
+    }
+
+    fn jump(&mut self, dest: U256) -> Result<(), HaltReason> {
+        self.pc = usize::try_from(dest)
+            .ok()
+            .filter(|dest| self.jumpdests.get(*dest) == Some(&true))
+            .ok_or(HaltReason::InvalidJump)?;
+        Ok(())
+    }
+
+    fn check_static(&self) -> Result<(), HaltReason> {
+        if self.msg.is_static {
+            return Err(HaltReason::StateChangeDuringStaticCall);
+        }
+        Ok(())
+    }
+}
+
+impl<I: Inspector> Evm<I> {
+    pub(crate) fn run(&mut self, msg: Message) -> FrameResult {
+        let mut frame = Frame::new(msg);
+        loop {
+            let op = frame.msg.code.get(frame.pc).copied().unwrap_or(STOP);
+            let storage = self
+                .state
+                .account(frame.msg.address)
+                .map_or(&EMPTY_STORAGE, |account| &account.storage);
+            self.inspector.step(&Step {
+                depth: frame.msg.depth,
+                address: frame.msg.address,
+                code: &frame.msg.code,
+                pc: frame.pc,
+                opcode: op,
+                gas_remaining: frame.gas,
+                stack: &frame.stack,
+                memory: &frame.memory,
+                storage,
+            });
+
+            let (status, output) = match self.execute(&mut frame, op) {
+                Ok(Control::Continue) => continue,
+                Ok(Control::Stop) => (Status::Success, Bytes::new()),
+                Ok(Control::Return(output)) => (Status::Success, output),
+                Ok(Control::Revert(output)) => (Status::Revert, output),
+                Err(reason) => return FrameResult::halt(reason),
+            };
+            return FrameResult {
+                status,
+                output,
+                gas_left: frame.gas,
+            };
+        }
+    }
+
+    fn access_account(&mut self, f: &mut Frame, address: Address) -> Result<(), HaltReason> {
+        if self.substate.warm_account(address) {
+            f.charge(gas::COLD_ACCOUNT_ACCESS)
+        } else {
+            f.charge(gas::WARM_ACCESS)
+        }
+    }
+
+    fn execute(&mut self, f: &mut Frame, op: u8) -> Result<Control, HaltReason> {
+        if !opcode::is_enabled(op, self.env.fork) {
+            return Err(HaltReason::InvalidOpcode(op));
+        }
+        f.charge(gas::base(op))?;
+        let pc = f.pc;
+        f.pc += 1;
+
+        match op {
+            STOP => return Ok(Control::Stop),
+            ADD => {
+                let [a, b] = f.pop_n()?;
+                f.push(a.wrapping_add(b))?;
+            }
+            MUL => {
+                let [a, b] = f.pop_n()?;
+                f.push(a.wrapping_mul(b))?;
+            }
+            SUB => {
+                let [a, b] = f.pop_n()?;
+                f.push(a.wrapping_sub(b))?;
+            }
+            DIV => {
+                let [a, b] = f.pop_n()?;
+                f.push(a.checked_div(b).unwrap_or_default())?;
+            }
+            SDIV => {
+                let [a, b] = f.pop_n()?;
+                f.push(sdiv(a, b))?;
+            }
+            MOD => {
+                let [a, b] = f.pop_n()?;
+                f.push(a.checked_rem(b).unwrap_or_default())?;
+            }
+            SMOD => {
+                let [a, b] = f.pop_n()?;
+                f.push(smod(a, b))?;
+            }
+            ADDMOD => {
+                let [a, b, n] = f.pop_n()?;
+                f.push(a.add_mod(b, n))?;
+            }
+            MULMOD => {
+                let [a, b, n] = f.pop_n()?;
+                f.push(a.mul_mod(b, n))?;
+            }
+            EXP => {
+                let [a, exponent] = f.pop_n()?;
+                f.charge(gas::EXP_BYTE * exponent.byte_len() as u64)?;
+                f.push(a.pow(exponent))?;
+            }
+            SIGNEXTEND => {
+                let [byte, x] = f.pop_n()?;
+                f.push(signextend(byte, x))?;
+            }
+            LT => {
+                let [a, b] = f.pop_n()?;
+                f.push_bool(a < b)?;
+            }
+            GT => {
+                let [a, b] = f.pop_n()?;
+                f.push_bool(a > b)?;
+            }
+            SLT => {
+                let [a, b] = f.pop_n()?;
+                f.push_bool(slt(a, b))?;
+            }
+            SGT => {
+                let [a, b] = f.pop_n()?;
+                f.push_bool(slt(b, a))?;
+            }
+            EQ => {
+                let [a, b] = f.pop_n()?;
+                f.push_bool(a == b)?;
+            }
+            ISZERO => {
+                let a = f.pop()?;
+                f.push_bool(a.is_zero())?;
+            }
+            AND => {
+                let [a, b] = f.pop_n()?;
+                f.push(a & b)?;
+            }
+            OR => {
+                let [a, b] = f.pop_n()?;
+                f.push(a | b)?;
+            }
+            XOR => {
+                let [a, b] = f.pop_n()?;
+                f.push(a ^ b)?;
+            }
+            NOT => {
+                let a = f.pop()?;
+                f.push(!a)?;
+            }
+            BYTE => {
+                let [i, x] = f.pop_n()?;
+                let byte = match usize::try_from(i) {
+                    Ok(i) if i < 32 => x.byte(31 - i),
+                    _ => 0,
+                };
+                f.push(U256::from(byte))?;
+            }
+            SHL => {
+                let [by, value] = f.pop_n()?;
+                f.push(shift(by).map_or(U256::ZERO, |by| value << by))?;
+            }
+            SHR => {
+                let [by, value] = f.pop_n()?;
+                f.push(shift(by).map_or(U256::ZERO, |by| value >> by))?;
+            }
+            SAR => {
+                let [by, value] = f.pop_n()?;
+                let result = match shift(by) {
+                    Some(by) => value.arithmetic_shr(by),
+                    None if is_negative(value) => U256::MAX,
+                    None => U256::ZERO,
+                };
+                f.push(result)?;
+            }
+            KECCAK256 => {
+                let [offset, len] = f.pop_n()?;
+                let range = f.memory_range(offset, len)?;
+                f.charge(gas::KECCAK256_WORD * gas::words(range.len()))?;
+                let hash = keccak256(&f.memory[range]);
+                f.push(U256::from_be_bytes(hash.0))?;
+            }
+            ADDRESS => f.push(to_word(f.msg.address))?,
+            BALANCE => {
+                let address = to_address(f.pop()?);
+                self.access_account(f, address)?;
+                f.push(self.state.balance(address))?;
+            }
+            ORIGIN => f.push(to_word(self.tx.caller))?,
+            CALLER => f.push(to_word(f.msg.caller))?,
+            CALLVALUE => f.push(f.msg.value)?,
+            CALLDATALOAD => {
+                let offset = f.pop()?;
+                let word = read_padded(&f.msg.data, offset, 32);
+                f.push(U256::from_be_slice(&word))?;
+            }
+            CALLDATASIZE => f.push(U256::from(f.msg.data.len()))?,
+            CALLDATACOPY => {
+                let [dest, offset, len] = f.pop_n()?;
+                let data = f.msg.data.clone();
+                f.copy_to_memory(dest, offset, len, &data)?;
+            }
+            CODESIZE => f.push(U256::from(f.msg.code.len()))?,
+            CODECOPY => {
+                let [dest, offset, len] = f.pop_n()?;
+                let code = f.msg.code.clone();
+                f.copy_to_memory(dest, offset, len, &code)?;
+            }
+            GASPRICE => f.push(self.tx.gas_price)?,
+            EXTCODESIZE => {
+                let address = to_address(f.pop()?);
+                self.access_account(f, address)?;
+                f.push(U256::from(self.state.code(address).len()))?;
+            }
+            EXTCODECOPY => {
+                let [address, dest, offset, len] = f.pop_n()?;
+                let address = to_address(address);
+                self.access_account(f, address)?;
+                f.copy_to_memory(dest, offset, len, &self.state.code(address))?;
+            }
+            RETURNDATASIZE => f.push(U256::from(f.return_data.len()))?,
+            RETURNDATACOPY => {
+                let [dest, offset, len] = f.pop_n()?;
+                let in_bounds = offset
+                    .checked_add(len)
+                    .is_some_and(|end| end <= U256::from(f.return_data.len()));
+                if !in_bounds {
+                    return Err(HaltReason::ReturnDataOutOfBounds);
+                }
+                let data = f.return_data.clone();
+                f.copy_to_memory(dest, offset, len, &data)?;
+            }
+            EXTCODEHASH => {
+                let address = to_address(f.pop()?);
+                self.access_account(f, address)?;
+                let hash = match self.state.account(address) {
+                    Some(account) if !account.is_empty() => {
+                        U256::from_be_bytes(account.code_hash().0)
+                    }
+                    _ => U256::ZERO,
+                };
+                f.push(hash)?;
+            }
+            // There is no block history to draw hashes from.
+            BLOCKHASH => {
+                f.pop()?;
+                f.push(U256::ZERO)?;
+            }
+            COINBASE => f.push(to_word(self.env.block.coinbase))?,
+            TIMESTAMP => f.push(U256::from(self.env.block.timestamp))?,
+            NUMBER => f.push(U256::from(self.env.block.number))?,
+            PREVRANDAO => f.push(U256::from_be_bytes(self.env.block.prevrandao.0))?,
+            GASLIMIT => f.push(U256::from(self.env.block.gas_limit))?,
+            CHAINID => f.push(U256::from(self.env.block.chain_id))?,
+            SELFBALANCE => f.push(self.state.balance(f.msg.address))?,
+            BASEFEE => f.push(self.env.block.base_fee)?,
+            BLOBHASH => {
+                let index = f.pop()?;
+                let hash = usize::try_from(index)
+                    .ok()
+                    .and_then(|index| self.tx.blob_hashes.get(index))
+                    .map_or(U256::ZERO, |hash| U256::from_be_bytes(hash.0));
+                f.push(hash)?;
+            }
+            BLOBBASEFEE => f.push(self.env.block.blob_base_fee)?,
+            POP => {
+                f.pop()?;
+            }
+            MLOAD => {
+                let offset = f.pop()?;
+                let range = f.memory_range(offset, U256::from(32))?;
+                let word = U256::from_be_slice(&f.memory[range]);
+                f.push(word)?;
+            }
+            MSTORE => {
+                let [offset, value] = f.pop_n()?;
+                let range = f.memory_range(offset, U256::from(32))?;
+                f.memory[range].copy_from_slice(&value.to_be_bytes::<32>());
+            }
+            MSTORE8 => {
+                let [offset, value] = f.pop_n()?;
+                let range = f.memory_range(offset, U256::from(1))?;
+                f.memory[range.start] = value.byte(0);
+            }
+            SLOAD => {
+                let slot = f.pop()?;
+                let address = f.msg.address;
+                if self.substate.warm_slot(address, slot) {
+                    f.charge(gas::COLD_SLOAD)?;
+                } else {
+                    f.charge(gas::WARM_ACCESS)?;
+                }
+                f.push(self.state.storage(address, slot))?;
+            }
+            SSTORE => self.sstore(f)?,
+            JUMP => {
+                let dest = f.pop()?;
+                f.jump(dest)?;
+            }
+            JUMPI => {
+                let [dest, condition] = f.pop_n()?;
+                if !condition.is_zero() {
+                    f.jump(dest)?;
+                }
+            }
+            PC => f.push(U256::from(pc))?,
+            MSIZE => f.push(U256::from(f.memory.len()))?,
+            GAS => f.push(U256::from(f.gas))?,
+            JUMPDEST => {}
+            TLOAD => {
+                let slot = f.pop()?;
+                let key = (f.msg.address, slot);
+                let value = self.substate.transient.get(&key).copied();
+                f.push(value.unwrap_or_default())?;
+            }
+            TSTORE => {
+                f.check_static()?;
+                let [slot, value] = f.pop_n()?;
+                let key = (f.msg.address, slot);
+                self.substate.set_transient(key, value);
+            }
+            MCOPY => {
+                let [dest, src, len] = f.pop_n()?;
+                let src = f.memory_range(src, len)?;
+                let dest = f.memory_range(dest, len)?;
+                f.charge(gas::COPY_WORD * gas::words(src.len()))?;
+                f.memory.copy_within(src, dest.start);
+            }
+            PUSH0 => f.push(U256::ZERO)?,
+            PUSH1..=PUSH32 => {
+                let size = opcode::immediate_size(op);
+                let value = read_padded(&f.msg.code, U256::from(f.pc), size);
+                f.push(U256::from_be_slice(&value))?;
+                f.pc += size;
+            }
+            DUP1..=DUP16 => {
+                let n = (op - DUP1 + 1) as usize;
+                let Some(value) = f.stack.len().checked_sub(n).map(|i| f.stack[i]) else {
+                    return Err(HaltReason::StackUnderflow);
+                };
+                f.push(value)?;
+            }
+            SWAP1..=SWAP16 => {
+                let n = (op - SWAP1 + 1) as usize;
+                let len = f.stack.len();
+                if len <= n {
+                    return Err(HaltReason::StackUnderflow);
+                }
+                f.stack.swap(len - 1, len - 1 - n);
+            }
+            LOG0..=LOG4 => {
+                f.check_static()?;
+                let [offset, len] = f.pop_n()?;
+                let topics = (0..op - LOG0)
+                    .map(|_| Ok(B256::from(f.pop()?.to_be_bytes::<32>())))
+                    .collect::<Result<Vec<_>, HaltReason>>()?;
+                let range = f.memory_range(offset, len)?;
+                f.charge(gas::LOG_DATA * range.len() as u64)?;
+                let data = Bytes::copy_from_slice(&f.memory[range]);
+                self.substate
+                    .logs
+                    .push(Log::new_unchecked(f.msg.address, topics, data));
+            }
+            CREATE | CREATE2 => self.create(f, op == CREATE2)?,
+            CALL | CALLCODE | DELEGATECALL | STATICCALL => self.call(f, op)?,
+            RETURN | REVERT => {
+                let [offset, len] = f.pop_n()?;
+                let range = f.memory_range(offset, len)?;
+                let output = Bytes::copy_from_slice(&f.memory[range]);
+                return Ok(match op {
+                    RETURN => Control::Return(output),
+                    _ => Control::Revert(output),
+                });
+            }
+            SELFDESTRUCT => {
+                self.selfdestruct(f)?;
+                return Ok(Control::Stop);
+            }
+            _ => return Err(HaltReason::InvalidOpcode(op)),
+        }
+        Ok(Control::Continue)
+    }
+
+    /// Stores with the gas and refund rules of EIP-2200, EIP-2929 and EIP-3529.
+    fn sstore(&mut self, f: &mut Frame) -> Result<(), HaltReason> {
+        f.check_static()?;
+        if f.gas <= gas::SSTORE_SENTRY {
+            return Err(HaltReason::OutOfGas);
+        }
+        let [slot, new] = f.pop_n()?;
+        let address = f.msg.address;
+
+        let mut cost = 0;
+        if self.substate.warm_slot(address, slot) {
+            cost += gas::COLD_SLOAD;
+        }
+        let current = self.state.storage(address, slot);
+        let original = self.substate.original_storage(address, slot, current);
+        let refund = &mut self.substate.refund;
+        if current == new {
+            cost += gas::WARM_ACCESS;
+        } else if original == current {
+            cost += if original.is_zero() {
+                gas::SSTORE_SET
+            } else {
+                gas::SSTORE_RESET - gas::COLD_SLOAD
+            };
+            if !original.is_zero() && new.is_zero() {
+                *refund += gas::SSTORE_CLEARS_REFUND;
+            }
+        } else {
+            cost += gas::WARM_ACCESS;
+            if !original.is_zero() {
+                if current.is_zero() {
+                    *refund -= gas::SSTORE_CLEARS_REFUND;
+                } else if new.is_zero() {
+                    *refund += gas::SSTORE_CLEARS_REFUND;
+                }
+            }
+            if original == new {
+                let restored = if original.is_zero() {
+                    gas::SSTORE_SET - gas::WARM_ACCESS
+                } else {
+                    gas::SSTORE_RESET - gas::COLD_SLOAD - gas::WARM_ACCESS
+                };
+                *refund += restored as i64;
+            }
+        }
+        f.charge(cost)?;
+        self.state.set_storage(address, slot, new);
+        Ok(())
+    }
+
+    fn call(&mut self, f: &mut Frame, op: u8) -> Result<(), HaltReason> {
+        let [gas_requested, to] = f.pop_n()?;
+        let value = match op {
+            CALL | CALLCODE => f.pop()?,
+            _ => U256::ZERO,
+        };
+        let [in_offset, in_len, out_offset, out_len] = f.pop_n()?;
+        let to = to_address(to);
+        if op == CALL && !value.is_zero() {
+            f.check_static()?;
+        }
+
+        let input = f.memory_range(in_offset, in_len)?;
+        let output = f.memory_range(out_offset, out_len)?;
+        self.access_account(f, to)?;
+        if !value.is_zero() {
+            f.charge(gas::CALL_VALUE)?;
+            let exists = self
+                .state
+                .account(to)
+                .is_some_and(|account| !account.is_empty());
+            if op == CALL && !exists {
+                f.charge(gas::NEW_ACCOUNT)?;
+            }
+        }
+        let gas_limit = gas::all_but_one_64th(f.gas).min(gas_requested.saturating_to());
+        f.charge(gas_limit)?;
+        let stipend = if value.is_zero() {
+            0
+        } else {
+            gas::CALL_STIPEND
+        };
+
+        f.return_data = Bytes::new();
+        let balance = self.state.balance(f.msg.address);
+        if f.msg.depth >= CALL_DEPTH_LIMIT || (matches!(op, CALL | CALLCODE) && balance < value) {
+            f.gas += gas_limit + stipend;
+            return f.push(U256::ZERO);
+        }
+
+        let (caller, address, value) = match op {
+            CALL | STATICCALL => (f.msg.address, to, value),
+            CALLCODE => (f.msg.address, f.msg.address, value),
+            _ => (f.msg.caller, f.msg.address, f.msg.value),
+        };
+        let msg = Message {
+            caller,
+            address,
+            code: self.state.code(to),
+            value,
+            data: Bytes::copy_from_slice(&f.memory[input]),
+            gas: gas_limit + stipend,
+            is_static: f.msg.is_static || op == STATICCALL,
+            depth: f.msg.depth + 1,
+        };
+        let result = self.execute_call(msg, to, op != DELEGATECALL);
+
+        f.gas += result.gas_left;
+        let len = output.len().min(result.output.len());
+        f.memory[output.start..output.start + len].copy_from_slice(&result.output[..len]);
+        f.return_data = result.output;
+        f.push_bool(result.status.is_success())
+    }
+
+    fn create(&mut self, f: &mut Frame, is_create2: bool) -> Result<(), HaltReason> {
+        f.check_static()?;
+        let [value, offset, len] = f.pop_n()?;
+        let salt = if is_create2 { Some(f.pop()?) } else { None };
+        let range = f.memory_range(offset, len)?;
+        if self.env.fork >= Fork::Shanghai {
+            if range.len() > gas::MAX_INITCODE_SIZE {
+                return Err(HaltReason::InitcodeSizeLimit);
+            }
+            f.charge(gas::INITCODE_WORD * gas::words(range.len()))?;
+        }
+        if is_create2 {
+            f.charge(gas::KECCAK256_WORD * gas::words(range.len()))?;
+        }
+        let initcode = Bytes::copy_from_slice(&f.memory[range]);
+        let gas_limit = gas::all_but_one_64th(f.gas);
+        f.charge(gas_limit)?;
+
+        f.return_data = Bytes::new();
+        let address = f.msg.address;
+        let nonce = self
+            .state
+            .account(address)
+            .map_or(0, |account| account.nonce);
+        if f.msg.depth >= CALL_DEPTH_LIMIT
+            || self.state.balance(address) < value
+            || nonce == u64::MAX
+        {
+            f.gas += gas_limit;
+            return f.push(U256::ZERO);
+        }
+
+        let (result, created) =
+            self.execute_create(address, value, initcode, gas_limit, f.msg.depth + 1, salt);
+        f.gas += result.gas_left;
+        if result.status == Status::Revert {
+            f.return_data = result.output;
+        }
+        f.push(created.map_or(U256::ZERO, to_word))
+    }
+
+    /// Moves the balance to the beneficiary. Since Cancun the account is only deleted if it was
+    /// created in the same transaction (EIP-6780).
+    fn selfdestruct(&mut self, f: &mut Frame) -> Result<(), HaltReason> {
+        f.check_static()?;
+        let beneficiary = to_address(f.pop()?);
+        if self.substate.warm_account(beneficiary) {
+            f.charge(gas::COLD_ACCOUNT_ACCESS)?;
+        }
+        let address = f.msg.address;
+        let balance = self.state.balance(address);
+        let exists = self
+            .state
+            .account(beneficiary)
+            .is_some_and(|account| !account.is_empty());
+        if !balance.is_zero() && !exists {
+            f.charge(gas::NEW_ACCOUNT)?;
+        }
+
+        self.state.set_balance(address, U256::ZERO);
+        let beneficiary_balance = self.state.balance(beneficiary);
+        self.state
+            .set_balance(beneficiary, beneficiary_balance.saturating_add(balance));
+        if self.env.fork < Fork::Cancun || self.substate.created.contains(&address) {
+            self.substate.destruct(address);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn neg(x: u64) -> U256 {
+        U256::from(x).wrapping_neg()
+    }
+
+    #[test]
+    fn computes_signed_arithmetic() {
+        assert_eq!(sdiv(neg(7), U256::from(2)), neg(3));
+        assert_eq!(smod(neg(7), U256::from(2)), neg(1));
+        assert!(slt(neg(1), U256::ZERO));
+        assert!(!slt(U256::ZERO, neg(1)));
+        assert_eq!(signextend(U256::ZERO, U256::from(0xff)), U256::MAX);
+        assert_eq!(signextend(U256::ZERO, U256::from(0x17f)), U256::from(0x7f));
+    }
+
+    #[test]
+    fn skips_push_data_for_jumpdests() {
+        assert_eq!(
+            jumpdests(&[PUSH1, JUMPDEST, JUMPDEST]),
+            [false, false, true]
+        );
+    }
+}
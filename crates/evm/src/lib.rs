@@ -0,0 +1,15 @@
+//! EVM interpreter for executing compiled Huff offline, following the rules of the selected
+//! [`Fork`]. [`Inspector`]s get to see the machine state before every instruction.
+
+mod env;
+mod evm;
+pub mod gas;
+mod inspector;
+mod interpreter;
+pub mod opcode;
+mod state;
+
+pub use env::*;
+pub use evm::*;
+pub use inspector::*;
+pub use state::*;
@@ -0,0 +1,366 @@
+//! Opcode bytes, their mnemonics and documentation.
+
+use crate::Fork;
+
+pub const STOP: u8 = 0x00;
+pub const ADD: u8 = 0x01;
+pub const MUL: u8 = 0x02;
+pub const SUB: u8 = 0x03;
+pub const DIV: u8 = 0x04;
+pub const SDIV: u8 = 0x05;
+pub const MOD: u8 = 0x06;
+pub const SMOD: u8 = 0x07;
+pub const ADDMOD: u8 = 0x08;
+pub const MULMOD: u8 = 0x09;
+pub const EXP: u8 = 0x0a;
+pub const SIGNEXTEND: u8 = 0x0b;
+pub const LT: u8 = 0x10;
+pub const GT: u8 = 0x11;
+pub const SLT: u8 = 0x12;
+pub const SGT: u8 = 0x13;
+pub const EQ: u8 = 0x14;
+pub const ISZERO: u8 = 0x15;
+pub const AND: u8 = 0x16;
+pub const OR: u8 = 0x17;
+pub const XOR: u8 = 0x18;
+pub const NOT: u8 = 0x19;
+pub const BYTE: u8 = 0x1a;
+pub const SHL: u8 = 0x1b;
+pub const SHR: u8 = 0x1c;
+pub const SAR: u8 = 0x1d;
+pub const KECCAK256: u8 = 0x20;
+pub const ADDRESS: u8 = 0x30;
+pub const BALANCE: u8 = 0x31;
+pub const ORIGIN: u8 = 0x32;
+pub const CALLER: u8 = 0x33;
+pub const CALLVALUE: u8 = 0x34;
+pub const CALLDATALOAD: u8 = 0x35;
+pub const CALLDATASIZE: u8 = 0x36;
+pub const CALLDATACOPY: u8 = 0x37;
+pub const CODESIZE: u8 = 0x38;
+pub const CODECOPY: u8 = 0x39;
+pub const GASPRICE: u8 = 0x3a;
+pub const EXTCODESIZE: u8 = 0x3b;
+pub const EXTCODECOPY: u8 = 0x3c;
+pub const RETURNDATASIZE: u8 = 0x3d;
+pub const RETURNDATACOPY: u8 = 0x3e;
+pub const EXTCODEHASH: u8 = 0x3f;
+pub const BLOCKHASH: u8 = 0x40;
+pub const COINBASE: u8 = 0x41;
+pub const TIMESTAMP: u8 = 0x42;
+pub const NUMBER: u8 = 0x43;
+pub const PREVRANDAO: u8 = 0x44;
+pub const GASLIMIT: u8 = 0x45;
+pub const CHAINID: u8 = 0x46;
+pub const SELFBALANCE: u8 = 0x47;
+pub const BASEFEE: u8 = 0x48;
+pub const BLOBHASH: u8 = 0x49;
+pub const BLOBBASEFEE: u8 = 0x4a;
+pub const POP: u8 = 0x50;
+pub const MLOAD: u8 = 0x51;
+pub const MSTORE: u8 = 0x52;
+pub const MSTORE8: u8 = 0x53;
+pub const SLOAD: u8 = 0x54;
+pub const SSTORE: u8 = 0x55;
+pub const JUMP: u8 = 0x56;
+pub const JUMPI: u8 = 0x57;
+pub const PC: u8 = 0x58;
+pub const MSIZE: u8 = 0x59;
+pub const GAS: u8 = 0x5a;
+pub const JUMPDEST: u8 = 0x5b;
+pub const TLOAD: u8 = 0x5c;
+pub const TSTORE: u8 = 0x5d;
+pub const MCOPY: u8 = 0x5e;
+pub const PUSH0: u8 = 0x5f;
+pub const PUSH1: u8 = 0x60;
+pub const PUSH32: u8 = 0x7f;
+pub const DUP1: u8 = 0x80;
+pub const DUP16: u8 = 0x8f;
+pub const SWAP1: u8 = 0x90;
+pub const SWAP16: u8 = 0x9f;
+pub const LOG0: u8 = 0xa0;
+pub const LOG4: u8 = 0xa4;
+pub const CREATE: u8 = 0xf0;
+pub const CALL: u8 = 0xf1;
+pub const CALLCODE: u8 = 0xf2;
+pub const RETURN: u8 = 0xf3;
+pub const DELEGATECALL: u8 = 0xf4;
+pub const CREATE2: u8 = 0xf5;
+pub const STATICCALL: u8 = 0xfa;
+pub const REVERT: u8 = 0xfd;
+pub const INVALID: u8 = 0xfe;
+pub const SELFDESTRUCT: u8 = 0xff;
+
+#[rustfmt::skip]
+const NAMES: &[(u8, &str)] = &[
+    (STOP, "STOP"), (ADD, "ADD"), (MUL, "MUL"), (SUB, "SUB"), (DIV, "DIV"), (SDIV, "SDIV"),
+    (MOD, "MOD"), (SMOD, "SMOD"), (ADDMOD, "ADDMOD"), (MULMOD, "MULMOD"), (EXP, "EXP"),
+    (SIGNEXTEND, "SIGNEXTEND"), (LT, "LT"), (GT, "GT"), (SLT, "SLT"), (SGT, "SGT"), (EQ, "EQ"),
+    (ISZERO, "ISZERO"), (AND, "AND"), (OR, "OR"), (XOR, "XOR"), (NOT, "NOT"), (BYTE, "BYTE"),
+    (SHL, "SHL"), (SHR, "SHR"), (SAR, "SAR"), (KECCAK256, "KECCAK256"), (ADDRESS, "ADDRESS"),
+    (BALANCE, "BALANCE"), (ORIGIN, "ORIGIN"), (CALLER, "CALLER"), (CALLVALUE, "CALLVALUE"),
+    (CALLDATALOAD, "CALLDATALOAD"), (CALLDATASIZE, "CALLDATASIZE"),
+    (CALLDATACOPY, "CALLDATACOPY"), (CODESIZE, "CODESIZE"), (CODECOPY, "CODECOPY"),
+    (GASPRICE, "GASPRICE"), (EXTCODESIZE, "EXTCODESIZE"), (EXTCODECOPY, "EXTCODECOPY"),
+    (RETURNDATASIZE, "RETURNDATASIZE"), (RETURNDATACOPY, "RETURNDATACOPY"),
+    (EXTCODEHASH, "EXTCODEHASH"), (BLOCKHASH, "BLOCKHASH"), (COINBASE, "COINBASE"),
+    (TIMESTAMP, "TIMESTAMP"), (NUMBER, "NUMBER"), (PREVRANDAO, "PREVRANDAO"),
+    (GASLIMIT, "GASLIMIT"), (CHAINID, "CHAINID"), (SELFBALANCE, "SELFBALANCE"),
+    (BASEFEE, "BASEFEE"), (BLOBHASH, "BLOBHASH"), (BLOBBASEFEE, "BLOBBASEFEE"), (POP, "POP"),
+    (MLOAD, "MLOAD"), (MSTORE, "MSTORE"), (MSTORE8, "MSTORE8"), (SLOAD, "SLOAD"),
+    (SSTORE, "SSTORE"), (JUMP, "JUMP"), (JUMPI, "JUMPI"), (PC, "PC"), (MSIZE, "MSIZE"),
+    (GAS, "GAS"), (JUMPDEST, "JUMPDEST"), (TLOAD, "TLOAD"), (TSTORE, "TSTORE"),
+    (MCOPY, "MCOPY"), (PUSH0, "PUSH0"), (CREATE, "CREATE"), (CALL, "CALL"),
+    (CALLCODE, "CALLCODE"), (RETURN, "RETURN"), (DELEGATECALL, "DELEGATECALL"),
+    (CREATE2, "CREATE2"), (STATICCALL, "STATICCALL"),
+];
+
+/// Mnemonic of the opcode, `None` for undefined opcodes. `INVALID` (`0xfe`) is named despite
+/// being undefined since it's the designated invalid instruction.
+pub fn name(op: u8) -> Option<String> {
+    let name = match op {
+        PUSH1..=PUSH32 => format!("PUSH{}", op - PUSH1 + 1),
+        DUP1..=DUP16 => format!("DUP{}", op - DUP1 + 1),
+        SWAP1..=SWAP16 => format!("SWAP{}", op - SWAP1 + 1),
+        LOG0..=LOG4 => format!("LOG{}", op - LOG0),
+        REVERT => "REVERT".to_owned(),
+        INVALID => "INVALID".to_owned(),
+        SELFDESTRUCT => "SELFDESTRUCT".to_owned(),
+        _ => NAMES
+            .iter()
+            .find(|(byte, _)| *byte == op)
+            .map(|(_, name)| name.to_string())?,
+    };
+    Some(name)
+}
+
+/// Number of immediate bytes following the opcode.
+pub fn immediate_size(op: u8) -> usize {
+    match op {
+        PUSH1..=PUSH32 => (op - PUSH1 + 1) as usize,
+        _ => 0,
+    }
+}
+
+/// Whether the opcode exists in the given fork, `INVALID` and undefined opcodes never do.
+pub fn is_enabled(op: u8, fork: Fork) -> bool {
+    match op {
+        PUSH0 => fork >= Fork::Shanghai,
+        TLOAD | TSTORE | MCOPY | BLOBHASH | BLOBBASEFEE => fork >= Fork::Cancun,
+        INVALID => false,
+        _ => name(op).is_some(),
+    }
+}
+
+/// Documentation of an opcode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Doc {
+    /// Stack inputs, top of the stack first.
+    pub inputs: String,
+    pub outputs: String,
+    /// Hard fork the opcode was introduced in.
+    pub since: &'static str,
+    pub description: String,
+}
+
+#[rustfmt::skip]
+const DOCS: &[(u8, &str, &str, &str, &str)] = &[
+    (STOP, "", "", "Frontier", "Halts execution."),
+    (ADD, "a, b", "a + b", "Frontier", "Addition modulo 2^256."),
+    (MUL, "a, b", "a * b", "Frontier", "Multiplication modulo 2^256."),
+    (SUB, "a, b", "a - b", "Frontier", "Subtraction modulo 2^256."),
+    (DIV, "a, b", "a / b", "Frontier", "Unsigned integer division, 0 if b is 0."),
+    (SDIV, "a, b", "a / b", "Frontier", "Signed integer division, 0 if b is 0."),
+    (MOD, "a, b", "a % b", "Frontier", "Unsigned modulo, 0 if b is 0."),
+    (SMOD, "a, b", "a % b", "Frontier", "Signed modulo, 0 if b is 0."),
+    (ADDMOD, "a, b, N", "(a + b) % N", "Frontier", "Addition modulo N, without intermediate overflow."),
+    (MULMOD, "a, b, N", "(a * b) % N", "Frontier", "Multiplication modulo N, without intermediate overflow."),
+    (EXP, "a, exponent", "a ** exponent", "Frontier", "Exponentiation, costs 50 more gas per byte of the exponent."),
+    (SIGNEXTEND, "b, x", "y", "Frontier", "Extends the sign of the (b + 1) byte integer x to 32 bytes."),
+    (LT, "a, b", "a < b", "Frontier", "Unsigned less-than comparison."),
+    (GT, "a, b", "a > b", "Frontier", "Unsigned greater-than comparison."),
+    (SLT, "a, b", "a < b", "Frontier", "Signed less-than comparison."),
+    (SGT, "a, b", "a > b", "Frontier", "Signed greater-than comparison."),
+    (EQ, "a, b", "a == b", "Frontier", "Equality comparison."),
+    (ISZERO, "a", "a == 0", "Frontier", "Is-zero comparison."),
+    (AND, "a, b", "a & b", "Frontier", "Bitwise AND."),
+    (OR, "a, b", "a | b", "Frontier", "Bitwise OR."),
+    (XOR, "a, b", "a ^ b", "Frontier", "Bitwise XOR."),
+    (NOT, "a", "~a", "Frontier", "Bitwise NOT."),
+    (BYTE, "i, x", "y", "Frontier", "Retrieves the i-th byte of x, counting from the most significant byte."),
+    (SHL, "shift, value", "value << shift", "Constantinople", "Left shift."),
+    (SHR, "shift, value", "value >> shift", "Constantinople", "Logical right shift."),
+    (SAR, "shift, value", "value >> shift", "Constantinople", "Arithmetic (signed) right shift."),
+    (KECCAK256, "offset, size", "hash", "Frontier", "Keccak-256 hash of the given memory, costs 6 more gas per word."),
+    (ADDRESS, "", "address", "Frontier", "Address of the currently executing account."),
+    (BALANCE, "address", "balance", "Frontier", "Balance of the given account in wei, 2600 gas if cold."),
+    (ORIGIN, "", "address", "Frontier", "Address of the transaction's sender."),
+    (CALLER, "", "address", "Frontier", "Address of the direct caller."),
+    (CALLVALUE, "", "value", "Frontier", "Wei sent along with the call."),
+    (CALLDATALOAD, "i", "data[i]", "Frontier", "Loads 32 bytes of calldata starting at byte offset i."),
+    (CALLDATASIZE, "", "size", "Frontier", "Size of the calldata in bytes."),
+    (CALLDATACOPY, "destOffset, offset, size", "", "Frontier", "Copies calldata to memory."),
+    (CODESIZE, "", "size", "Frontier", "Size of the currently executing code in bytes."),
+    (CODECOPY, "destOffset, offset, size", "", "Frontier", "Copies the currently executing code to memory."),
+    (GASPRICE, "", "price", "Frontier", "Gas price of the transaction."),
+    (EXTCODESIZE, "address", "size", "Frontier", "Size of an account's code, 2600 gas if cold."),
+    (EXTCODECOPY, "address, destOffset, offset, size", "", "Frontier", "Copies an account's code to memory, 2600 gas if cold."),
+    (RETURNDATASIZE, "", "size", "Byzantium", "Size of the return data of the last call."),
+    (RETURNDATACOPY, "destOffset, offset, size", "", "Byzantium", "Copies the return data of the last call to memory."),
+    (EXTCODEHASH, "address", "hash", "Constantinople", "Hash of an account's code, 2600 gas if cold."),
+    (BLOCKHASH, "blockNumber", "hash", "Frontier", "Hash of one of the 256 most recent blocks."),
+    (COINBASE, "", "address", "Frontier", "The block's beneficiary address."),
+    (TIMESTAMP, "", "timestamp", "Frontier", "The block's timestamp."),
+    (NUMBER, "", "blockNumber", "Frontier", "The block's number."),
+    (PREVRANDAO, "", "prevrandao", "Frontier", "The previous block's RANDAO mix, the block's difficulty before Paris."),
+    (GASLIMIT, "", "gasLimit", "Frontier", "The block's gas limit."),
+    (CHAINID, "", "chainId", "Istanbul", "The chain id."),
+    (SELFBALANCE, "", "balance", "Istanbul", "Balance of the currently executing account."),
+    (BASEFEE, "", "baseFee", "London", "The block's base fee."),
+    (BLOBHASH, "index", "blobVersionedHash", "Cancun", "Versioned hash of the transaction's blob at the given index."),
+    (BLOBBASEFEE, "", "blobBaseFee", "Cancun", "The block's blob base fee."),
+    (POP, "a", "", "Frontier", "Removes the top item from the stack."),
+    (MLOAD, "offset", "value", "Frontier", "Loads a word from memory."),
+    (MSTORE, "offset, value", "", "Frontier", "Stores a word to memory."),
+    (MSTORE8, "offset, value", "", "Frontier", "Stores the lowest byte of value to memory."),
+    (SLOAD, "key", "value", "Frontier", "Loads a word from storage, 2100 gas if cold."),
+    (SSTORE, "key, value", "", "Frontier", "Stores a word to storage, cost depends on the current and original value."),
+    (JUMP, "counter", "", "Frontier", "Jumps to the destination, which has to be a JUMPDEST."),
+    (JUMPI, "counter, b", "", "Frontier", "Jumps to the destination if b is not 0."),
+    (PC, "", "counter", "Frontier", "Program counter of this instruction."),
+    (MSIZE, "", "size", "Frontier", "Size of the active memory in bytes."),
+    (GAS, "", "gas", "Frontier", "Remaining gas after this instruction."),
+    (JUMPDEST, "", "", "Frontier", "Marks a valid jump destination."),
+    (TLOAD, "key", "value", "Cancun", "Loads a word from transient storage."),
+    (TSTORE, "key, value", "", "Cancun", "Stores a word to transient storage."),
+    (MCOPY, "destOffset, offset, size", "", "Cancun", "Copies memory to memory."),
+    (PUSH0, "", "0", "Shanghai", "Places 0 on the stack."),
+    (CREATE, "value, offset, size", "address", "Frontier", "Creates a new account from the initcode in memory."),
+    (CALL, "gas, address, value, argsOffset, argsSize, retOffset, retSize", "success", "Frontier", "Calls another account."),
+    (CALLCODE, "gas, address, value, argsOffset, argsSize, retOffset, retSize", "success", "Frontier", "Calls another account's code in the context of this account."),
+    (RETURN, "offset, size", "", "Frontier", "Halts execution, returning the given memory."),
+    (DELEGATECALL, "gas, address, argsOffset, argsSize, retOffset, retSize", "success", "Homestead", "Calls another account's code, keeping the current caller and value."),
+    (CREATE2, "value, offset, size, salt", "address", "Constantinople", "Creates a new account at a deterministic address."),
+    (STATICCALL, "gas, address, argsOffset, argsSize, retOffset, retSize", "success", "Byzantium", "Calls another account, disallowing state modifications."),
+    (REVERT, "offset, size", "", "Byzantium", "Halts execution reverting state changes, returning the given memory."),
+    (INVALID, "", "", "Frontier", "Designated invalid instruction, consumes all remaining gas."),
+    (SELFDESTRUCT, "address", "", "Frontier", "Sends the account's balance to the given address, only deletes the account if created in the same transaction since Cancun."),
+];
+
+fn stack_items(name: &str, count: usize) -> String {
+    (1..=count)
+        .map(|i| format!("{}{}", name, i))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Joins stack descriptions, skipping empty ones.
+fn join(parts: &[&str]) -> String {
+    parts
+        .iter()
+        .filter(|part| !part.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Stack effect and description of the opcode, `None` for undefined opcodes.
+pub fn doc(op: u8) -> Option<Doc> {
+    let generated = |inputs: String, outputs: String, description: String| Doc {
+        inputs,
+        outputs,
+        since: "Frontier",
+        description,
+    };
+    let doc = match op {
+        PUSH1..=PUSH32 => {
+            let n = op - PUSH1 + 1;
+            generated(
+                String::new(),
+                "value".to_owned(),
+                format!("Places a {} byte item on the stack.", n),
+            )
+        }
+        DUP1..=DUP16 => {
+            let n = usize::from(op - DUP1 + 1);
+            generated(
+                stack_items("a", n),
+                format!("a{}, {}", n, stack_items("a", n)),
+                format!("Duplicates the {}. stack item.", n),
+            )
+        }
+        SWAP1..=SWAP16 => {
+            let n = usize::from(op - SWAP1 + 1);
+            generated(
+                join(&["a", &stack_items("x", n - 1), "b"]),
+                join(&["b", &stack_items("x", n - 1), "a"]),
+                format!("Exchanges the 1. and {}. stack items.", n + 1),
+            )
+        }
+        LOG0..=LOG4 => {
+            let n = usize::from(op - LOG0);
+            generated(
+                join(&["offset, size", &stack_items("topic", n)]),
+                String::new(),
+                format!(
+                    "Appends a log record with {} topic{}, costs 8 more gas per byte of data.",
+                    n,
+                    if n == 1 { "" } else { "s" }
+                ),
+            )
+        }
+        _ => DOCS.iter().find(|(byte, ..)| *byte == op).map(
+            |&(_, inputs, outputs, since, description)| Doc {
+                inputs: inputs.to_owned(),
+                outputs: outputs.to_owned(),
+                since,
+                description: description.to_owned(),
+            },
+        )?,
+    };
+    Some(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_opcodes() {
+        assert_eq!(name(0x01).as_deref(), Some("ADD"));
+        assert_eq!(name(0x61).as_deref(), Some("PUSH2"));
+        assert_eq!(name(0x9f).as_deref(), Some("SWAP16"));
+        assert_eq!(name(0xa2).as_deref(), Some("LOG2"));
+        assert_eq!(name(0x0c), None);
+        assert_eq!(immediate_size(0x7f), 32);
+    }
+
+    #[test]
+    fn gates_opcodes_by_fork() {
+        assert!(!is_enabled(PUSH0, Fork::Paris));
+        assert!(is_enabled(PUSH0, Fork::Shanghai));
+        assert!(!is_enabled(TSTORE, Fork::Shanghai));
+        assert!(is_enabled(MCOPY, Fork::Cancun));
+        assert!(!is_enabled(INVALID, Fork::Cancun));
+        assert!(!is_enabled(0x0c, Fork::Cancun));
+    }
+
+    #[test]
+    fn documents_opcodes() {
+        let add = doc(ADD).unwrap();
+        assert_eq!(add.inputs, "a, b");
+        assert_eq!(add.since, "Frontier");
+
+        assert_eq!(doc(PUSH0).unwrap().since, "Shanghai");
+        assert_eq!(doc(SWAP1).unwrap().inputs, "a, b");
+        assert_eq!(doc(SWAP1 + 1).unwrap().outputs, "b, x1, a");
+        assert_eq!(doc(DUP1 + 1).unwrap().outputs, "a2, a1, a2");
+        assert_eq!(
+            doc(LOG0 + 2).unwrap().inputs,
+            "offset, size, topic1, topic2"
+        );
+        assert!(doc(0x0c).is_none());
+        // Every named opcode is documented.
+        assert!((0..=u8::MAX).all(|op| name(op).is_some() == doc(op).is_some()));
+    }
+}
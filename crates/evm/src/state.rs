@@ -0,0 +1,305 @@
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Account {
+    pub balance: U256,
+    pub nonce: u64,
+    pub code: Bytes,
+    /// Non-zero storage slots, zeroed slots are removed.
+    pub storage: BTreeMap<U256, U256>,
+}
+
+impl Account {
+    /// Empty as defined by EIP-161.
+    pub fn is_empty(&self) -> bool {
+        self.balance.is_zero() && self.nonce == 0 && self.code.is_empty()
+    }
+
+    pub fn code_hash(&self) -> B256 {
+        keccak256(&self.code)
+    }
+}
+
+/// A storage slot whose value differs between two states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageChange {
+    pub address: Address,
+    pub slot: U256,
+    pub before: U256,
+    pub after: U256,
+}
+
+/// A change to the world state, holding what's needed to undo it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    /// The account didn't exist before.
+    Created(Address),
+    Removed(Address, Account),
+    Balance(Address, U256),
+    Nonce(Address, u64),
+    Code(Address, Bytes),
+    Storage(Address, U256, U256),
+}
+
+/// Position in the journal that changes can be reverted to.
+#[derive(Debug)]
+pub(crate) struct Checkpoint(usize);
+
+/// In-memory world state.
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    accounts: BTreeMap<Address, Account>,
+    /// Changes made since the outermost open checkpoint, only recorded while one is open.
+    journal: Vec<Change>,
+    checkpoints: usize,
+}
+
+impl PartialEq for State {
+    fn eq(&self, other: &Self) -> bool {
+        self.accounts == other.accounts
+    }
+}
+
+impl Eq for State {}
+
+impl State {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn accounts(&self) -> &BTreeMap<Address, Account> {
+        &self.accounts
+    }
+
+    pub fn account(&self, address: Address) -> Option<&Account> {
+        self.accounts.get(&address)
+    }
+
+    /// The account at the address, created if it doesn't exist yet.
+    fn account_mut(&mut self, address: Address) -> &mut Account {
+        if !self.accounts.contains_key(&address) {
+            self.record(Change::Created(address));
+        }
+        self.accounts.entry(address).or_default()
+    }
+
+    pub fn exists(&self, address: Address) -> bool {
+        self.accounts.contains_key(&address)
+    }
+
+    pub fn remove(&mut self, address: Address) -> Option<Account> {
+        let account = self.accounts.remove(&address)?;
+        if self.checkpoints > 0 {
+            self.journal.push(Change::Removed(address, account.clone()));
+        }
+        Some(account)
+    }
+
+    pub fn balance(&self, address: Address) -> U256 {
+        self.account(address)
+            .map_or(U256::ZERO, |account| account.balance)
+    }
+
+    pub fn set_balance(&mut self, address: Address, balance: U256) {
+        let previous = std::mem::replace(&mut self.account_mut(address).balance, balance);
+        self.record(Change::Balance(address, previous));
+    }
+
+    pub fn nonce(&self, address: Address) -> u64 {
+        self.account(address).map_or(0, |account| account.nonce)
+    }
+
+    pub fn set_nonce(&mut self, address: Address, nonce: u64) {
+        let previous = std::mem::replace(&mut self.account_mut(address).nonce, nonce);
+        self.record(Change::Nonce(address, previous));
+    }
+
+    pub fn code(&self, address: Address) -> Bytes {
+        self.account(address)
+            .map(|account| account.code.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_code(&mut self, address: Address, code: impl Into<Bytes>) {
+        let previous = std::mem::replace(&mut self.account_mut(address).code, code.into());
+        self.record(Change::Code(address, previous));
+    }
+
+    pub fn storage(&self, address: Address, slot: U256) -> U256 {
+        self.account(address)
+            .and_then(|account| account.storage.get(&slot).copied())
+            .unwrap_or_default()
+    }
+
+    pub fn set_storage(&mut self, address: Address, slot: U256, value: U256) {
+        let previous = write_slot(&mut self.account_mut(address).storage, slot, value);
+        self.record(Change::Storage(address, slot, previous));
+    }
+
+    /// Moves `value` wei between the accounts, `false` if the sender's balance is insufficient.
+    pub fn transfer(&mut self, from: Address, to: Address, value: U256) -> bool {
+        let Some(balance) = self.balance(from).checked_sub(value) else {
+            return false;
+        };
+        self.set_balance(from, balance);
+        let to_balance = self.balance(to);
+        self.set_balance(to, to_balance.saturating_add(value));
+        true
+    }
+
+    fn record(&mut self, change: Change) {
+        if self.checkpoints > 0 {
+            self.journal.push(change);
+        }
+    }
+
+    /// Starts recording changes until the checkpoint is committed or reverted, checkpoints
+    /// being closed in the reverse order they were opened.
+    pub(crate) fn checkpoint(&mut self) -> Checkpoint {
+        self.checkpoints += 1;
+        Checkpoint(self.journal.len())
+    }
+
+    /// Keeps the changes made since the checkpoint, they're still undone if an outer
+    /// checkpoint is reverted.
+    pub(crate) fn commit(&mut self, _checkpoint: Checkpoint) {
+        self.checkpoints -= 1;
+        if self.checkpoints == 0 {
+            self.journal.clear();
+        }
+    }
+
+    /// Undoes the changes made since the checkpoint.
+    pub(crate) fn revert(&mut self, Checkpoint(len): Checkpoint) {
+        self.checkpoints -= 1;
+        for change in self.journal.drain(len..).rev() {
+            match change {
+                Change::Created(address) => {
+                    self.accounts.remove(&address);
+                }
+                Change::Removed(address, account) => {
+                    self.accounts.insert(address, account);
+                }
+                Change::Balance(address, balance) => {
+                    self.accounts.entry(address).or_default().balance = balance;
+                }
+                Change::Nonce(address, nonce) => {
+                    self.accounts.entry(address).or_default().nonce = nonce;
+                }
+                Change::Code(address, code) => {
+                    self.accounts.entry(address).or_default().code = code;
+                }
+                Change::Storage(address, slot, value) => {
+                    write_slot(
+                        &mut self.accounts.entry(address).or_default().storage,
+                        slot,
+                        value,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Storage slots that differ in `after`, ordered by address and slot.
+    pub fn storage_changes(&self, after: &State) -> Vec<StorageChange> {
+        let empty = BTreeMap::new();
+        let addresses: BTreeSet<Address> = self
+            .accounts
+            .keys()
+            .chain(after.accounts.keys())
+            .copied()
+            .collect();
+
+        let mut changes = Vec::new();
+        for address in addresses {
+            let before_storage = self
+                .account(address)
+                .map_or(&empty, |account| &account.storage);
+            let after_storage = after
+                .account(address)
+                .map_or(&empty, |account| &account.storage);
+            let slots: BTreeSet<U256> = before_storage
+                .keys()
+                .chain(after_storage.keys())
+                .copied()
+                .collect();
+            for slot in slots {
+                let before = before_storage.get(&slot).copied().unwrap_or_default();
+                let after = after_storage.get(&slot).copied().unwrap_or_default();
+                if before != after {
+                    changes.push(StorageChange {
+                        address,
+                        slot,
+                        before,
+                        after,
+                    });
+                }
+            }
+        }
+        changes
+    }
+}
+
+/// Writes the slot, removing it if zeroed, and returns its previous value.
+fn write_slot(storage: &mut BTreeMap<U256, U256>, slot: U256, value: U256) -> U256 {
+    let previous = if value.is_zero() {
+        storage.remove(&slot)
+    } else {
+        storage.insert(slot, value)
+    };
+    previous.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_storage() {
+        let address = Address::with_last_byte(1);
+        let mut before = State::new();
+        before.set_storage(address, U256::from(1), U256::from(1));
+        before.set_storage(address, U256::from(2), U256::from(2));
+        let mut after = before.clone();
+        after.set_storage(address, U256::from(1), U256::ZERO);
+        after.set_storage(address, U256::from(3), U256::from(3));
+
+        let changes: Vec<_> = before
+            .storage_changes(&after)
+            .into_iter()
+            .map(|change| (change.slot, change.before, change.after))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (U256::from(1), U256::from(1), U256::ZERO),
+                (U256::from(3), U256::ZERO, U256::from(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn reverts_to_checkpoints() {
+        let address = Address::with_last_byte(1);
+        let mut state = State::new();
+        state.set_balance(address, U256::from(1));
+        let before = state.clone();
+
+        let outer = state.checkpoint();
+        state.set_storage(address, U256::from(1), U256::from(1));
+        let inner = state.checkpoint();
+        state.set_balance(address, U256::from(2));
+        state.set_code(Address::with_last_byte(2), vec![0x00]);
+        state.commit(inner);
+        let inner = state.checkpoint();
+        state.set_nonce(address, 1);
+        state.revert(inner);
+        assert_eq!(state.nonce(address), 0);
+        assert_eq!(state.balance(address), U256::from(2));
+
+        state.revert(outer);
+        assert_eq!(state, before);
+        assert!(state.journal.is_empty());
+    }
+}
@@ -0,0 +1,496 @@
+use chumsky::error::Rich;
+use huff_ast::{parse, Cst, CstNodeKind, CstToken, Spanned, Token, Trivia};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Formatter settings, read from the `[fmt]` section of the project file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FmtConfig {
+    /// Spaces per indentation level.
+    pub indent_width: usize,
+    /// Maximum number of consecutive blank lines that are kept.
+    pub max_blank_lines: usize,
+    /// Whether trailing comments inside a macro body are aligned into a single column.
+    pub align_comments: bool,
+    /// Minimum number of spaces between code and its trailing comment.
+    pub comment_gap: usize,
+}
+
+impl Default for FmtConfig {
+    fn default() -> Self {
+        Self {
+            indent_width: 4,
+            max_blank_lines: 1,
+            align_comments: true,
+            comment_gap: 4,
+        }
+    }
+}
+
+/// Format the given source code string. Only syntactically valid code is formatted, otherwise the
+/// parse errors are returned.
+pub fn format<'src>(
+    src: &'src str,
+    config: &FmtConfig,
+) -> Result<String, Vec<Rich<'src, Token<'src>>>> {
+    parse(src)?;
+    let cst = Cst::parse(src)?;
+
+    let layout = layout(&cst, config);
+    let mut printer = Printer::new(config);
+
+    for (i, token) in cst.tokens.iter().enumerate() {
+        let token_layout = &layout[i];
+        printer.leading(token, token_layout);
+
+        if token_layout.lowercase_hex {
+            printer.push(
+                &format!("0x{}", token.text[2..].to_ascii_lowercase()),
+                token_layout.space_before,
+            );
+        } else {
+            printer.push(token.text, token_layout.space_before);
+        }
+
+        let line_ends = match cst.tokens.get(i + 1) {
+            Some(next) => layout[i + 1].break_before || has_own_line_comment(&next.leading),
+            None => true,
+        };
+        printer.trailing(token, line_ends);
+    }
+    printer.standalone_trivia(&cst.eof_trivia, 0, (0, config.max_blank_lines));
+
+    Ok(printer.finish())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Code,
+    Table,
+}
+
+/// Placement of a single token, derived from its position in the CST.
+#[derive(Debug, Clone, Default)]
+struct TokenLayout {
+    break_before: bool,
+    space_before: bool,
+    indent: usize,
+    /// Indentation of comments on their own line in the token's leading trivia.
+    comment_indent: usize,
+    /// Allowed blank lines (min, max) before the first line break in the leading trivia.
+    first_blank: (usize, usize),
+    /// Allowed blank lines (min, max) before the token if its leading comments already broke the
+    /// line.
+    own_blank: (usize, usize),
+    align_group: Option<usize>,
+    lowercase_hex: bool,
+}
+
+fn layout(cst: &Cst, config: &FmtConfig) -> Vec<TokenLayout> {
+    let max_blank = config.max_blank_lines;
+
+    // Section start -> (is ABI definition, contains block)
+    let mut sections: BTreeMap<usize, (bool, bool)> = BTreeMap::new();
+    let mut openers: BTreeMap<usize, BlockKind> = BTreeMap::new();
+    let mut closers: BTreeSet<usize> = BTreeSet::new();
+    for section in cst.root.children.iter() {
+        let def_kind = cst
+            .section_keyword(section)
+            .and_then(|keyword| cst.tokens.get(keyword + 1))
+            .map_or("", |token| token.text);
+        let block_kind = match def_kind {
+            "table" | "jumptable" => BlockKind::Table,
+            _ => BlockKind::Code,
+        };
+        let mut has_block = false;
+        if section.kind == CstNodeKind::Definition {
+            for group in section.children.iter() {
+                if group.kind == CstNodeKind::Group('{') {
+                    openers.insert(group.tokens.start, block_kind);
+                    closers.insert(group.tokens.end - 1);
+                    has_block = true;
+                }
+            }
+        }
+        sections.insert(
+            section.tokens.start,
+            (matches!(def_kind, "function" | "event" | "error"), has_block),
+        );
+    }
+
+    let mut blocks: Vec<(BlockKind, usize)> = Vec::new();
+    let mut next_group = 0;
+    let mut paren_depth = 0usize;
+    let mut prev_has_block = false;
+    let mut in_abi_def = false;
+
+    cst.tokens
+        .iter()
+        .enumerate()
+        .map(|(i, token)| {
+            let prev = i.checked_sub(1).map(|prev| &cst.tokens[prev]);
+            let after_opener = i > 0 && openers.contains_key(&(i - 1));
+            let mut token_layout = TokenLayout {
+                first_blank: (0, max_blank),
+                own_blank: (0, max_blank),
+                ..Default::default()
+            };
+
+            if let Some(&(is_abi, has_block)) = sections.get(&i) {
+                blocks.clear();
+                paren_depth = 0;
+                let min_blank = if i > 0 && (has_block || prev_has_block) {
+                    1
+                } else {
+                    0
+                };
+                token_layout.break_before = i > 0;
+                token_layout.first_blank = (min_blank.min(max_blank), max_blank);
+                prev_has_block = has_block;
+                in_abi_def = is_abi;
+            } else if matches!(token.token, Token::Keyword(_) | Token::Punct('#')) {
+                // Each decorator as well as the definition following them start a line.
+                token_layout.break_before = true;
+                token_layout.first_blank = (0, 0);
+                token_layout.own_blank = (0, 0);
+            } else if closers.contains(&i) {
+                blocks.pop();
+                // Keep empty bodies on one line: `{}`.
+                token_layout.break_before = !after_opener || !token.leading.is_empty();
+                token_layout.first_blank = (0, 0);
+                token_layout.own_blank = (0, 0);
+            } else if after_opener {
+                token_layout.break_before = true;
+                token_layout.first_blank = (0, 0);
+            } else {
+                token_layout.break_before = match blocks.last() {
+                    Some((BlockKind::Table, _)) => true,
+                    Some((BlockKind::Code, _)) => token.leading_newlines() > 0,
+                    None => false,
+                };
+            }
+
+            token_layout.indent = blocks.len();
+            token_layout.comment_indent = if closers.contains(&i) {
+                blocks.len() + 1
+            } else {
+                blocks.len()
+            };
+            token_layout.align_group = blocks.last().map(|(_, group)| *group);
+            token_layout.space_before = !token_layout.break_before
+                && prev.is_some_and(|prev| {
+                    space_between(&prev.token, &token.token, paren_depth > 0, in_abi_def)
+                });
+            token_layout.lowercase_hex = matches!(blocks.last(), Some((BlockKind::Table, _)))
+                && matches!(token.token, Token::Hex(_));
+
+            if let Some(&kind) = openers.get(&i) {
+                blocks.push((kind, next_group));
+                next_group += 1;
+            }
+            match token.token {
+                Token::Punct('(') => paren_depth += 1,
+                Token::Punct(')') => paren_depth = paren_depth.saturating_sub(1),
+                _ => {}
+            }
+
+            token_layout
+        })
+        .collect()
+}
+
+fn space_between(prev: &Token, next: &Token, in_parens: bool, in_abi_def: bool) -> bool {
+    match (prev, next) {
+        (Token::Punct('(' | '[' | '<' | '#'), _) | (Token::Punct('{'), Token::Punct('}')) => false,
+        (_, Token::Punct(')' | ']' | '>' | ',' | ':')) => false,
+        (Token::Ident("returns"), Token::Punct('(')) => in_abi_def,
+        (Token::Ident(_), Token::Punct('(')) => false,
+        // Array types, e.g. `uint256[2][]`
+        (Token::Ident(_) | Token::Punct(')' | ']'), Token::Punct('[')) => !in_parens,
+        _ => true,
+    }
+}
+
+/// Whether the trivia contains a comment that starts on its own line.
+fn has_own_line_comment(trivia: &[Spanned<Trivia>]) -> bool {
+    let mut seen_newline = false;
+    for (trivia, _) in trivia {
+        match trivia {
+            Trivia::Whitespace(ws) => seen_newline |= ws.contains('\n'),
+            Trivia::Comment(_) if seen_newline => return true,
+            Trivia::Comment(_) => {}
+        }
+    }
+    false
+}
+
+#[derive(Debug, Default)]
+struct Line {
+    indent: usize,
+    code: String,
+    comment: Option<String>,
+    align_group: Option<usize>,
+}
+
+struct Printer<'a> {
+    config: &'a FmtConfig,
+    /// Printed lines, `None` being a blank line.
+    lines: Vec<Option<Line>>,
+    current: Line,
+    /// Set if the current line ends in a line comment.
+    must_break: bool,
+}
+
+impl<'a> Printer<'a> {
+    fn new(config: &'a FmtConfig) -> Self {
+        Self {
+            config,
+            lines: Vec::new(),
+            current: Line::default(),
+            must_break: false,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.current.code.is_empty() && self.current.comment.is_none()
+    }
+
+    fn flush(&mut self) {
+        if !self.current.code.is_empty() || self.current.comment.is_some() {
+            self.lines.push(Some(std::mem::take(&mut self.current)));
+        }
+    }
+
+    fn break_line(&mut self, blank: usize, indent: usize, align_group: Option<usize>) {
+        self.flush();
+        if !self.lines.is_empty() {
+            self.lines.extend((0..blank).map(|_| None));
+        }
+        self.current = Line {
+            indent,
+            align_group,
+            ..Default::default()
+        };
+        self.must_break = false;
+    }
+
+    fn push(&mut self, text: &str, space: bool) {
+        if space && !self.current.code.is_empty() {
+            self.current.code.push(' ');
+        }
+        self.current.code.push_str(text);
+    }
+
+    /// Prints comments in the trivia, comments that were on their own line stay on their own line.
+    /// Returns the newlines following the last comment and whether a line was broken.
+    fn standalone_trivia(
+        &mut self,
+        trivia: &[Spanned<Trivia>],
+        indent: usize,
+        first_blank: (usize, usize),
+    ) -> (usize, bool) {
+        let mut newlines = 0;
+        let mut broke = false;
+        for (trivia, _) in trivia {
+            match trivia {
+                Trivia::Whitespace(ws) => newlines += ws.matches('\n').count(),
+                Trivia::Comment(comment) => {
+                    if newlines > 0 || self.is_empty() {
+                        let (min, max) = if broke {
+                            (0, self.config.max_blank_lines)
+                        } else {
+                            first_blank
+                        };
+                        self.break_line(newlines.saturating_sub(1).clamp(min, max), indent, None);
+                        broke = true;
+                    }
+                    self.push(comment, true);
+                    self.must_break |= comment.starts_with("//");
+                    newlines = 0;
+                }
+            }
+        }
+        (newlines, broke)
+    }
+
+    fn leading(&mut self, token: &CstToken, layout: &TokenLayout) {
+        let (newlines, broke) =
+            self.standalone_trivia(&token.leading, layout.comment_indent, layout.first_blank);
+
+        if layout.break_before || self.must_break || (broke && newlines > 0) {
+            let (min, max) = if broke {
+                layout.own_blank
+            } else {
+                layout.first_blank
+            };
+            self.break_line(
+                newlines.saturating_sub(1).clamp(min, max),
+                layout.indent,
+                layout.align_group,
+            );
+        }
+    }
+
+    fn trailing(&mut self, token: &CstToken, line_ends: bool) {
+        let comments: Vec<&str> = token
+            .trailing_comments()
+            .map(|(comment, _)| comment.text())
+            .collect();
+        if comments.is_empty() {
+            return;
+        }
+
+        let has_line_comment = comments.iter().any(|comment| comment.starts_with("//"));
+        if line_ends || has_line_comment {
+            let comments = comments.join(" ");
+            self.current.comment = Some(match self.current.comment.take() {
+                Some(previous) => format!("{} {}", previous, comments),
+                None => comments,
+            });
+            self.must_break |= has_line_comment;
+        } else {
+            comments.into_iter().for_each(|comment| self.push(comment, true));
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.flush();
+        while let Some(None) = self.lines.last() {
+            self.lines.pop();
+        }
+
+        let indent_width = self.config.indent_width;
+        let code_end = |line: &Line| line.indent * indent_width + line.code.chars().count();
+
+        let mut columns: BTreeMap<usize, usize> = BTreeMap::new();
+        if self.config.align_comments {
+            for line in self.lines.iter().flatten() {
+                if let (Some(group), Some(_)) = (line.align_group, &line.comment) {
+                    let column = columns.entry(group).or_default();
+                    *column = (*column).max(code_end(line) + self.config.comment_gap);
+                }
+            }
+        }
+
+        let mut out = String::new();
+        for line in self.lines.iter() {
+            let Some(line) = line else {
+                out.push('\n');
+                continue;
+            };
+            let mut text = " ".repeat(line.indent * indent_width);
+            text.push_str(&line.code);
+            if let Some(comment) = &line.comment {
+                if !line.code.is_empty() {
+                    let len = code_end(line);
+                    let column = line
+                        .align_group
+                        .and_then(|group| columns.get(&group).copied())
+                        .unwrap_or(len + self.config.comment_gap);
+                    text.push_str(&" ".repeat(column.saturating_sub(len).max(1)));
+                }
+                text.push_str(comment);
+            }
+            out.push_str(text.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(src: &str) -> String {
+        format(src, &FmtConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn formats_macro_body() {
+        let src = "#define constant   X=0x01\n\
+                   #define macro MAIN( )=takes( 0 )returns( 0 ){\n  [X]   sload // [x]\n      \
+                   dup1 0x0 mstore   // [x]\n\n\n\n  done:\n    stop\n}\n\
+                   #define table T {0xAABB 0xcc}\n";
+
+        assert_eq!(
+            fmt(src),
+            "#define constant X = 0x01\n\
+             \n\
+             #define macro MAIN() = takes(0) returns(0) {\n    \
+                 [X] sload          // [x]\n    \
+                 dup1 0x0 mstore    // [x]\n\
+             \n    \
+                 done:\n    \
+                 stop\n\
+             }\n\
+             \n\
+             #define table T {\n    \
+                 0xaabb\n    \
+                 0xcc\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn formats_abi_definitions() {
+        let src = "#define function  transfer( address to,uint256  amount ) nonpayable \
+                   returns(bool)\n#define event Transfer(address from,address to, uint256 value)";
+
+        assert_eq!(
+            fmt(src),
+            "#define function transfer(address to, uint256 amount) nonpayable returns (bool)\n\
+             #define event Transfer(address from, address to, uint256 value)\n"
+        );
+    }
+
+    #[test]
+    fn formats_decorators() {
+        let src = "#define macro A() = {}\n#[ calldata( \"0x01\" ),value(1)] #[storage([S] ,0x2)]\n\n\
+                   #define test T() = {\n    A()\n}";
+
+        assert_eq!(
+            fmt(src),
+            "#define macro A() = {}\n\
+             \n\
+             #[calldata(\"0x01\"), value(1)]\n\
+             #[storage([S], 0x2)]\n\
+             #define test T() = {\n    \
+                 A()\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn preserves_comments() {
+        let src = "// header\n\n\n#define macro A() = {\n    // inside\n    stop /* inline */ stop\n    \
+                   /* end */\n}\n#define macro B() = {}";
+
+        assert_eq!(
+            fmt(src),
+            "// header\n\
+             \n\
+             #define macro A() = {\n    \
+                 // inside\n    \
+                 stop /* inline */ stop\n    \
+                 /* end */\n\
+             }\n\
+             \n\
+             #define macro B() = {}\n"
+        );
+    }
+
+    #[test]
+    fn is_idempotent() {
+        for src in [
+            include_str!("../../../examples/Ownable.huff"),
+            include_str!("../../../examples/SimpleMacros.huff"),
+            include_str!("../../../examples/features/CodeTable.huff"),
+            include_str!("../../../examples/features/FuncSigUse.huff"),
+        ] {
+            let formatted = fmt(src);
+            assert_eq!(fmt(&formatted), formatted);
+        }
+    }
+}
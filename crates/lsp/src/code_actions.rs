@@ -0,0 +1,327 @@
+use crate::{diagnostics::analysis_errors, document::Document, index::SymbolKind};
+use huff_analysis::errors::AnalysisError;
+use huff_ast::{
+    parse, Cst, IdentifiableNode, Instruction, Invoke, Macro, MacroStatement, Span, Spanned, Token,
+};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Diagnostic, Range, TextEdit, Url,
+    WorkspaceEdit,
+};
+use std::collections::HashMap;
+
+/// Maximum number of "did you mean" suggestions per error.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Quick fixes for the analysis errors overlapping the given range.
+pub fn code_actions(
+    uri: &Url,
+    doc: &Document,
+    range: Range,
+    diagnostics: &[Diagnostic],
+) -> Vec<CodeActionOrCommand> {
+    let Ok(root) = parse(&doc.text) else {
+        return vec![];
+    };
+    let Ok(cst) = Cst::parse(&doc.text) else {
+        return vec![];
+    };
+    let (start, end) = (doc.offset(range.start), doc.offset(range.end));
+
+    let mut actions = Vec::new();
+    for err in analysis_errors(&root) {
+        let Some(span) = err.span() else {
+            continue;
+        };
+        if span.end < start || end < span.start {
+            continue;
+        }
+        let fixes = Fixes {
+            uri,
+            doc,
+            cst: &cst,
+            diagnostic: diagnostics
+                .iter()
+                .find(|diagnostic| diagnostic.range == doc.range(span)),
+        };
+
+        match err {
+            AnalysisError::DefinitionNotFound {
+                scope,
+                def_type,
+                not_found,
+            } => {
+                match def_type {
+                    "macro" => actions.push(fixes.create_macro(scope, not_found)),
+                    "constant" => actions.push(fixes.create_constant(scope, not_found)),
+                    _ => {}
+                }
+                let candidates = root
+                    .definitions()
+                    .filter(|def| accepts(def_type, SymbolKind::of(def)))
+                    .map(|def| def.ident());
+                actions.extend(fixes.did_you_mean(not_found, candidates));
+            }
+            AnalysisError::MacroArgumentCountMismatch { args, target, .. } => {
+                actions.extend(fixes.fix_argument_count(args, target));
+            }
+            AnalysisError::LabelNotFound {
+                scope,
+                invocation_chain,
+                not_found,
+            } => {
+                let candidates = std::iter::once(scope)
+                    .chain(invocation_chain.iter().map(|(invoker, _)| *invoker))
+                    .flat_map(|m| m.body.iter())
+                    .filter_map(|stmt| match stmt {
+                        MacroStatement::LabelDefinition(label) => Some(label.ident()),
+                        _ => None,
+                    });
+                actions.extend(fixes.did_you_mean(not_found, candidates));
+            }
+            AnalysisError::MacroArgNotFound { scope, not_found } => {
+                let candidates = scope.args.0.iter().map(|arg| arg.ident());
+                actions.extend(fixes.did_you_mean(not_found, candidates));
+            }
+            _ => {}
+        }
+    }
+    actions
+}
+
+/// Whether a definition of the given kind satisfies the analysis' `def_type`.
+fn accepts(def_type: &str, kind: SymbolKind) -> bool {
+    match def_type {
+        "macro" => kind == SymbolKind::Macro,
+        "constant" => kind == SymbolKind::Constant,
+        "table" => kind == SymbolKind::Table,
+        "ABI function" => kind == SymbolKind::Function,
+        "solidity function / error" => matches!(kind, SymbolKind::Function | SymbolKind::Error),
+        "solidity event" => kind == SymbolKind::Event,
+        "solidity error" => kind == SymbolKind::Error,
+        _ => false,
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+struct Fixes<'a> {
+    uri: &'a Url,
+    doc: &'a Document,
+    cst: &'a Cst<'a>,
+    diagnostic: Option<&'a Diagnostic>,
+}
+
+impl Fixes<'_> {
+    fn action(
+        &self,
+        title: String,
+        edits: Vec<TextEdit>,
+        is_preferred: bool,
+    ) -> CodeActionOrCommand {
+        CodeActionOrCommand::CodeAction(CodeAction {
+            title,
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: self.diagnostic.map(|diagnostic| vec![diagnostic.clone()]),
+            edit: Some(WorkspaceEdit {
+                changes: Some(HashMap::from([(self.uri.clone(), edits)])),
+                ..Default::default()
+            }),
+            is_preferred: Some(is_preferred),
+            ..Default::default()
+        })
+    }
+
+    /// Inserts the text on its own lines right before the definition of the macro.
+    fn insert_before(&self, scope: &Macro, text: String) -> TextEdit {
+        let start = self.cst.node_span(self.cst.node_for(scope)).start;
+        let position = self.doc.position(start);
+        TextEdit::new(Range::new(position, position), text)
+    }
+
+    fn create_macro(&self, scope: &Macro, not_found: &Spanned<&str>) -> CodeActionOrCommand {
+        // Match the argument count of the invocation, if it is one.
+        let arg_count = scope
+            .body
+            .iter()
+            .find_map(|stmt| match stmt {
+                MacroStatement::Invoke(Invoke::Macro { name, args }) if name == not_found => {
+                    Some(args.0.len())
+                }
+                _ => None,
+            })
+            .unwrap_or(0);
+        let args: Vec<_> = (1..=arg_count).map(|i| format!("arg{}", i)).collect();
+        let text = format!(
+            "#define macro {}({}) = {{\n}}\n\n",
+            not_found.ident(),
+            args.join(", ")
+        );
+        self.action(
+            format!("Create macro `{}`", not_found.ident()),
+            vec![self.insert_before(scope, text)],
+            false,
+        )
+    }
+
+    fn create_constant(&self, scope: &Macro, not_found: &Spanned<&str>) -> CodeActionOrCommand {
+        let text = format!("#define constant {} = 0x00\n\n", not_found.ident());
+        self.action(
+            format!("Create constant `{}`", not_found.ident()),
+            vec![self.insert_before(scope, text)],
+            false,
+        )
+    }
+
+    /// Drops superfluous arguments or appends the target's argument names as placeholders.
+    fn fix_argument_count(
+        &self,
+        args: &Spanned<Box<[Instruction]>>,
+        target: &Macro,
+    ) -> Option<CodeActionOrCommand> {
+        let tokens = &self.cst.tokens[self.cst.token_range(args.1)];
+        let (open, inner) = tokens.split_first()?;
+        let (close, inner) = inner.split_last()?;
+        if open.token != Token::Punct('(') || close.token != Token::Punct(')') {
+            return None;
+        }
+
+        let mut segments: Vec<Span> = Vec::new();
+        let mut depth = 0usize;
+        let mut current_segment: Option<Span> = None;
+        for token in inner {
+            match token.token {
+                Token::Punct(',') if depth == 0 => {
+                    segments.extend(current_segment.take());
+                    continue;
+                }
+                Token::Punct('(' | '[' | '{' | '<') => depth += 1,
+                Token::Punct(')' | ']' | '}' | '>') => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            match current_segment.as_mut() {
+                Some(segment) => *segment = Span::new(segment.start, token.span.end),
+                None => current_segment = Some(token.span),
+            }
+        }
+        segments.extend(current_segment);
+
+        let target_args = &target.args.0;
+        let new_args: Vec<&str> = segments
+            .iter()
+            .map(|span| &self.doc.text[span.into_range()])
+            .chain(
+                target_args
+                    .iter()
+                    .skip(segments.len())
+                    .map(|arg| arg.ident()),
+            )
+            .take(target_args.len())
+            .collect();
+        let range = Range::new(
+            self.doc.position(open.span.end),
+            self.doc.position(close.span.start),
+        );
+        Some(self.action(
+            format!(
+                "Pass {} argument{} to `{}`",
+                target_args.len(),
+                if target_args.len() == 1 { "" } else { "s" },
+                target.ident()
+            ),
+            vec![TextEdit::new(range, new_args.join(", "))],
+            true,
+        ))
+    }
+
+    fn did_you_mean<'src>(
+        &self,
+        not_found: &Spanned<&str>,
+        candidates: impl Iterator<Item = &'src str>,
+    ) -> Vec<CodeActionOrCommand> {
+        let name = not_found.ident();
+        let max_distance = (name.len() / 3).max(1);
+        let mut suggestions: Vec<(usize, &str)> = candidates
+            .map(|candidate| (edit_distance(name, candidate), candidate))
+            .filter(|&(distance, _)| 0 < distance && distance <= max_distance)
+            .collect();
+        suggestions.sort();
+        suggestions.dedup();
+
+        suggestions
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .enumerate()
+            .map(|(i, (_, suggestion))| {
+                let edit = TextEdit::new(self.doc.range(not_found.1), suggestion.to_owned());
+                self.action(
+                    format!("Did you mean `{}`?", suggestion),
+                    vec![edit],
+                    i == 0,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(src: &str, needle: &str) -> Vec<String> {
+        let doc = Document::new(src.to_string(), 0);
+        let uri = Url::parse("file:///test.huff").unwrap();
+        let position = doc.position(src.find(needle).unwrap());
+        code_actions(&uri, &doc, Range::new(position, position), &[])
+            .into_iter()
+            .map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => action.title,
+                CodeActionOrCommand::Command(command) => command.title,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn computes_edit_distance() {
+        assert_eq!(edit_distance("OWNER", "OWNER"), 0);
+        assert_eq!(edit_distance("OWNR", "OWNER"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn suggests_fixes_for_missing_definitions() {
+        let src = "#define constant OWNER = 0x0\n#define macro MAIN() = {\n    [OWNR]\n}";
+        assert_eq!(
+            titles(src, "OWNR"),
+            ["Create constant `OWNR`", "Did you mean `OWNER`?"]
+        );
+    }
+
+    #[test]
+    fn fixes_argument_count() {
+        let src =
+            "#define macro A(x, y) = {\n    <x> <y>\n}\n#define macro MAIN() = {\n    A(0x1)\n}";
+        let doc = Document::new(src.to_string(), 0);
+        let uri = Url::parse("file:///test.huff").unwrap();
+        let position = doc.position(src.find("(0x1)").unwrap());
+        let actions = code_actions(&uri, &doc, Range::new(position, position), &[]);
+
+        let [CodeActionOrCommand::CodeAction(action)] = actions.as_slice() else {
+            panic!("expected a single code action, got {:?}", actions);
+        };
+        let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+        assert_eq!(edits[0].new_text, "0x1, y");
+    }
+}
@@ -0,0 +1,273 @@
+use crate::{document::Document, opcodes};
+use huff_ast::{Cst, CstNode, CstNodeKind, Token};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, Documentation, InsertTextFormat, MarkupContent, MarkupKind,
+};
+use std::collections::{BTreeSet, VecDeque};
+
+const BUILTINS: &[(&str, &str)] = &[
+    ("__tablestart", "Offset of a table in the code."),
+    ("__tablesize", "Size of a table in bytes."),
+    ("__codesize", "Size of a macro's code in bytes."),
+    (
+        "__codeoffset",
+        "Offset of a macro's code included via `__codesize`.",
+    ),
+    ("__FUNC_SIG", "4 byte selector of an ABI function."),
+    ("__EVENT_HASH", "Topic of an ABI event."),
+    ("__ERROR", "4 byte selector of an ABI error, left aligned."),
+    (
+        "__argsoffset",
+        "End of the entry point's code, where constructor arguments are appended.",
+    ),
+];
+
+/// A definition as far as it can be recovered from the tokens, works on sources that don't parse.
+#[derive(Debug, Default)]
+struct DefOutline<'src> {
+    kind: &'src str,
+    name: &'src str,
+    args: Vec<&'src str>,
+    labels: Vec<&'src str>,
+    calls: BTreeSet<&'src str>,
+    /// Byte range between the braces of the body, unclosed bodies extend to the next section.
+    body: Option<(usize, usize)>,
+}
+
+fn ident<'src>(cst: &Cst<'src>, idx: usize) -> Option<&'src str> {
+    match cst.tokens.get(idx)?.token {
+        Token::Ident(ident) => Some(ident),
+        _ => None,
+    }
+}
+
+fn group(node: &CstNode, delim: char) -> Option<&CstNode> {
+    node.children
+        .iter()
+        .find(|child| child.kind == CstNodeKind::Group(delim))
+}
+
+fn outline<'src>(cst: &Cst<'src>) -> Vec<DefOutline<'src>> {
+    let is_punct = |idx: usize, c: char| {
+        cst.tokens
+            .get(idx)
+            .is_some_and(|tok| tok.token == Token::Punct(c))
+    };
+
+    let mut defs = Vec::new();
+    for node in cst.root.children.iter() {
+        if node.kind != CstNodeKind::Definition {
+            continue;
+        }
+        let Some(start) = cst.section_keyword(node) else {
+            continue;
+        };
+        let (Some(kind), Some(name)) = (ident(cst, start + 1), ident(cst, start + 2)) else {
+            continue;
+        };
+        let mut def = DefOutline {
+            kind,
+            name,
+            ..Default::default()
+        };
+
+        if matches!(kind, "macro" | "test" | "fuzz") {
+            // A fuzz test's parentheses hold ABI types, not macro arguments.
+            if let Some(args) = group(node, '(').filter(|_| kind != "fuzz") {
+                def.args = args
+                    .tokens
+                    .clone()
+                    .filter_map(|idx| ident(cst, idx))
+                    .collect();
+            }
+            if let Some(body) = group(node, '{') {
+                for idx in body.tokens.clone() {
+                    let Some(name) = ident(cst, idx) else {
+                        continue;
+                    };
+                    if is_punct(idx + 1, ':') {
+                        def.labels.push(name);
+                    } else if is_punct(idx + 1, '(') && !name.starts_with("__") {
+                        def.calls.insert(name);
+                    }
+                }
+                let body_start = cst.tokens[body.tokens.start].span.end;
+                let body_end = if is_punct(body.tokens.end - 1, '}') {
+                    cst.tokens[body.tokens.end - 1].span.start
+                } else {
+                    cst.tokens
+                        .get(body.tokens.end)
+                        .map_or(usize::MAX, |tok| tok.span.start)
+                };
+                def.body = Some((body_start, body_end));
+            }
+        }
+        defs.push(def);
+    }
+    defs
+}
+
+/// Labels visible in the macro, being its own and those of the macros invoking it.
+fn visible_labels<'src>(defs: &[DefOutline<'src>], scope: &'src str) -> BTreeSet<&'src str> {
+    let mut labels = BTreeSet::new();
+    let mut visited = BTreeSet::from([scope]);
+    let mut queue = VecDeque::from([scope]);
+    while let Some(current) = queue.pop_front() {
+        for def in defs.iter().filter(|def| def.kind == "macro") {
+            if def.name == current {
+                labels.extend(def.labels.iter().copied());
+            }
+            if def.calls.contains(current) && visited.insert(def.name) {
+                queue.push_back(def.name);
+            }
+        }
+    }
+    labels
+}
+
+fn item(label: &str, kind: CompletionItemKind, detail: Option<String>) -> CompletionItem {
+    CompletionItem {
+        label: label.to_owned(),
+        kind: Some(kind),
+        detail,
+        ..Default::default()
+    }
+}
+
+fn markdown(value: String) -> Option<Documentation> {
+    Some(Documentation::MarkupContent(MarkupContent {
+        kind: MarkupKind::Markdown,
+        value,
+    }))
+}
+
+/// Completions at the given offset, only macro bodies are completed. Directly after `<` the
+/// macro's arguments are suggested, after `[` the constants.
+pub fn complete(doc: &Document, offset: usize) -> Vec<CompletionItem> {
+    let Ok(cst) = Cst::parse(&doc.text) else {
+        return vec![];
+    };
+    let defs = outline(&cst);
+    let Some(scope) = defs.iter().find(|def| {
+        def.body
+            .is_some_and(|(start, end)| start <= offset && offset <= end)
+    }) else {
+        return vec![];
+    };
+
+    let before = &doc.text[..offset];
+    let prefix_start = before
+        .trim_end_matches(|c: char| c.is_ascii_alphanumeric() || c == '_')
+        .len();
+    let trigger = doc.text[..prefix_start].chars().last();
+
+    match trigger {
+        Some('<') => scope
+            .args
+            .iter()
+            .map(|arg| item(arg, CompletionItemKind::VARIABLE, None))
+            .collect(),
+        Some('[') => defs
+            .iter()
+            .filter(|def| def.kind == "constant")
+            .map(|def| item(def.name, CompletionItemKind::CONSTANT, None))
+            .collect(),
+        _ => {
+            let mut items: Vec<_> = opcodes::all()
+                .map(|(mnemonic, op)| CompletionItem {
+                    documentation: opcodes::markdown(&mnemonic, op).and_then(markdown),
+                    ..item(
+                        &mnemonic,
+                        CompletionItemKind::KEYWORD,
+                        opcodes::signature(op),
+                    )
+                })
+                .collect();
+            items.extend(
+                visible_labels(&defs, scope.name)
+                    .into_iter()
+                    .map(|label| item(label, CompletionItemKind::REFERENCE, None)),
+            );
+            items.extend(defs.iter().filter(|def| def.kind == "macro").map(|def| {
+                let args: Vec<_> = def
+                    .args
+                    .iter()
+                    .enumerate()
+                    .map(|(i, arg)| format!("${{{}:{}}}", i + 1, arg))
+                    .collect();
+                CompletionItem {
+                    insert_text: Some(format!("{}({})", def.name, args.join(", "))),
+                    insert_text_format: Some(InsertTextFormat::SNIPPET),
+                    ..item(
+                        def.name,
+                        CompletionItemKind::FUNCTION,
+                        Some(format!("macro {}({})", def.name, def.args.join(", "))),
+                    )
+                }
+            }));
+            items.extend(
+                BUILTINS
+                    .iter()
+                    .map(|(builtin, description)| CompletionItem {
+                        insert_text: Some(format!("{}($1)", builtin)),
+                        insert_text_format: Some(InsertTextFormat::SNIPPET),
+                        documentation: markdown(description.to_string()),
+                        ..item(builtin, CompletionItemKind::FUNCTION, None)
+                    }),
+            );
+            items
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: &str = "#define constant OWNER = FREE_STORAGE_POINTER()
+#define macro INNER(dest) = {
+    <
+}
+#define macro MAIN() = {
+    start:
+    INNER(start)
+    [
+}";
+
+    fn labels_at(needle: &str) -> Vec<String> {
+        let doc = Document::new(SRC.to_string(), 0);
+        let offset = SRC.find(needle).unwrap() + needle.len();
+        complete(&doc, offset)
+            .into_iter()
+            .map(|item| item.label)
+            .collect()
+    }
+
+    #[test]
+    fn completes_by_context() {
+        assert_eq!(labels_at("    <"), ["dest"]);
+        assert_eq!(labels_at("    ["), ["OWNER"]);
+        assert!(labels_at("OWNER").is_empty());
+    }
+
+    #[test]
+    fn completes_macro_bodies() {
+        let labels = labels_at("start:\n");
+        assert!(labels.iter().any(|label| label == "sstore"));
+        assert!(labels.iter().any(|label| label == "INNER"));
+        assert!(labels.iter().any(|label| label == "__FUNC_SIG"));
+        assert!(labels.iter().any(|label| label == "start"));
+    }
+
+    #[test]
+    fn completes_labels_of_invokers() {
+        let doc = Document::new(SRC.replace("    <\n", "    \n"), 0);
+        let offset = doc.text.find("{\n    \n").unwrap() + 6;
+        let labels: Vec<_> = complete(&doc, offset)
+            .into_iter()
+            .filter(|item| item.kind == Some(CompletionItemKind::REFERENCE))
+            .map(|item| item.label)
+            .collect();
+        assert_eq!(labels, ["start"]);
+    }
+}
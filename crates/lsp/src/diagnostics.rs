@@ -0,0 +1,169 @@
+use crate::document::Document;
+use huff_analysis::{errors::AnalysisError, *};
+use huff_ast::{
+    parse, Definition, IdentifiableNode, Invoke, MacroStatement, Root, RootSection, Span, Test,
+};
+use lsp_types::{Diagnostic, DiagnosticSeverity};
+use std::collections::BTreeSet;
+
+const SOURCE: &str = "huff";
+
+/// `def_type`s of the analysis that refer to functions, events or errors.
+const ABI_DEF_TYPES: [&str; 4] =
+    ["ABI function", "solidity function / error", "solidity event", "solidity error"];
+
+/// Analyzes the document. As there's no known entry point every macro that isn't invoked by
+/// another macro is analyzed as one.
+pub fn analysis_errors<'a>(ast: &'a Root<'a>) -> Vec<AnalysisError<'a, 'a>> {
+    let mut analysis_errors = Vec::new();
+    let mut includes_abi = false;
+    let global_defs = build_ident_map(ast.0.iter().filter_map(|section| match section {
+        RootSection::Include(huff_include) => {
+            analysis_errors.push(AnalysisError::NotYetSupported {
+                intent: "Huff '#include'".to_owned(),
+                span: ((), huff_include.1),
+            });
+            None
+        }
+        RootSection::IncludeAbi(_) => {
+            includes_abi = true;
+            None
+        }
+        RootSection::Definition(def) => Some(def),
+    }));
+    analyze_global_for_dups(&global_defs, |err| analysis_errors.push(err));
+
+    let invoked: BTreeSet<&str> = global_defs
+        .values()
+        .flatten()
+        .filter_map(|def| match def {
+            Definition::Macro(m) | Definition::Test(Test { r#macro: m, .. }) => Some(m.body.iter()),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|stmt| match stmt {
+            MacroStatement::Invoke(Invoke::Macro { name, .. }) => Some(name.ident()),
+            _ => None,
+        })
+        .collect();
+
+    for (name, defs) in global_defs.iter() {
+        let Some(Definition::Macro(m) | Definition::Test(Test { r#macro: m, .. })) = defs.first()
+        else {
+            continue;
+        };
+        if invoked.contains(name) {
+            continue;
+        }
+        let takes_args = !m.args.0.is_empty();
+        analyze_entry_point_with_inclusions(&global_defs, name, |err| {
+            // Labels of macros with arguments are typically passed in or defined by the invoker.
+            let skip = takes_args
+                && matches!(
+                    err,
+                    AnalysisError::EntryPointHasArgs { .. } | AnalysisError::LabelNotFound { .. }
+                );
+            // Included ABIs aren't read, references may be to their definitions.
+            let skip = skip
+                || (includes_abi
+                    && matches!(
+                        err,
+                        AnalysisError::DefinitionNotFound { def_type, .. }
+                            if ABI_DEF_TYPES.contains(&def_type)
+                    ));
+            if !skip && !analysis_errors.contains(&err) {
+                analysis_errors.push(err);
+            }
+        });
+    }
+
+    analysis_errors
+}
+
+/// Parses and analyzes the document.
+pub fn diagnose(doc: &Document) -> Vec<Diagnostic> {
+    let ast = match parse(&doc.text) {
+        Ok(ast) => ast,
+        Err(errs) => {
+            return errs
+                .iter()
+                .map(|err| diagnostic(doc, *err.span(), err.reason().to_string()))
+                .collect()
+        }
+    };
+    let analysis_errors = analysis_errors(&ast);
+
+    let mut diagnostics = Vec::with_capacity(analysis_errors.len());
+    for err in analysis_errors.iter() {
+        match err {
+            // Flag every one of the colliding definitions, not just the last.
+            AnalysisError::DefinitionNameCollision { collided, .. } => {
+                diagnostics.extend(
+                    collided
+                        .iter()
+                        .map(|def| diagnostic(doc, def.span(), err.message())),
+                );
+            }
+            _ => {
+                if let Some(span) = err.span() {
+                    diagnostics.push(diagnostic(doc, span, err.message()));
+                }
+            }
+        }
+    }
+    diagnostics
+}
+
+fn diagnostic(doc: &Document, span: Span, message: String) -> Diagnostic {
+    Diagnostic {
+        range: doc.range(span),
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some(SOURCE.to_owned()),
+        message,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::Position;
+
+    #[test]
+    fn reports_analysis_errors() {
+        let doc = Document::new(
+            "#define macro MAIN() = {\n    A() missing jump\n}\n#define macro A() = {}".to_string(),
+            0,
+        );
+        let diagnostics = diagnose(&doc);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(1, 8));
+        assert!(diagnostics[0].message.contains("missing"));
+    }
+
+    #[test]
+    fn skips_labels_of_macros_with_args() {
+        let doc = Document::new(
+            "#define macro HELPER(x) = {\n    <x> dest jump\n}".to_string(),
+            0,
+        );
+        assert!(diagnose(&doc).is_empty());
+    }
+
+    #[test]
+    fn skips_references_into_included_abis() {
+        let doc = Document::new(
+            "#include_abi \"IERC20.json\"\n#define macro MAIN() = {\n    __FUNC_SIG(transfer)\n}"
+                .to_string(),
+            0,
+        );
+        assert!(diagnose(&doc).is_empty());
+    }
+
+    #[test]
+    fn reports_parse_errors() {
+        let doc = Document::new("#define macro MAIN() = {".to_string(), 0);
+        assert!(!diagnose(&doc).is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+use huff_ast::Span;
+use lsp_types::{Position, Range, TextDocumentContentChangeEvent};
+
+/// An open text document, positions are converted using the LSP default UTF-16 encoding.
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub text: String,
+    pub version: i32,
+    line_starts: Vec<usize>,
+}
+
+impl Document {
+    pub fn new(text: String, version: i32) -> Self {
+        let line_starts = line_starts(&text);
+        Self {
+            text,
+            version,
+            line_starts,
+        }
+    }
+
+    /// Applies an incremental (or full, if no range is given) change to the document.
+    pub fn apply_change(&mut self, change: TextDocumentContentChangeEvent) {
+        match change.range {
+            Some(range) => {
+                let start = self.offset(range.start);
+                let end = self.offset(range.end).max(start);
+                self.text.replace_range(start..end, &change.text);
+            }
+            None => self.text = change.text,
+        }
+        self.line_starts = line_starts(&self.text);
+    }
+
+    /// Byte offset of the position, clamped to the end of its line.
+    pub fn offset(&self, position: Position) -> usize {
+        let line = position.line as usize;
+        let Some(&line_start) = self.line_starts.get(line) else {
+            return self.text.len();
+        };
+        let line_end = self
+            .line_starts
+            .get(line + 1)
+            .copied()
+            .unwrap_or(self.text.len());
+        let line_text = self.text[line_start..line_end].trim_end_matches(['\n', '\r']);
+
+        let mut utf16_offset = 0;
+        for (i, c) in line_text.char_indices() {
+            if utf16_offset >= position.character as usize {
+                return line_start + i;
+            }
+            utf16_offset += c.len_utf16();
+        }
+        line_start + line_text.len()
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let character = self.text[line_start..offset].encode_utf16().count();
+        Position::new(line as u32, character as u32)
+    }
+
+    pub fn range(&self, span: Span) -> Range {
+        Range::new(self.position(span.start), self.position(span.end))
+    }
+}
+
+fn line_starts(text: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_positions() {
+        let doc = Document::new("ab\n\u{1F600}c\n".to_string(), 0);
+
+        assert_eq!(doc.position(0), Position::new(0, 0));
+        assert_eq!(doc.position(3), Position::new(1, 0));
+        // The emoji takes up 4 bytes but 2 UTF-16 code units.
+        assert_eq!(doc.position(7), Position::new(1, 2));
+        assert_eq!(doc.offset(Position::new(1, 2)), 7);
+        assert_eq!(doc.offset(Position::new(0, 10)), 2);
+        assert_eq!(doc.offset(Position::new(5, 0)), doc.text.len());
+    }
+
+    #[test]
+    fn applies_incremental_changes() {
+        let mut doc = Document::new("#define macro A() = {\n    add\n}".to_string(), 0);
+        doc.apply_change(TextDocumentContentChangeEvent {
+            range: Some(Range::new(Position::new(1, 4), Position::new(1, 7))),
+            range_length: None,
+            text: "sub\n    mul".to_string(),
+        });
+
+        assert_eq!(doc.text, "#define macro A() = {\n    sub\n    mul\n}");
+        assert_eq!(doc.position(doc.text.len()), Position::new(3, 1));
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{
+    document::Document,
+    index::{SymbolIndex, SymbolKind},
+    opcodes,
+};
+use huff_analysis::build_ident_map;
+use huff_ast::{
+    compute_event_topic, compute_selector, parse, ConstExpr, Cst, Definition, IdentifiableNode,
+    Instruction, Invoke, MacroStatement, Root, RootSection, SolParam, Span, Spanned, Test, Token,
+};
+use huff_compilation::evaluate_constants;
+use lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind};
+use std::collections::BTreeMap;
+
+pub fn hover(doc: &Document, offset: usize) -> Option<Hover> {
+    let root = parse(&doc.text).ok()?;
+    let index = SymbolIndex::build(&root);
+
+    let (value, span) = match index.at(offset) {
+        Some(occ) => {
+            let def = *index.definitions(occ).first()?;
+            let value = match def.kind {
+                SymbolKind::Label => format!("label `{}` of macro `{}`", def.name, def.scope?),
+                SymbolKind::MacroArg => {
+                    format!("argument `{}` of macro `{}`", def.name, def.scope?)
+                }
+                _ => describe_definition(&root, def.name, def.span)?,
+            };
+            (value, occ.span)
+        }
+        None => opcode_at(&doc.text, &root, offset)?,
+    };
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: Some(doc.range(span)),
+    })
+}
+
+fn types(args: &[Spanned<impl ToString>]) -> String {
+    let args: Vec<_> = args.iter().map(|(arg, _)| arg.to_string()).collect();
+    args.join(",")
+}
+
+fn signature(name: &str, args: &[Spanned<impl ToString>]) -> String {
+    format!("{}({})", name, types(args))
+}
+
+/// Parameters along with their modifiers and names, e.g. `address indexed to, uint256`.
+fn params(args: &[SolParam]) -> String {
+    let args: Vec<_> = args
+        .iter()
+        .map(|arg| {
+            let mut param = arg.ty.0.to_string();
+            if arg.indexed {
+                param.push_str(" indexed");
+            }
+            if let Some((name, _)) = arg.name {
+                param.push(' ');
+                param.push_str(name);
+            }
+            param
+        })
+        .collect();
+    args.join(", ")
+}
+
+fn code_block(code: String) -> String {
+    format!("```huff\n{}\n```", code)
+}
+
+fn describe_definition(root: &Root, name: &str, span: Span) -> Option<String> {
+    let defs = root.definitions();
+    let def = defs.clone().find(|def| def.span() == span)?;
+
+    let description = match def {
+        Definition::Macro(m) => {
+            let args: Vec<_> = m.args.0.iter().map(|arg| arg.ident()).collect();
+            let mut decl = format!("#define macro {}({}) =", name, args.join(", "));
+            if let Some(((takes, _), (returns, _))) = m.takes_returns {
+                decl.push_str(&format!(" takes ({}) returns ({})", takes, returns));
+            }
+            code_block(decl)
+        }
+        Definition::Test(test) => code_block(match &test.fuzz {
+            Some(args) => format!("#define fuzz {} =", signature(name, args)),
+            None => format!("#define test {}() =", name),
+        }),
+        Definition::Constant(constant) => {
+            // Constants are evaluated the same way the compiler does, unique names only.
+            let unique_defs: BTreeMap<_, _> = build_ident_map(defs)
+                .into_iter()
+                .map(|(name, defs)| (name, defs[0]))
+                .collect();
+            let value = evaluate_constants(&unique_defs).get(name).copied()?;
+            match constant.expr.0 {
+                ConstExpr::Value(_) => format!(
+                    "{}\n\nValue: `{:#x}` ({})",
+                    code_block(format!("#define constant {} = {:#x}", name, value)),
+                    value,
+                    value
+                ),
+                ConstExpr::FreeStoragePointer => format!(
+                    "{}\n\nStorage slot: `{}`",
+                    code_block(format!(
+                        "#define constant {} = FREE_STORAGE_POINTER()",
+                        name
+                    )),
+                    value
+                ),
+            }
+        }
+        Definition::Jumptable(table) => code_block(format!(
+            "#define jumptable {} // {} labels",
+            name,
+            table.labels.len()
+        )),
+        Definition::CodeTable { data, .. } => {
+            code_block(format!("#define table {} // {} bytes", name, data.len()))
+        }
+        Definition::SolFunction(func) => format!(
+            "{}\n\nSelector: `{}`",
+            code_block(format!(
+                "function {}({}) {} returns ({})",
+                name,
+                params(&func.args),
+                func.state_mutability.as_str(),
+                params(&func.rets)
+            )),
+            compute_selector(&func.name, &func.args)
+        ),
+        Definition::SolEvent(event) => format!(
+            "{}\n\nTopic: `{}`",
+            code_block(format!(
+                "event {}({}){}",
+                name,
+                params(&event.args),
+                if event.anonymous { " anonymous" } else { "" }
+            )),
+            compute_event_topic(&event.name, &event.args)
+        ),
+        Definition::SolError(error) => format!(
+            "{}\n\nSelector: `{}`",
+            code_block(format!("error {}({})", name, params(&error.args))),
+            compute_selector(&error.name, &error.args)
+        ),
+    };
+    Some(description)
+}
+
+/// Documentation of the opcode under the offset. Explicit pushes (`push1 0x01`) are spanned by their value, the
+/// mnemonic token preceding it is matched as well.
+fn opcode_at(src: &str, root: &Root, offset: usize) -> Option<(String, Span)> {
+    let cst = Cst::parse(src).ok()?;
+    let idx = cst
+        .token_index_at(offset)
+        .or_else(|| cst.token_index_at(offset.checked_sub(1)?))?;
+
+    let mut op_spans = Vec::new();
+    for section in root.0.iter() {
+        let RootSection::Definition(
+            Definition::Macro(m) | Definition::Test(Test { r#macro: m, .. }),
+        ) = section
+        else {
+            continue;
+        };
+        for stmt in m.body.iter() {
+            match stmt {
+                MacroStatement::Instruction(Instruction::Op((_, span))) => op_spans.push(*span),
+                MacroStatement::Invoke(Invoke::Macro { args, .. }) => {
+                    op_spans.extend(args.0.iter().filter_map(|arg| match arg {
+                        Instruction::Op((_, span)) => Some(*span),
+                        _ => None,
+                    }))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let token = &cst.tokens[idx];
+    let is_op = op_spans.contains(&token.span)
+        || cst
+            .tokens
+            .get(idx + 1)
+            .is_some_and(|next| op_spans.contains(&next.span));
+    match token.token {
+        Token::Ident(mnemonic) if is_op => Some((
+            opcodes::markdown(mnemonic, opcodes::lookup(mnemonic)?)?,
+            token.span,
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: &str = "#define function transfer(address,uint256) nonpayable returns (bool)
+#define constant OWNER = FREE_STORAGE_POINTER()
+#define constant BALANCES = FREE_STORAGE_POINTER()
+#define macro MAIN() = {
+    [OWNER] sload
+    push1 0x01
+    __FUNC_SIG(transfer)
+}";
+
+    fn hover_at(needle: &str) -> String {
+        let doc = Document::new(SRC.to_string(), 0);
+        let hover = hover(&doc, SRC.rfind(needle).unwrap()).unwrap();
+        let HoverContents::Markup(content) = hover.contents else {
+            panic!("expected markup");
+        };
+        content.value
+    }
+
+    #[test]
+    fn hovers_opcodes() {
+        assert!(hover_at("sload").contains("Stack: `[key]` → `[value]`"));
+        assert!(hover_at("push1").contains("Places a 1 byte item on the stack."));
+    }
+
+    #[test]
+    fn hovers_definitions() {
+        // Slots are assigned in order of the names.
+        assert!(hover_at("OWNER").contains("Storage slot: `1`"));
+        assert!(hover_at("transfer)").contains("Selector: `0xa9059cbb`"));
+    }
+}
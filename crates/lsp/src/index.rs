@@ -0,0 +1,362 @@
+use huff_ast::{
+    Decorator, Definition, IdentifiableNode, Instruction, Invoke, Macro, MacroStatement, Root,
+    RootSection, Span, Spanned, Test, WordRef,
+};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Macro,
+    Constant,
+    Table,
+    Function,
+    Event,
+    Error,
+    Label,
+    MacroArg,
+}
+
+impl SymbolKind {
+    pub fn of(def: &Definition) -> Self {
+        match def {
+            Definition::Macro(_) | Definition::Test(_) => Self::Macro,
+            Definition::Constant(_) => Self::Constant,
+            Definition::Jumptable(_) | Definition::CodeTable { .. } => Self::Table,
+            Definition::SolFunction(_) => Self::Function,
+            Definition::SolEvent(_) => Self::Event,
+            Definition::SolError(_) => Self::Error,
+        }
+    }
+
+    /// Whether a reference of this kind can resolve to a definition of kind `def`.
+    fn accepts(self, def: SymbolKind) -> bool {
+        // `__ERROR` also accepts functions, mirroring the analysis.
+        self == def || (self == Self::Error && def == Self::Function)
+    }
+
+    fn is_scoped(self) -> bool {
+        matches!(self, Self::Label | Self::MacroArg)
+    }
+}
+
+/// A definition of, or reference to a symbol in a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence<'src> {
+    pub kind: SymbolKind,
+    pub name: &'src str,
+    pub span: Span,
+    /// The macro the occurrence belongs to, only set for labels and macro arguments.
+    pub scope: Option<&'src str>,
+    pub is_definition: bool,
+}
+
+/// All the symbol occurrences of a single document, in source order.
+#[derive(Debug, Default)]
+pub struct SymbolIndex<'src> {
+    pub occurrences: Vec<Occurrence<'src>>,
+    /// Macro name -> names of the macros it invokes.
+    calls: BTreeMap<&'src str, BTreeSet<&'src str>>,
+}
+
+impl<'src> SymbolIndex<'src> {
+    pub fn build(root: &Root<'src>) -> Self {
+        let mut index = Self::default();
+        for section in root.0.iter() {
+            let RootSection::Definition(def) = section else {
+                continue;
+            };
+            index.push(SymbolKind::of(def), def.spanned(), None, true);
+            match def {
+                Definition::Macro(m) => index.add_macro(m),
+                Definition::Test(test) => {
+                    index.add_decorators(test);
+                    index.add_macro(&test.r#macro);
+                }
+                _ => {}
+            }
+        }
+        index.occurrences.sort_by_key(|occ| occ.span.start);
+        index
+    }
+
+    fn push(
+        &mut self,
+        kind: SymbolKind,
+        (name, span): &Spanned<&'src str>,
+        scope: Option<&'src str>,
+        is_definition: bool,
+    ) {
+        self.occurrences.push(Occurrence {
+            kind,
+            name,
+            span: *span,
+            scope: if kind.is_scoped() { scope } else { None },
+            is_definition,
+        });
+    }
+
+    fn add_macro(&mut self, m: &Macro<'src>) {
+        let scope = Some(m.ident());
+        self.calls.entry(m.ident()).or_default();
+        for arg in m.args.0.iter() {
+            self.push(SymbolKind::MacroArg, arg, scope, true);
+        }
+        for stmt in m.body.iter() {
+            match stmt {
+                MacroStatement::LabelDefinition(label) => {
+                    self.push(SymbolKind::Label, label, scope, true)
+                }
+                MacroStatement::Instruction(instruction) => {
+                    self.add_instruction(instruction, scope)
+                }
+                MacroStatement::Invoke(invoke) => self.add_invoke(invoke, m.ident()),
+            }
+        }
+    }
+
+    fn add_decorators(&mut self, test: &Test<'src>) {
+        let scope = Some(test.r#macro.ident());
+        for (decorator, _) in test.decorators.iter() {
+            match decorator {
+                Decorator::Storage { slot, value } => {
+                    for word in [slot, value] {
+                        if let WordRef::Constant(name) = word {
+                            self.push(SymbolKind::Constant, name, scope, false);
+                        }
+                    }
+                }
+                Decorator::ExpectRevert(Some(name)) => {
+                    self.push(SymbolKind::Error, name, scope, false)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn add_instruction(&mut self, instruction: &Instruction<'src>, scope: Option<&'src str>) {
+        match instruction {
+            Instruction::Op(_) | Instruction::VariablePush(_) => {}
+            Instruction::LabelReference(name) => self.push(SymbolKind::Label, name, scope, false),
+            Instruction::MacroArgReference(name) => {
+                self.push(SymbolKind::MacroArg, name, scope, false)
+            }
+            Instruction::ConstantReference(name) => {
+                self.push(SymbolKind::Constant, name, scope, false)
+            }
+        }
+    }
+
+    fn add_invoke(&mut self, invoke: &Invoke<'src>, scope: &'src str) {
+        let (kind, name) = match invoke {
+            Invoke::Macro { name, args } => {
+                self.calls.entry(scope).or_default().insert(name.ident());
+                for arg in args.0.iter() {
+                    self.add_instruction(arg, Some(scope));
+                }
+                (SymbolKind::Macro, name)
+            }
+            Invoke::BuiltinTableStart(name) | Invoke::BuiltinTableSize(name) => {
+                (SymbolKind::Table, name)
+            }
+            Invoke::BuiltinCodeSize(name) | Invoke::BuiltinCodeOffset(name) => {
+                (SymbolKind::Macro, name)
+            }
+            Invoke::BuiltinFuncSig(name) => (SymbolKind::Function, name),
+            Invoke::BuiltinEventHash(name) => (SymbolKind::Event, name),
+            Invoke::BuiltinError(name) => (SymbolKind::Error, name),
+            // Signature strings aren't tied to a single definition.
+            Invoke::BuiltinFuncSigLiteral(_) | Invoke::BuiltinEventHashLiteral(_) => return,
+            Invoke::BuiltinArgsOffset(_) => return,
+        };
+        self.push(kind, name, Some(scope), false);
+    }
+
+    /// The occurrence under the given byte offset, touching its end counts as well.
+    pub fn at(&self, offset: usize) -> Option<&Occurrence<'src>> {
+        self.occurrences
+            .iter()
+            .find(|occ| occ.span.start <= offset && offset <= occ.span.end)
+    }
+
+    fn defined_in(&self, kind: SymbolKind, name: &str, scope: &str) -> bool {
+        self.occurrences.iter().any(|occ| {
+            occ.is_definition && occ.kind == kind && occ.name == name && occ.scope == Some(scope)
+        })
+    }
+
+    /// Names of the macros invoking `callee`.
+    fn callers(&self, callee: &'src str) -> impl Iterator<Item = &'src str> + '_ {
+        self.calls
+            .iter()
+            .filter(move |(_, callees)| callees.contains(callee))
+            .map(|(caller, _)| *caller)
+    }
+
+    /// Resolves the occurrence to its definition(s). Labels are looked up in their own macro
+    /// first and then in the macros invoking it, the closest ones winning.
+    pub fn definitions(&self, occ: &Occurrence<'src>) -> Vec<&Occurrence<'src>> {
+        let matching = |scope: Option<&str>| -> Vec<&Occurrence<'src>> {
+            self.occurrences
+                .iter()
+                .filter(|def| {
+                    def.is_definition
+                        && occ.kind.accepts(def.kind)
+                        && def.name == occ.name
+                        && def.scope == scope
+                })
+                .collect()
+        };
+
+        match (occ.kind, occ.scope) {
+            (SymbolKind::Label, Some(scope)) => {
+                let mut visited = BTreeSet::from([scope]);
+                let mut queue = VecDeque::from([scope]);
+                while let Some(current) = queue.pop_front() {
+                    let defs = matching(Some(current));
+                    if !defs.is_empty() {
+                        return defs;
+                    }
+                    queue.extend(self.callers(current).filter(|c| visited.insert(*c)));
+                }
+                vec![]
+            }
+            (_, scope) => matching(scope),
+        }
+    }
+
+    /// All occurrences referring to the given definition, including the definition itself.
+    pub fn references(&self, def: &Occurrence<'src>) -> Vec<&Occurrence<'src>> {
+        let Some(scope) = def.scope else {
+            return self
+                .occurrences
+                .iter()
+                .filter(|occ| occ.name == def.name && occ.kind.accepts(def.kind))
+                .filter(|occ| !occ.kind.is_scoped())
+                .collect();
+        };
+        if def.kind == SymbolKind::MacroArg {
+            return self
+                .occurrences
+                .iter()
+                .filter(|occ| occ.kind == def.kind && occ.name == def.name)
+                .filter(|occ| occ.scope == Some(scope))
+                .collect();
+        }
+
+        // Labels are visible to invoked macros unless they define their own label of that name.
+        let mut visible = BTreeSet::from([scope]);
+        let mut queue = VecDeque::from([scope]);
+        while let Some(current) = queue.pop_front() {
+            let callees = self.calls.get(current).into_iter().flatten().copied();
+            for callee in callees {
+                if !self.defined_in(SymbolKind::Label, def.name, callee) && visible.insert(callee) {
+                    queue.push_back(callee);
+                }
+            }
+        }
+        self.occurrences
+            .iter()
+            .filter(|occ| occ.kind == SymbolKind::Label && occ.name == def.name)
+            .filter(|occ| match occ.scope {
+                Some(occ_scope) if occ.is_definition => occ_scope == scope,
+                Some(occ_scope) => visible.contains(occ_scope),
+                None => false,
+            })
+            .collect()
+    }
+
+    /// Label definitions sharing references with the given one, including itself. A reference in
+    /// a macro invoked by several macros defining the label refers to all their definitions.
+    pub fn shared_definitions(&self, def: &Occurrence<'src>) -> Vec<&Occurrence<'src>> {
+        let label_defs: Vec<_> = self
+            .occurrences
+            .iter()
+            .filter(|occ| occ.is_definition && occ.kind == def.kind && occ.name == def.name)
+            .collect();
+        let mut shared: Vec<_> = label_defs
+            .iter()
+            .copied()
+            .filter(|occ| *occ == def)
+            .collect();
+        let mut i = 0;
+        while let Some(current) = shared.get(i) {
+            let refs = self.references(current);
+            for other in label_defs.iter().copied() {
+                let shares = || self.references(other).iter().any(|occ| refs.contains(occ));
+                if !shared.contains(&other) && shares() {
+                    shared.push(other);
+                }
+            }
+            i += 1;
+        }
+        shared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use huff_ast::parse;
+
+    const SRC: &str = "
+#define constant C = 0x1
+#define macro INNER() = {
+    done jump
+}
+#define macro MAIN() = {
+    INNER()
+    [C]
+    done:
+}
+#define macro OTHER(x) = {
+    <x> INNER()
+    done:
+}
+";
+
+    fn find<'a>(index: &'a SymbolIndex, needle: &str, nth: usize) -> &'a Occurrence<'a> {
+        let offset = SRC.match_indices(needle).nth(nth).unwrap().0;
+        index.at(offset).unwrap()
+    }
+
+    #[test]
+    fn resolves_globals() {
+        let root = parse(SRC).unwrap();
+        let index = SymbolIndex::build(&root);
+
+        let c_ref = find(&index, "C]", 0);
+        assert_eq!(c_ref.kind, SymbolKind::Constant);
+        let defs = index.definitions(c_ref);
+        assert_eq!(defs.len(), 1);
+        assert!(defs[0].is_definition);
+        assert_eq!(index.references(defs[0]).len(), 2);
+    }
+
+    #[test]
+    fn resolves_labels_through_invokers() {
+        let root = parse(SRC).unwrap();
+        let index = SymbolIndex::build(&root);
+
+        let label_ref = find(&index, "done jump", 0);
+        let defs = index.definitions(label_ref);
+        assert_eq!(defs.len(), 1);
+        assert_eq!(defs[0].scope, Some("MAIN"));
+
+        // The reference in INNER is visible from both MAIN and OTHER.
+        let other_def = find(&index, "done:", 1);
+        assert_eq!(other_def.scope, Some("OTHER"));
+        assert_eq!(index.references(other_def).len(), 2);
+    }
+
+    #[test]
+    fn scopes_macro_args() {
+        let root = parse(SRC).unwrap();
+        let index = SymbolIndex::build(&root);
+
+        let arg_ref = find(&index, "x>", 0);
+        assert_eq!(arg_ref.kind, SymbolKind::MacroArg);
+        let defs = index.definitions(arg_ref);
+        assert_eq!(defs.len(), 1);
+        assert_eq!(index.references(defs[0]).len(), 2);
+    }
+}
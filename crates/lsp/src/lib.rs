@@ -0,0 +1,347 @@
+mod code_actions;
+mod completion;
+mod diagnostics;
+mod document;
+mod hover;
+mod index;
+mod opcodes;
+mod rename;
+mod symbols;
+
+use crate::{document::Document, index::SymbolIndex};
+use huff_ast::parse;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as LspNotification, PublishDiagnostics,
+    },
+    request::{
+        CodeActionRequest, Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest,
+        PrepareRenameRequest, References, Rename, Request as LspRequest, WorkspaceSymbolRequest,
+    },
+    CodeActionKind, CodeActionOptions, CodeActionParams, CodeActionProviderCapability,
+    CodeActionResponse, CompletionOptions, CompletionParams, CompletionResponse,
+    DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams, GotoDefinitionResponse,
+    Hover, HoverParams, HoverProviderCapability, InitializeParams, Location, OneOf,
+    PrepareRenameResponse, PublishDiagnosticsParams, ReferenceParams, RenameOptions, RenameParams,
+    ServerCapabilities, TextDocumentPositionParams, TextDocumentSyncCapability,
+    TextDocumentSyncKind, Url, WorkspaceEdit, WorkspaceSymbolParams, WorkspaceSymbolResponse,
+};
+use std::{collections::HashMap, path::PathBuf};
+
+/// Runs the language server over stdin/stdout until the client shuts it down.
+pub fn run_stdio() -> Result<(), Box<dyn std::error::Error>> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let params = connection.initialize(serde_json::to_value(capabilities())?)?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+    Server::new(connection, &params).main_loop()?;
+
+    io_threads.join()?;
+    Ok(())
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::INCREMENTAL,
+        )),
+        definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        workspace_symbol_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["<".to_owned(), "[".to_owned()]),
+            ..Default::default()
+        }),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        rename_provider: Some(OneOf::Right(RenameOptions {
+            prepare_provider: Some(true),
+            work_done_progress_options: Default::default(),
+        })),
+        code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+            code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+}
+
+struct Server {
+    connection: Connection,
+    documents: HashMap<Url, Document>,
+    workspace_roots: Vec<PathBuf>,
+}
+
+impl Server {
+    fn new(connection: Connection, params: &InitializeParams) -> Self {
+        #[allow(deprecated)]
+        let root_uris = match &params.workspace_folders {
+            Some(folders) => folders.iter().map(|folder| folder.uri.clone()).collect(),
+            None => params.root_uri.iter().cloned().collect::<Vec<_>>(),
+        };
+        Self {
+            connection,
+            documents: HashMap::new(),
+            workspace_roots: root_uris
+                .iter()
+                .filter_map(|uri| uri.to_file_path().ok())
+                .collect(),
+        }
+    }
+
+    fn main_loop(mut self) -> Result<(), Box<dyn std::error::Error>> {
+        while let Ok(msg) = self.connection.receiver.recv() {
+            match msg {
+                Message::Request(req) => {
+                    if self.connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    let response = self.handle_request(req);
+                    self.connection.sender.send(response.into())?;
+                }
+                Message::Notification(not) => self.handle_notification(not)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&self, req: Request) -> Response {
+        match req.method.as_str() {
+            GotoDefinition::METHOD => self.respond::<GotoDefinition>(req, Self::goto_definition),
+            References::METHOD => self.respond::<References>(req, Self::references),
+            DocumentSymbolRequest::METHOD => {
+                self.respond::<DocumentSymbolRequest>(req, Self::document_symbols)
+            }
+            WorkspaceSymbolRequest::METHOD => {
+                self.respond::<WorkspaceSymbolRequest>(req, Self::workspace_symbols)
+            }
+            Completion::METHOD => self.respond::<Completion>(req, Self::completion),
+            HoverRequest::METHOD => self.respond::<HoverRequest>(req, Self::hover),
+            PrepareRenameRequest::METHOD => {
+                self.respond::<PrepareRenameRequest>(req, Self::prepare_rename)
+            }
+            Rename::METHOD => self.respond_or_fail::<Rename>(req, Self::rename),
+            CodeActionRequest::METHOD => self.respond::<CodeActionRequest>(req, Self::code_actions),
+            _ => Response::new_err(
+                req.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("Unhandled request '{}'", req.method),
+            ),
+        }
+    }
+
+    fn respond<R: LspRequest>(
+        &self,
+        req: Request,
+        handler: fn(&Self, R::Params) -> R::Result,
+    ) -> Response {
+        self.respond_or_fail::<R>(req, |server, params| Ok(handler(server, params)))
+    }
+
+    /// Like [`Self::respond`], failing requests with the handler's error message.
+    fn respond_or_fail<R: LspRequest>(
+        &self,
+        req: Request,
+        handler: impl Fn(&Self, R::Params) -> Result<R::Result, String>,
+    ) -> Response {
+        let id = req.id.clone();
+        match req.extract::<R::Params>(R::METHOD) {
+            Ok((id, params)) => match handler(self, params) {
+                Ok(result) => Response::new_ok(id, result),
+                Err(message) => Response::new_err(id, ErrorCode::RequestFailed as i32, message),
+            },
+            Err(err) => Response::new_err(id, ErrorCode::InvalidParams as i32, err.to_string()),
+        }
+    }
+
+    fn handle_notification(&mut self, not: Notification) -> Result<(), Box<dyn std::error::Error>> {
+        let uri = match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = extract_notification::<DidOpenTextDocument>(not)?;
+                let doc = Document::new(params.text_document.text, params.text_document.version);
+                self.documents.insert(params.text_document.uri.clone(), doc);
+                params.text_document.uri
+            }
+            DidChangeTextDocument::METHOD => {
+                let params = extract_notification::<DidChangeTextDocument>(not)?;
+                let Some(doc) = self.documents.get_mut(&params.text_document.uri) else {
+                    return Ok(());
+                };
+                doc.version = params.text_document.version;
+                params
+                    .content_changes
+                    .into_iter()
+                    .for_each(|change| doc.apply_change(change));
+                params.text_document.uri
+            }
+            DidCloseTextDocument::METHOD => {
+                let params = extract_notification::<DidCloseTextDocument>(not)?;
+                self.documents.remove(&params.text_document.uri);
+                // Clear the diagnostics of the closed document.
+                return self.notify::<PublishDiagnostics>(PublishDiagnosticsParams::new(
+                    params.text_document.uri,
+                    vec![],
+                    None,
+                ));
+            }
+            _ => return Ok(()),
+        };
+        self.publish_diagnostics(uri)
+    }
+
+    fn notify<N: LspNotification>(
+        &self,
+        params: N::Params,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let not = Notification::new(N::METHOD.to_owned(), params);
+        self.connection.sender.send(not.into())?;
+        Ok(())
+    }
+
+    fn publish_diagnostics(&self, uri: Url) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(doc) = self.documents.get(&uri) else {
+            return Ok(());
+        };
+        let diagnostics = diagnostics::diagnose(doc);
+        self.notify::<PublishDiagnostics>(PublishDiagnosticsParams::new(
+            uri,
+            diagnostics,
+            Some(doc.version),
+        ))
+    }
+
+    /// Builds the symbol index of the document under the cursor and hands it the cursor's offset.
+    fn with_index<T>(
+        &self,
+        position: &TextDocumentPositionParams,
+        f: impl FnOnce(&Document, &SymbolIndex, usize) -> Option<T>,
+    ) -> Option<T> {
+        let doc = self.documents.get(&position.text_document.uri)?;
+        let root = parse(&doc.text).ok()?;
+        let index = SymbolIndex::build(&root);
+        f(doc, &index, doc.offset(position.position))
+    }
+
+    fn goto_definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let position = params.text_document_position_params;
+        let uri = &position.text_document.uri;
+        self.with_index(&position, |doc, index, offset| {
+            let locations: Vec<_> = index
+                .definitions(index.at(offset)?)
+                .into_iter()
+                .map(|def| Location::new(uri.clone(), doc.range(def.span)))
+                .collect();
+            Some(GotoDefinitionResponse::Array(locations))
+        })
+    }
+
+    fn references(&self, params: ReferenceParams) -> Option<Vec<Location>> {
+        let position = params.text_document_position;
+        let uri = &position.text_document.uri;
+        let include_declaration = params.context.include_declaration;
+        self.with_index(&position, |doc, index, offset| {
+            let occ = index.at(offset)?;
+            let defs = if occ.is_definition {
+                vec![occ]
+            } else {
+                index.definitions(occ)
+            };
+            let mut locations = Vec::new();
+            for def in defs {
+                locations.extend(
+                    index
+                        .references(def)
+                        .into_iter()
+                        .filter(|occ| include_declaration || !occ.is_definition)
+                        .map(|occ| Location::new(uri.clone(), doc.range(occ.span))),
+                );
+            }
+            Some(locations)
+        })
+    }
+
+    fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+        let position = params.text_document_position;
+        let doc = self.documents.get(&position.text_document.uri)?;
+        let items = completion::complete(doc, doc.offset(position.position));
+        Some(CompletionResponse::Array(items))
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let position = params.text_document_position_params;
+        let doc = self.documents.get(&position.text_document.uri)?;
+        hover::hover(doc, doc.offset(position.position))
+    }
+
+    fn prepare_rename(&self, params: TextDocumentPositionParams) -> Option<PrepareRenameResponse> {
+        let doc = self.documents.get(&params.text_document.uri)?;
+        rename::prepare_rename(doc, doc.offset(params.position)).map(PrepareRenameResponse::Range)
+    }
+
+    fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>, String> {
+        let position = params.text_document_position;
+        let uri = position.text_document.uri;
+        let Some(doc) = self.documents.get(&uri) else {
+            return Ok(None);
+        };
+        let edits = rename::rename(doc, doc.offset(position.position), &params.new_name)?;
+        Ok(edits.map(|edits| WorkspaceEdit {
+            changes: Some(HashMap::from([(uri, edits)])),
+            ..Default::default()
+        }))
+    }
+
+    fn code_actions(&self, params: CodeActionParams) -> Option<CodeActionResponse> {
+        let uri = params.text_document.uri;
+        let doc = self.documents.get(&uri)?;
+        Some(code_actions::code_actions(
+            &uri,
+            doc,
+            params.range,
+            &params.context.diagnostics,
+        ))
+    }
+
+    fn document_symbols(&self, params: DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
+        let doc = self.documents.get(&params.text_document.uri)?;
+        symbols::document_symbols(doc).map(DocumentSymbolResponse::Nested)
+    }
+
+    /// Searches the open documents as well as any `.huff` files in the workspace folders.
+    fn workspace_symbols(&self, params: WorkspaceSymbolParams) -> Option<WorkspaceSymbolResponse> {
+        let mut found = Vec::new();
+        for (uri, doc) in self.documents.iter() {
+            found.extend(symbols::workspace_symbols(uri, doc, &params.query));
+        }
+
+        let mut files = Vec::new();
+        for root in self.workspace_roots.iter() {
+            // Unreadable directories only leave out the files found after them.
+            let _ = huff_ast::collect_huff_files(root, &mut files);
+        }
+        for file in files {
+            let Ok(uri) = Url::from_file_path(&file) else {
+                continue;
+            };
+            if self.documents.contains_key(&uri) {
+                continue;
+            }
+            let Ok(text) = std::fs::read_to_string(&file) else {
+                continue;
+            };
+            let doc = Document::new(text, 0);
+            found.extend(symbols::workspace_symbols(&uri, &doc, &params.query));
+        }
+
+        Some(WorkspaceSymbolResponse::Flat(found))
+    }
+}
+
+fn extract_notification<N: LspNotification>(
+    not: Notification,
+) -> Result<N::Params, Box<dyn std::error::Error>> {
+    Ok(not.extract::<N::Params>(N::METHOD)?)
+}
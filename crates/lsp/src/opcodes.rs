@@ -0,0 +1,58 @@
+use huff_evm::{
+    gas,
+    opcode::{self, KECCAK256, PREVRANDAO},
+};
+
+/// Huff's mnemonics of all opcodes, `KECCAK256` being spelled `sha3` and `DIFFICULTY` still
+/// accepted for `PREVRANDAO`.
+pub fn all() -> impl Iterator<Item = (String, u8)> {
+    (0..=u8::MAX)
+        .filter_map(|op| {
+            let mnemonic = match op {
+                KECCAK256 => "sha3".to_owned(),
+                _ => opcode::name(op)?.to_lowercase(),
+            };
+            Some((mnemonic, op))
+        })
+        .chain([("difficulty".to_owned(), PREVRANDAO)])
+}
+
+pub fn lookup(mnemonic: &str) -> Option<u8> {
+    all().find(|(name, _)| name == mnemonic).map(|(_, op)| op)
+}
+
+/// Stack effect shown next to completions.
+pub fn signature(op: u8) -> Option<String> {
+    let doc = opcode::doc(op)?;
+    Some(format!("[{}] → [{}]", doc.inputs, doc.outputs))
+}
+
+/// Markdown documentation shown on hover.
+pub fn markdown(mnemonic: &str, op: u8) -> Option<String> {
+    let doc = opcode::doc(op)?;
+    Some(format!(
+        "```huff\n{}\n```\n{}\n\nStack: `[{}]` → `[{}]`  \nMinimum gas: {}  \nAvailable since: {}",
+        mnemonic,
+        doc.description,
+        doc.inputs,
+        doc.outputs,
+        gas::min(op),
+        doc.since
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_opcodes() {
+        assert_eq!(lookup("add"), Some(opcode::ADD));
+        assert_eq!(lookup("sha3"), Some(KECCAK256));
+        assert_eq!(lookup("difficulty"), Some(PREVRANDAO));
+        assert_eq!(lookup("push33"), None);
+        assert!(markdown("sload", opcode::SLOAD)
+            .unwrap()
+            .contains("Minimum gas: 100"));
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{
+    document::Document,
+    index::{Occurrence, SymbolIndex, SymbolKind},
+    opcodes,
+};
+use huff_ast::{parse, Span};
+use lsp_types::{Range, TextEdit};
+
+pub fn is_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Definitions the occurrence refers to, a definition refers to itself.
+fn targets<'a, 'src>(
+    index: &'a SymbolIndex<'src>,
+    occ: &'a Occurrence<'src>,
+) -> Vec<&'a Occurrence<'src>> {
+    if occ.is_definition {
+        vec![occ]
+    } else {
+        index.definitions(occ)
+    }
+}
+
+/// Definitions to rename along with the targets, the labels sharing references with them.
+fn renamed_definitions<'a, 'src>(
+    index: &'a SymbolIndex<'src>,
+    targets: Vec<&'a Occurrence<'src>>,
+) -> Vec<&'a Occurrence<'src>> {
+    let mut renamed = Vec::with_capacity(targets.len());
+    for target in targets {
+        let shared = match target.kind {
+            SymbolKind::Label => index.shared_definitions(target),
+            _ => vec![target],
+        };
+        for def in shared {
+            if !renamed.contains(&def) {
+                renamed.push(def);
+            }
+        }
+    }
+    renamed
+}
+
+/// Range of the symbol under the cursor, `None` if it can't be renamed.
+pub fn prepare_rename(doc: &Document, offset: usize) -> Option<Range> {
+    let root = parse(&doc.text).ok()?;
+    let index = SymbolIndex::build(&root);
+    let occ = index.at(offset)?;
+    if targets(&index, occ).is_empty() {
+        return None;
+    }
+    Some(doc.range(occ.span))
+}
+
+/// Edits renaming the symbol under the cursor and all its references. Labels are only renamed
+/// within the macros they're visible in, along with the labels of other macros invoking the same
+/// macros referencing them. Fails if the new name is already defined in the same scope, global
+/// definitions sharing a single scope.
+pub fn rename(
+    doc: &Document,
+    offset: usize,
+    new_name: &str,
+) -> Result<Option<Vec<TextEdit>>, String> {
+    let Ok(root) = parse(&doc.text) else {
+        return Ok(None);
+    };
+    let index = SymbolIndex::build(&root);
+    let Some(occ) = index.at(offset) else {
+        return Ok(None);
+    };
+    // Identifiers that are opcodes would no longer parse as labels.
+    if !is_ident(new_name) || (occ.kind == SymbolKind::Label && opcodes::lookup(new_name).is_some())
+    {
+        return Ok(None);
+    }
+
+    let targets = renamed_definitions(&index, targets(&index, occ));
+    for target in targets.iter() {
+        let collides = index.occurrences.iter().any(|def| {
+            def.is_definition
+                && def.name == new_name
+                && def.scope == target.scope
+                && (target.scope.is_none() || def.kind == target.kind)
+                && def.span != target.span
+        });
+        if collides {
+            return Err(format!("'{}' is already defined", new_name));
+        }
+    }
+
+    let mut spans: Vec<Span> = targets
+        .into_iter()
+        .flat_map(|def| index.references(def))
+        .map(|occ| occ.span)
+        .collect();
+    if spans.is_empty() {
+        return Ok(None);
+    }
+    spans.sort_by_key(|span| span.start);
+    spans.dedup();
+
+    Ok(Some(
+        spans
+            .into_iter()
+            .map(|span| TextEdit::new(doc.range(span), new_name.to_owned()))
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(doc: &Document, mut edits: Vec<TextEdit>) -> String {
+        let mut text = doc.text.clone();
+        edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+        for edit in edits {
+            let range = doc.offset(edit.range.start)..doc.offset(edit.range.end);
+            text.replace_range(range, &edit.new_text);
+        }
+        text
+    }
+
+    #[test]
+    fn renames_constants() {
+        let src = "#define constant C = 0x1\n#define macro M() = {\n    [C] [C]\n}";
+        let doc = Document::new(src.to_string(), 0);
+        let edits = rename(&doc, src.find("C]").unwrap(), "VALUE")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            apply(&doc, edits),
+            "#define constant VALUE = 0x1\n#define macro M() = {\n    [VALUE] [VALUE]\n}"
+        );
+    }
+
+    #[test]
+    fn renames_labels_per_scope() {
+        let src =
+            "#define macro A() = {\n    l jump l:\n}\n#define macro B() = {\n    l jump l:\n}";
+        let doc = Document::new(src.to_string(), 0);
+        let edits = rename(&doc, src.find("l:").unwrap(), "done")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            apply(&doc, edits),
+            "#define macro A() = {\n    done jump done:\n}\n#define macro B() = {\n    l jump l:\n}"
+        );
+
+        assert!(rename(&doc, src.find("l:").unwrap(), "add")
+            .unwrap()
+            .is_none());
+        assert!(rename(&doc, src.find("l:").unwrap(), "1abc")
+            .unwrap()
+            .is_none());
+
+        // INNER's reference also resolves to OTHER's label, which must follow.
+        let src = "#define macro INNER() = {\n    l jump\n}\n#define macro MAIN() = {\n    INNER() l:\n}\n#define macro OTHER() = {\n    INNER() l:\n}";
+        let doc = Document::new(src.to_string(), 0);
+        let edits = rename(&doc, src.find("l:").unwrap(), "done")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            apply(&doc, edits),
+            "#define macro INNER() = {\n    done jump\n}\n#define macro MAIN() = {\n    INNER() done:\n}\n#define macro OTHER() = {\n    INNER() done:\n}"
+        );
+    }
+
+    #[test]
+    fn rejects_names_already_defined() {
+        let src = "#define macro A() = {\n    l jump l: m:\n}\n#define macro B() = {\n    n:\n}";
+        let doc = Document::new(src.to_string(), 0);
+
+        assert_eq!(
+            rename(&doc, src.find("B()").unwrap(), "A"),
+            Err("'A' is already defined".to_owned())
+        );
+        assert_eq!(
+            rename(&doc, src.find("l:").unwrap(), "m"),
+            Err("'m' is already defined".to_owned())
+        );
+        // Labels of other macros are out of scope.
+        assert!(rename(&doc, src.find("l:").unwrap(), "n")
+            .unwrap()
+            .is_some());
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{document::Document, index};
+use huff_ast::{parse, Cst, Definition, IdentifiableNode, MacroStatement, Test};
+use lsp_types::{DocumentSymbol, Location, SymbolInformation, SymbolKind, Url};
+
+pub fn lsp_kind(kind: index::SymbolKind) -> SymbolKind {
+    match kind {
+        index::SymbolKind::Macro => SymbolKind::FUNCTION,
+        index::SymbolKind::Constant => SymbolKind::CONSTANT,
+        index::SymbolKind::Table => SymbolKind::ARRAY,
+        index::SymbolKind::Function => SymbolKind::METHOD,
+        index::SymbolKind::Event => SymbolKind::EVENT,
+        index::SymbolKind::Error => SymbolKind::STRUCT,
+        index::SymbolKind::Label => SymbolKind::KEY,
+        index::SymbolKind::MacroArg => SymbolKind::VARIABLE,
+    }
+}
+
+/// Outline of the document, labels are nested under their macro.
+pub fn document_symbols(doc: &Document) -> Option<Vec<DocumentSymbol>> {
+    let root = parse(&doc.text).ok()?;
+    let cst = Cst::parse(&doc.text).ok()?;
+
+    let symbols = root
+        .definitions()
+        .map(|def| {
+            let children = match def {
+                Definition::Macro(m) | Definition::Test(Test { r#macro: m, .. }) => Some(
+                    m.body
+                        .iter()
+                        .filter_map(|stmt| match stmt {
+                            MacroStatement::LabelDefinition(label) => Some(symbol(
+                                label.ident(),
+                                index::SymbolKind::Label,
+                                doc.range(label.1),
+                                doc.range(label.1),
+                                None,
+                            )),
+                            _ => None,
+                        })
+                        .collect(),
+                ),
+                _ => None,
+            };
+            symbol(
+                def.ident(),
+                index::SymbolKind::of(def),
+                doc.range(cst.node_span(cst.node_for(def))),
+                doc.range(def.span()),
+                children,
+            )
+        })
+        .collect();
+    Some(symbols)
+}
+
+#[allow(deprecated)]
+fn symbol(
+    name: &str,
+    kind: index::SymbolKind,
+    range: lsp_types::Range,
+    selection_range: lsp_types::Range,
+    children: Option<Vec<DocumentSymbol>>,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        name: name.to_owned(),
+        detail: None,
+        kind: lsp_kind(kind),
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range,
+        children,
+    }
+}
+
+/// Top-level definitions of `doc` whose name contains `query`, case insensitively.
+#[allow(deprecated)]
+pub fn workspace_symbols(uri: &Url, doc: &Document, query: &str) -> Vec<SymbolInformation> {
+    let Ok(root) = parse(&doc.text) else {
+        return vec![];
+    };
+    let query = query.to_lowercase();
+
+    root.definitions()
+        .filter(|def| def.ident().to_lowercase().contains(&query))
+        .map(|def| SymbolInformation {
+            name: def.ident().to_owned(),
+            kind: lsp_kind(index::SymbolKind::of(def)),
+            tags: None,
+            deprecated: None,
+            location: Location::new(uri.clone(), doc.range(def.span())),
+            container_name: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outlines_document() {
+        let doc = Document::new(
+            "#define constant C = 0x1\n#define macro MAIN() = {\n    a:\n    b:\n}".to_string(),
+            0,
+        );
+        let symbols = document_symbols(&doc).unwrap();
+
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols[0].kind, SymbolKind::CONSTANT);
+        assert_eq!(symbols[1].name, "MAIN");
+        assert_eq!(symbols[1].range.start.line, 1);
+        assert_eq!(symbols[1].range.end.line, 4);
+        let labels: Vec<_> = symbols[1]
+            .children
+            .iter()
+            .flatten()
+            .map(|label| label.name.as_str())
+            .collect();
+        assert_eq!(labels, ["a", "b"]);
+    }
+}